@@ -11,11 +11,20 @@ window.parseImage = async(src) => {
 }
 
 rust.then(async(func)=>{
-    await func.init();
-    const callback = ()=>{
-        func.prepare();
+    const viewers = [
+        await func.init("canvas", "squidgame.png"),
+        await func.init("canvas2", "squidgame.png"),
+    ];
+    let last = performance.now();
+    const callback = (now)=>{
+        const dt = (now - last) / 1000;
+        last = now;
+        for (const viewer of viewers) {
+            viewer.update(dt);
+            viewer.prepare();
 
-        func.draw();
+            viewer.draw();
+        }
         requestAnimationFrame(callback);
     }
     requestAnimationFrame(callback);
@@ -2,10 +2,17 @@
 
 precision highp float;
 
-out vec4 outColor;
+uniform vec4 color;
+// dash, gap and offset in pixels, pixels in a unit of the mesh
+uniform vec4 dash;
+
+in float dashDistance;
 
-const vec3 lineColor = vec3(1,0,0);
+out vec4 outColor;
 
 void main() {
-    outColor = vec4(lineColor,1.0);
+    if (dash.y > 0.0 && mod(dashDistance - dash.z, dash.x + dash.y) >= dash.x) {
+        discard;
+    }
+    outColor = color;
 }
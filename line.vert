@@ -3,11 +3,45 @@
 uniform mat4 transformationMatrix;
 uniform mat4 viewMatrix;
 uniform mat4 projectionMatrix;
+// canvas width and height, line width, all in pixels
+uniform vec4 stroke;
+// dash, gap and offset in pixels, pixels in a unit of the mesh
+uniform vec4 dash;
 
+// z is the side of the line the vertex is pushed to, 0 for plain lines
 layout(location = 0) in vec3 position;
+// previous and next vertex of the line
+layout(location = 3) in vec4 neighbours;
+// distance along the line, in mesh units
+layout(location = 4) in float distance;
+
+out float dashDistance;
+
+// longest miter, in half line widths
+const float miterLimit = 4.0;
+
+vec2 toScreen(vec4 clip) { return clip.xy / clip.w * stroke.xy * 0.5; }
 
 void main() {
-  vec4 worldPosition = vec4(position, 1.0);
-  gl_Position =
-      projectionMatrix * viewMatrix * transformationMatrix * worldPosition;
+  mat4 mvp = projectionMatrix * viewMatrix * transformationMatrix;
+  vec4 clip = mvp * vec4(position.xy, 0.0, 1.0);
+  gl_Position = clip;
+  dashDistance = distance * dash.w;
+  if (position.z == 0.0) {
+    return;
+  }
+  vec2 current = toScreen(clip);
+  vec2 previous = toScreen(mvp * vec4(neighbours.xy, 0.0, 1.0));
+  vec2 next = toScreen(mvp * vec4(neighbours.zw, 0.0, 1.0));
+  vec2 incoming = normalize(current - previous);
+  vec2 outgoing = normalize(next - current);
+  vec2 normal = vec2(-incoming.y, incoming.x);
+  vec2 tangent = incoming + outgoing;
+  // a line turning back on itself has no miter
+  vec2 miter = length(tangent) < 1e-4
+      ? normal
+      : vec2(-normalize(tangent).y, normalize(tangent).x);
+  float miterLength = min(1.0 / max(dot(miter, normal), 1e-4), miterLimit);
+  vec2 offset = miter * miterLength * stroke.z * 0.5 * position.z;
+  gl_Position.xy += offset / (stroke.xy * 0.5) * clip.w;
 }
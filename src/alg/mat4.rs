@@ -8,13 +8,9 @@ pub struct Mat4(pub [f32; 16]);
 impl fmt::Display for Mat4 {
     #[allow(dead_code)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {}, {}, {})\n", self[0], self[1], self[2], self[3])?;
-        write!(f, "({}, {}, {}, {})\n", self[4], self[5], self[6], self[7])?;
-        write!(
-            f,
-            "({}, {}, {}, {})\n",
-            self[8], self[9], self[10], self[11]
-        )?;
+        writeln!(f, "({}, {}, {}, {})", self[0], self[1], self[2], self[3])?;
+        writeln!(f, "({}, {}, {}, {})", self[4], self[5], self[6], self[7])?;
+        writeln!(f, "({}, {}, {}, {})", self[8], self[9], self[10], self[11])?;
         write!(
             f,
             "({}, {}, {}, {})",
@@ -41,7 +37,7 @@ impl Mat4 {
     }
     #[allow(dead_code)]
     pub fn transpose(&self) -> Self {
-        let mut m = self.clone();
+        let mut m = *self;
         m[4] = m[1];
         m[8] = m[2];
         m[12] = m[3];
@@ -108,14 +104,14 @@ impl Mat4 {
     }
     fn get_row_indexes_removing_row(row: usize) -> (usize, usize, usize) {
         let mut mi: [usize; 3] = [0, 0, 0];
-        for i in 0..3 {
+        for (i, m) in mi.iter_mut().enumerate() {
             let increment = if i < row { 0 } else { 1 };
-            mi[i] = i + increment;
+            *m = i + increment;
         }
         (mi[0], mi[1], mi[2])
     }
     #[allow(dead_code)]
-    pub fn get<'a>(&'a self, i: usize, j: usize) -> &'a f32 {
+    pub fn get(&self, i: usize, j: usize) -> &f32 {
         &self[i + j * 4]
     }
     #[allow(dead_code)]
@@ -155,10 +151,10 @@ impl Mat4 {
         }
         let (i0, i1, i2) = Self::get_row_indexes_removing_row(_i);
         let (j0, j1, j2) = Self::get_row_indexes_removing_row(_j);
-        let sign = if (_i + _j) % 2 == 0 {
-            1 as f32
+        let sign = if (_i + _j).is_multiple_of(2) {
+            1_f32
         } else {
-            -1 as f32
+            -1_f32
         };
         let mut determinant = 0 as f32;
         determinant += self.get(i0, j0) * self.get(i1, j1) * self.get(i2, j2);
@@ -182,12 +178,12 @@ impl Mat4 {
     pub fn div(&self, k: f32) -> Vec4 {
         Vec4([self[0] / k, self[1] / k, self[2] / k, self[3] / k])
     }
-    pub fn data<'a>(&'a self) -> &'a [f32] {
+    pub fn data(&self) -> &[f32] {
         &self.0[..]
     }
     #[allow(dead_code)]
     pub fn determinant(&self) -> f32 {
-        let det = self[0] * self[5] * self[10] * self[15] - self[0] * self[5] * self[11] * self[14]
+        self[0] * self[5] * self[10] * self[15] - self[0] * self[5] * self[11] * self[14]
             + self[0] * self[6] * self[11] * self[13]
             - self[0] * self[6] * self[9] * self[15]
             + self[0] * self[7] * self[9] * self[14]
@@ -196,33 +192,32 @@ impl Mat4 {
             + self[1] * self[4] * self[11] * self[14]
             + self[1] * self[6] * self[8] * self[15]
             - self[1] * self[6] * self[12] * self[11]
-            + self[1] * self[7] * self[8] * self[14]
-            - self[1] * self[7] * self[10] * self[12]
+            - self[1] * self[7] * self[8] * self[14]
+            + self[1] * self[7] * self[10] * self[12]
             + self[2] * self[7] * self[8] * self[13]
             - self[2] * self[7] * self[9] * self[12]
-            + self[2] * self[4] * self[9] * self[14]
-            - self[2] * self[4] * self[10] * self[13]
-            + self[2] * self[5] * self[10] * self[12]
-            - self[2] * self[5] * self[8] * self[14]
+            + self[2] * self[4] * self[9] * self[15]
+            - self[2] * self[4] * self[11] * self[13]
+            + self[2] * self[5] * self[11] * self[12]
+            - self[2] * self[5] * self[8] * self[15]
             - self[3] * self[4] * self[9] * self[14]
             + self[3] * self[4] * self[10] * self[13]
             + self[3] * self[5] * self[8] * self[14]
             - self[3] * self[5] * self[10] * self[12]
             - self[3] * self[6] * self[8] * self[13]
-            + self[3] * self[6] * self[9] * self[12];
-        det
+            + self[3] * self[6] * self[9] * self[12]
     }
 }
 
 impl Index<usize> for Mat4 {
     type Output = f32;
-    fn index<'a>(&'a self, i: usize) -> &'a f32 {
+    fn index(&self, i: usize) -> &f32 {
         &self.0[i]
     }
 }
 
 impl IndexMut<usize> for Mat4 {
-    fn index_mut<'a>(&'a mut self, i: usize) -> &'a mut f32 {
+    fn index_mut(&mut self, i: usize) -> &mut f32 {
         &mut self.0[i]
     }
 }
@@ -313,3 +308,15 @@ fn test() {
     let (a, b, c) = Mat4::get_row_indexes_removing_row(3);
     println!("{} {} {}", a, b, c);
 }
+
+#[test]
+fn inverses_undo_turned_matrices() {
+    let position = super::Vec4([-190.0, 5.0, 145.0, 1.0]);
+    let view = super::utils::view_matrix(&position, 20.0, 310.0, 0.0);
+    assert!((view.determinant() - 1.0).abs() < 1e-4);
+    let identity = view * view.inverse().unwrap();
+    for i in 0..16 {
+        let expected = if i % 5 == 0 { 1.0 } else { 0.0 };
+        assert!((identity[i] - expected).abs() < 1e-3, "{}", identity);
+    }
+}
@@ -5,3 +5,6 @@ mod mat4;
 pub use mat4::*;
 
 pub mod utils;
+
+mod polygon;
+pub use polygon::*;
@@ -0,0 +1,257 @@
+/// Simple polygon in the xy plane, the vertices are kept in the order they
+/// were given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<(f32, f32)>,
+}
+
+fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn on_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> bool {
+    p.0 >= a.0.min(b.0) && p.0 <= a.0.max(b.0) && p.1 >= a.1.min(b.1) && p.1 <= a.1.max(b.1)
+}
+
+fn segments_intersect(a: (f32, f32), b: (f32, f32), c: (f32, f32), d: (f32, f32)) -> bool {
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && on_segment(a, c, d))
+        || (d2 == 0.0 && on_segment(b, c, d))
+        || (d3 == 0.0 && on_segment(c, a, b))
+        || (d4 == 0.0 && on_segment(d, a, b))
+}
+
+fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+impl Polygon {
+    /// Fails if there are less than three vertices, if one of them is not
+    /// finite, if the polygon has no area or if its edges cross each other.
+    pub fn new(vertices: Vec<(f32, f32)>) -> Result<Self, String> {
+        if vertices.len() < 3 {
+            return Err("a polygon needs at least three vertices".into());
+        }
+        // NaN would slip through the area and crossing tests below
+        if !vertices
+            .iter()
+            .all(|&(x, y)| x.is_finite() && y.is_finite())
+        {
+            return Err("the vertices of the polygon must be finite".into());
+        }
+        let polygon = Polygon { vertices };
+        if polygon.signed_area() == 0.0 {
+            return Err("the polygon has no area".into());
+        }
+        if !polygon.is_simple() {
+            return Err("the edges of the polygon intersect".into());
+        }
+        Ok(polygon)
+    }
+    /// Rectangle with the down left corner in `(x, y)`, counterclockwise.
+    pub fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Polygon {
+            vertices: vec![
+                (x, y),
+                (x + width, y),
+                (x + width, y + height),
+                (x, y + height),
+            ],
+        }
+    }
+    pub fn get_vertices(&self) -> &[(f32, f32)] {
+        &self.vertices
+    }
+    fn edge(&self, i: usize) -> ((f32, f32), (f32, f32)) {
+        let n = self.vertices.len();
+        (self.vertices[i], self.vertices[(i + 1) % n])
+    }
+    /// Positive when the vertices are counterclockwise.
+    pub fn signed_area(&self) -> f32 {
+        (0..self.vertices.len())
+            .map(|i| {
+                let (a, b) = self.edge(i);
+                a.0 * b.1 - b.0 * a.1
+            })
+            .sum::<f32>()
+            / 2.0
+    }
+    fn is_simple(&self) -> bool {
+        let n = self.vertices.len();
+        for i in 0..n {
+            for j in i + 1..n {
+                // adjacent edges share a vertex
+                if j == i + 1 || (i == 0 && j == n - 1) {
+                    continue;
+                }
+                let (a, b) = self.edge(i);
+                let (c, d) = self.edge(j);
+                if segments_intersect(a, b, c, d) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    /// Returns the down left and the up right corners.
+    pub fn bounding_box(&self) -> ((f32, f32), (f32, f32)) {
+        let mut min = self.vertices[0];
+        let mut max = self.vertices[0];
+        for &(x, y) in &self.vertices {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        (min, max)
+    }
+    pub fn translate(&self, dx: f32, dy: f32) -> Self {
+        self.map(|x, y| (x + dx, y + dy))
+    }
+    /// Moves every vertex with `f`, which must be an invertible affine map so
+    /// that the polygon stays simple.
+    pub fn map(&self, f: impl Fn(f32, f32) -> (f32, f32)) -> Self {
+        Polygon {
+            vertices: self.vertices.iter().map(|&(x, y)| f(x, y)).collect(),
+        }
+    }
+    /// Even-odd rule, by casting a ray towards positive x.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let mut inside = false;
+        for i in 0..self.vertices.len() {
+            let (a, b) = self.edge(i);
+            if (a.1 > y) != (b.1 > y) {
+                let cross_x = a.0 + (y - a.1) / (b.1 - a.1) * (b.0 - a.0);
+                if x < cross_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+    /// Splits the polygon in triangles by ear clipping. The indices refer to
+    /// the vertices and every triangle is counterclockwise.
+    pub fn triangulate(&self) -> Vec<u32> {
+        let mut remaining: Vec<usize> = (0..self.vertices.len()).collect();
+        if self.signed_area() < 0.0 {
+            remaining.reverse();
+        }
+        let mut indices = Vec::with_capacity((self.vertices.len() - 2) * 3);
+        while remaining.len() > 3 {
+            let n = remaining.len();
+            let ear = (0..n).find(|&i| {
+                let (prev, current, next) = (
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                );
+                let (a, b, c) = (
+                    self.vertices[prev],
+                    self.vertices[current],
+                    self.vertices[next],
+                );
+                let turn = cross(a, b, c);
+                if turn == 0.0 {
+                    return true;
+                }
+                turn > 0.0
+                    && remaining
+                        .iter()
+                        .filter(|&&j| j != prev && j != current && j != next)
+                        .all(|&j| {
+                            let p = self.vertices[j];
+                            p == a || p == b || p == c || !in_triangle(p, a, b, c)
+                        })
+            });
+            // only rounding errors leave no ear, the rest is closed as a fan
+            let i = match ear {
+                Some(i) => i,
+                None => break,
+            };
+            let (prev, current, next) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let (a, b, c) = (
+                self.vertices[prev],
+                self.vertices[current],
+                self.vertices[next],
+            );
+            if cross(a, b, c) != 0.0 {
+                indices.extend_from_slice(&[prev as u32, current as u32, next as u32]);
+            }
+            remaining.remove(i);
+        }
+        for i in 1..remaining.len() - 1 {
+            indices.extend_from_slice(&[
+                remaining[0] as u32,
+                remaining[i] as u32,
+                remaining[i + 1] as u32,
+            ]);
+        }
+        indices
+    }
+}
+
+#[test]
+fn concave_polygon_contains_and_triangulation() {
+    // an L shape
+    let polygon = Polygon::new(vec![
+        (0.0, 0.0),
+        (2.0, 0.0),
+        (2.0, 1.0),
+        (1.0, 1.0),
+        (1.0, 2.0),
+        (0.0, 2.0),
+    ])
+    .unwrap();
+    assert_eq!(polygon.signed_area(), 3.0);
+    assert!(polygon.contains(0.5, 1.5));
+    assert!(polygon.contains(1.5, 0.5));
+    assert!(!polygon.contains(1.5, 1.5));
+    assert!(!polygon.contains(-0.5, 0.5));
+    assert_eq!(polygon.bounding_box(), ((0.0, 0.0), (2.0, 2.0)));
+
+    let indices = polygon.triangulate();
+    assert_eq!(indices.len(), 4 * 3);
+    let vertices = polygon.get_vertices();
+    let area: f32 = indices
+        .chunks(3)
+        .map(|t| {
+            let turn = cross(
+                vertices[t[0] as usize],
+                vertices[t[1] as usize],
+                vertices[t[2] as usize],
+            );
+            assert!(turn > 0.0);
+            turn / 2.0
+        })
+        .sum();
+    assert_eq!(area, 3.0);
+
+    // clockwise input gives the same covered area
+    let mut reversed = vertices.to_vec();
+    reversed.reverse();
+    let reversed = Polygon::new(reversed).unwrap();
+    assert_eq!(reversed.triangulate().len(), 4 * 3);
+}
+
+#[test]
+fn invalid_polygons_are_rejected() {
+    assert!(Polygon::new(vec![(0.0, 0.0), (1.0, 0.0)]).is_err());
+    assert!(Polygon::new(vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]).is_err());
+    // a bow tie crosses itself
+    let bow_tie = vec![(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)];
+    assert!(Polygon::new(bow_tie).is_err());
+    let square = |x: f32| vec![(-10.0, -10.0), (x, -10.0), (10.0, 10.0), (-10.0, 10.0)];
+    assert!(Polygon::new(square(f32::NAN)).is_err());
+    assert!(Polygon::new(square(f32::INFINITY)).is_err());
+    assert!(Polygon::new(square(10.0)).is_ok());
+}
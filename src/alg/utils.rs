@@ -41,6 +41,5 @@ pub fn perspective_matrix(aspect_ratio: f32, fov: f32, near: f32, far: f32) -> M
 pub fn view_matrix(position: &Vec4, pitch: f32, yaw: f32, _roll: f32) -> Mat4 {
     let m = Mat4::identity();
     let m = m.rotate_x(to_radians(pitch)).rotate_y(to_radians(yaw));
-    let m = m.translate(&-*position);
-    m
+    m.translate(&-*position)
 }
@@ -7,7 +7,7 @@ pub struct Vec4(pub [f32; 4]);
 impl fmt::Display for Vec4 {
     #[allow(dead_code)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "({}, {}, {}, {})\n", self[0], self[1], self[2], self[3])
+        writeln!(f, "({}, {}, {}, {})", self[0], self[1], self[2], self[3])
     }
 }
 
@@ -26,7 +26,7 @@ impl Vec4 {
         v
     }
     #[allow(dead_code)]
-    pub fn data<'a>(&'a self) -> &'a [f32] {
+    pub fn data(&self) -> &[f32] {
         &self.0[..]
     }
     #[allow(dead_code)]
@@ -53,13 +53,13 @@ impl Vec4 {
 
 impl Index<usize> for Vec4 {
     type Output = f32;
-    fn index<'a>(&'a self, i: usize) -> &'a f32 {
+    fn index(&self, i: usize) -> &f32 {
         &self.0[i]
     }
 }
 
 impl IndexMut<usize> for Vec4 {
-    fn index_mut<'a>(&'a mut self, i: usize) -> &'a mut f32 {
+    fn index_mut(&mut self, i: usize) -> &mut f32 {
         &mut self.0[i]
     }
 }
@@ -4,11 +4,20 @@ extern crate web_sys;
 mod alg;
 mod renderer;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
-use alg::{Mat4, Vec4};
-use renderer::Renderer;
-use renderer::{meshes, Element, Shader, ShaderProgram, StdUniforms, Texture, Vao};
+use alg::{Mat4, Polygon, Vec4};
+use renderer::formats::project;
+use renderer::{events, AttributeSpec, AttributeValue, ElementId, ElementKind, FrameState};
+use renderer::{meshes, Rectangle, Shader, ShaderProgram, StdUniforms, StrokeStyle, Texture, Vao};
+use renderer::{
+    parse_color, Easing, Font, LabelClass, MotionParams, RenderMode, Renderer, TextLabel,
+    TextSpace, Tool,
+};
+use std::cell::RefCell;
 use std::panic;
+use std::rc::Rc;
+use wasm_bindgen_futures::future_to_promise;
 
 #[wasm_bindgen]
 extern "C" {
@@ -20,28 +29,49 @@ extern "C" {
     fn error(s: &str);
 }
 
+/// A renderer bound to one canvas. Every viewer owns its own context,
+/// camera and scene, so several of them can live on the same page.
 #[wasm_bindgen]
-pub async fn init() -> Result<(), JsValue> {
+pub struct Viewer {
+    renderer: Rc<RefCell<Renderer>>,
+}
+
+#[wasm_bindgen]
+pub async fn init(canvas_id: String, image_name: String) -> Result<Viewer, JsValue> {
     panic::set_hook(Box::new(|panic_info| {
         error(&format!("panic occurred {:?}", panic_info));
     }));
-    let renderer = Renderer::get_instance();
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .get_element_by_id(&canvas_id)
+        .ok_or_else(|| JsValue::from_str(&format!("no element with id {}", canvas_id)))?;
+    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+    let mut renderer = Renderer::from_canvas(&canvas)?;
     renderer.camera.set_position(Vec4([0.0, 0.0, 1.0, 1.0]));
-    let context = renderer.get_context_instance();
-    context.line_width(5.0);
+    let backend = renderer.get_backend().clone();
 
     let program: ShaderProgram<StdUniforms> =
-        ShaderProgram::from_file_name("shader.vert", "shader.frag").await?;
+        ShaderProgram::from_file_name(&backend, "shader.vert", "shader.frag").await?;
     let program = Shader::new(program);
     renderer.set_std_program(program);
 
     let program: ShaderProgram<StdUniforms> =
-        ShaderProgram::from_file_name("line.vert", "line.frag").await?;
+        ShaderProgram::from_file_name(&backend, "line.vert", "line.frag").await?;
     let program = Shader::new(program);
     renderer.set_line_program(program);
-    renderer.create_plane("squidgame.png").await?;
 
-    Ok(())
+    let program: ShaderProgram<StdUniforms> =
+        ShaderProgram::from_file_name(&backend, "text.vert", "text.frag").await?;
+    renderer.set_text_program(Shader::new(program));
+
+    let texture = Texture::new(&backend, &image_name).await?;
+    renderer
+        .create_plane_from_texture(texture)
+        .map_err(|s| JsValue::from_str(&s))?;
+
+    let renderer = Rc::new(RefCell::new(renderer));
+    events::listen(&renderer, &canvas)?;
+    Ok(Viewer { renderer })
 }
 
 #[wasm_bindgen]
@@ -61,41 +91,630 @@ pub fn get_height(vao: &Vao) -> f32 {
     texture_coords.unwrap().texture.height
 }
 
-#[wasm_bindgen]
-pub async fn create_quad(image_name: String) -> Result<Vao, JsValue> {
-    let texture = Texture::new(&image_name).await?;
-    let mesh = meshes::textured_rectangle(texture)?;
-    Vao::new(mesh).map_err(|s| JsValue::from_str(&s))
-}
-#[wasm_bindgen]
-pub fn create_quad_line(width: f32, height: f32) -> Result<Vao, JsValue> {
-    let mesh = meshes::line_rectangle(width, height);
-    Vao::new(mesh).map_err(|s| JsValue::from_str(&s))
+fn polygon_from_coords(coords: &[f32]) -> Result<Polygon, JsValue> {
+    if !coords.len().is_multiple_of(2) {
+        return Err(JsValue::from_str("odd number of coordinates"));
+    }
+    let vertices = coords.chunks(2).map(|v| (v[0], v[1])).collect();
+    Polygon::new(vertices).map_err(|s| JsValue::from_str(&s))
 }
-#[wasm_bindgen]
-pub fn prepare() {
-    let renderer = Renderer::get_instance();
-    renderer.prepare();
+
+fn get_easing(name: Option<String>) -> Result<Easing, JsValue> {
+    name.map_or(Ok(Easing::EaseInOut), |name| {
+        Easing::from_name(&name).map_err(|s| JsValue::from_str(&s))
+    })
 }
 
-#[wasm_bindgen]
-pub fn draw_line(vao: &Vao, rotation: f32) -> Result<(), JsValue> {
-    let renderer = Renderer::get_instance();
-    let program = renderer.get_line_program();
-    program.use_program();
-    let mat = Mat4::identity();
-    // let mat = mat.rotate(&vec4::Vec4([0.0, 0.0, rotation, 1.0]));
-    // let mat = mat.scale(&vec4::Vec4([0.5, 0.5, 0.5, 1.0]));
-    program.load_tranformation_matrix(mat.data());
-    program.load_view_matrix(renderer.camera.get_view_matrix().data());
-    program.load_projection_matrix(renderer.get_projection_matrix().data());
-    vao.draw_line_loop();
-    Ok(())
+impl Viewer {
+    /// Runs an edit, then lets the change listener know about it once the
+    /// renderer is no longer borrowed.
+    fn edit<T>(&self, edit: impl FnOnce(&mut Renderer) -> Result<T, String>) -> Result<T, JsValue> {
+        let result = edit(&mut self.renderer.borrow_mut());
+        Renderer::notify_change(&self.renderer);
+        result.map_err(|s| JsValue::from_str(&s))
+    }
 }
 
 #[wasm_bindgen]
-pub fn draw() -> Result<(), JsValue> {
-    let renderer = renderer::Renderer::get_instance();
-    renderer.draw();
-    Ok(())
+impl Viewer {
+    pub fn create_plane(&self, image_name: String) -> js_sys::Promise {
+        let renderer = self.renderer.clone();
+        future_to_promise(async move {
+            let backend = renderer.borrow().get_backend().clone();
+            let texture = Texture::new(&backend, &image_name).await?;
+            let result = renderer.borrow_mut().create_plane_from_texture(texture);
+            Renderer::notify_change(&renderer);
+            result.map_err(|s| JsValue::from_str(&s))?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+    pub fn create_quad(&self, image_name: String) -> js_sys::Promise {
+        let backend = self.renderer.borrow().get_backend().clone();
+        future_to_promise(async move {
+            let texture = Texture::new(&backend, &image_name).await?;
+            let mesh = meshes::textured_rectangle(texture)?;
+            let vao = Vao::new(&backend, mesh).map_err(|s| JsValue::from_str(&s))?;
+            Ok(vao.into())
+        })
+    }
+    pub fn create_quad_line(&self, width: f32, height: f32) -> Result<Vao, JsValue> {
+        let renderer = self.renderer.borrow();
+        let mesh = meshes::stroke_rectangle(width, height);
+        Vao::new(renderer.get_backend(), mesh).map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn prepare(&self) {
+        self.renderer.borrow().prepare();
+    }
+
+    /// Draws a stroke made by `create_quad_line` as a solid frame.
+    pub fn draw_line(&self, vao: &Vao, _rotation: f32) -> Result<(), JsValue> {
+        let renderer = self.renderer.borrow();
+        let program = renderer.get_line_program();
+        program.use_program();
+        let mat = Mat4::identity();
+        program.load_tranformation_matrix(mat.data());
+        program.load_color(renderer::FRAME_COLOR);
+        program.load_view_matrix(renderer.camera.get_view_matrix().data());
+        program.load_projection_matrix(renderer.get_projection_matrix().data());
+        let line_width = renderer.get_line_width();
+        let backend = renderer.get_backend();
+        program.load_stroke(backend.drawing_buffer_size(), line_width);
+        program.load_dash(StrokeStyle::Solid.get_dash(line_width, 0.0), 1.0);
+        vao.draw();
+        Ok(())
+    }
+
+    pub fn get_plane(&self) -> Option<u32> {
+        self.renderer
+            .borrow()
+            .get_scene()
+            .get_plane()
+            .map(|id| id.0)
+    }
+    /// Adds an area to the plane. `x` and `y` are the down left corner in
+    /// world coordinates, with the origin at the center of the plane.
+    pub fn add_area(&self, x: f32, y: f32, width: f32, height: f32) -> Result<u32, JsValue> {
+        self.edit(|renderer| renderer.create_area(Polygon::rectangle(x, y, width, height)))
+            .map(|id| id.0)
+    }
+    /// Adds an area outlined by a simple polygon, given as the flat list of
+    /// its vertices `[x0, y0, x1, y1, ...]` in world coordinates.
+    pub fn add_polygon_area(&self, vertices: Vec<f32>) -> Result<u32, JsValue> {
+        let polygon = polygon_from_coords(&vertices)?;
+        self.edit(|renderer| renderer.create_area(polygon))
+            .map(|id| id.0)
+    }
+    /// Vertices of an area as `[x0, y0, x1, y1, ...]`.
+    pub fn get_polygon(&self, id: u32) -> Option<Vec<f32>> {
+        let renderer = self.renderer.borrow();
+        match renderer.get_scene().get(ElementId(id))?.kind {
+            ElementKind::AREA(ref area) => Some(
+                area.polygon
+                    .get_vertices()
+                    .iter()
+                    .flat_map(|&(x, y)| vec![x, y])
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+    pub fn add_point(&self, area: u32, x: f32, y: f32, value: f32) -> Result<u32, JsValue> {
+        self.edit(|renderer| renderer.create_point(ElementId(area), x, y, value))
+            .map(|id| id.0)
+    }
+    /// Removes an area or a point. Removing an area removes its points too.
+    pub fn remove_element(&self, id: u32) -> bool {
+        let removed = self.renderer.borrow_mut().remove_element(ElementId(id));
+        Renderer::notify_change(&self.renderer);
+        removed
+    }
+    /// Moves an area, with its points, or a point by `(dx, dy)` world units.
+    pub fn move_element(&self, id: u32, dx: f32, dy: f32) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.move_element(ElementId(id), dx, dy))
+    }
+    /// Gives an area a new outline, as `[x0, y0, x1, y1, ...]`.
+    pub fn set_polygon(&self, id: u32, vertices: Vec<f32>) -> Result<(), JsValue> {
+        let polygon = polygon_from_coords(&vertices)?;
+        self.edit(|renderer| renderer.set_polygon(ElementId(id), polygon))
+    }
+    /// Edits made until `end_edit_group`, like the steps of a drag, are
+    /// undone in one step.
+    pub fn begin_edit_group(&self) {
+        self.renderer.borrow_mut().begin_edit_group();
+    }
+    pub fn end_edit_group(&self) {
+        self.renderer.borrow_mut().end_edit_group();
+    }
+    pub fn undo(&self) -> Result<bool, JsValue> {
+        self.edit(|renderer| renderer.undo())
+    }
+    pub fn redo(&self) -> Result<bool, JsValue> {
+        self.edit(|renderer| renderer.redo())
+    }
+    pub fn can_undo(&self) -> bool {
+        self.renderer.borrow().can_undo()
+    }
+    pub fn can_redo(&self) -> bool {
+        self.renderer.borrow().can_redo()
+    }
+    /// `callback` is called with no arguments after each change to the
+    /// areas and points.
+    pub fn on_change(&self, callback: js_sys::Function) {
+        let listener = move || {
+            if let Err(e) = callback.call0(&JsValue::NULL) {
+                error(&format!("change listener failed {:?}", e));
+            }
+        };
+        self.renderer
+            .borrow_mut()
+            .set_change_listener(Rc::new(listener));
+    }
+    pub fn get_areas(&self) -> Vec<u32> {
+        let renderer = self.renderer.borrow();
+        renderer
+            .get_scene()
+            .get_areas()
+            .iter()
+            .map(|id| id.0)
+            .collect()
+    }
+    pub fn get_points(&self, area: u32) -> Vec<u32> {
+        let renderer = self.renderer.borrow();
+        let points = renderer.get_scene().get_points(ElementId(area));
+        points.iter().map(|id| id.0).collect()
+    }
+    /// Bounds of an element as `[x, y, width, height]`.
+    pub fn get_rectangle(&self, id: u32) -> Option<Vec<f32>> {
+        let renderer = self.renderer.borrow();
+        let rect = renderer.get_scene().get(ElementId(id))?.rect;
+        let (x, y) = rect.down_left_corner;
+        let (width, height) = rect.dimensions;
+        Some(vec![x, y, width, height])
+    }
+    /// Position and value of a point as `[x, y, value]`.
+    pub fn get_point(&self, id: u32) -> Option<Vec<f32>> {
+        let renderer = self.renderer.borrow();
+        let element = renderer.get_scene().get(ElementId(id))?;
+        match element.kind {
+            ElementKind::POINT(ref point) => {
+                let (x, y) = element.rect.get_center();
+                Some(vec![x, y, point.value])
+            }
+            _ => None,
+        }
+    }
+    pub fn set_point_value(&self, id: u32, value: f32) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.set_point_value(ElementId(id), value))
+    }
+    /// Annotations of the plane as a COCO JSON document, in image pixels.
+    pub fn export_coco(&self) -> Result<String, JsValue> {
+        self.renderer
+            .borrow()
+            .export_coco()
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Replaces the areas of the plane with the ones of a COCO JSON document.
+    pub fn import_coco(&self, json: &str) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.import_coco(json))
+    }
+    /// Annotations of the plane as a Pascal VOC XML document.
+    pub fn export_voc(&self) -> Result<String, JsValue> {
+        self.renderer
+            .borrow()
+            .export_voc()
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn import_voc(&self, xml: &str) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.import_voc(xml))
+    }
+    /// Annotations of the plane in the YOLO text format, one box per line.
+    pub fn export_yolo(&self) -> Result<String, JsValue> {
+        self.renderer
+            .borrow()
+            .export_yolo()
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn import_yolo(&self, text: &str) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.import_yolo(text))
+    }
+    /// Saves the plane, its annotations and the camera as a JSON document.
+    pub fn save_project(&self) -> Result<String, JsValue> {
+        self.renderer
+            .borrow()
+            .save_project()
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Restores a document written by `save_project`, loading the image it
+    /// refers to. Documents written by older versions are upgraded.
+    pub fn load_project(&self, json: String) -> js_sys::Promise {
+        let renderer = self.renderer.clone();
+        future_to_promise(async move {
+            let project = project::from_str(&json).map_err(|s| JsValue::from_str(&s))?;
+            let backend = renderer.borrow().get_backend().clone();
+            let texture = Texture::new(&backend, &project.image.file_name).await?;
+            let result = renderer.borrow_mut().load_project(&project, texture);
+            Renderer::notify_change(&renderer);
+            result.map_err(|s| JsValue::from_str(&s))?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+    /// Loads a signed distance field font: the atlas image and its metrics,
+    /// the JSON written by BMFont tools like `msdf-bmfont-xml`.
+    pub fn load_font(&self, atlas_name: String, metrics: String) -> js_sys::Promise {
+        let renderer = self.renderer.clone();
+        future_to_promise(async move {
+            let backend = renderer.borrow().get_backend().clone();
+            let atlas = Texture::new(&backend, &atlas_name).await?;
+            let font = Font::from_json(&metrics, atlas).map_err(|s| JsValue::from_str(&s))?;
+            renderer
+                .borrow_mut()
+                .set_font(font)
+                .map_err(|s| JsValue::from_str(&s))?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+    /// Places a text label and returns its id. In world space `(x, y)` and
+    /// `size` are in world units and the label zooms with the plane, else
+    /// they are in canvas pixels. `(x, y)` is the start of the baseline.
+    pub fn add_text(
+        &self,
+        text: String,
+        x: f32,
+        y: f32,
+        size: f32,
+        world: bool,
+        color: &str,
+    ) -> Result<u32, JsValue> {
+        let label = TextLabel {
+            text,
+            x,
+            y,
+            size,
+            space: if world {
+                TextSpace::World
+            } else {
+                TextSpace::Screen
+            },
+            color: parse_color(color).map_err(|s| JsValue::from_str(&s))?,
+        };
+        self.renderer
+            .borrow_mut()
+            .add_text(label)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn remove_text(&self, id: u32) -> bool {
+        self.renderer.borrow_mut().remove_text(id)
+    }
+    /// Whether class names and point values are drawn next to the
+    /// annotations.
+    pub fn get_captions(&self) -> bool {
+        self.renderer.borrow().get_captions()
+    }
+    pub fn set_captions(&self, captions: bool) {
+        self.renderer.borrow_mut().set_captions(captions);
+    }
+    /// Width of the frames in canvas pixels, it does not change with the
+    /// zoom.
+    pub fn get_line_width(&self) -> f32 {
+        self.renderer.borrow().get_line_width()
+    }
+    pub fn set_line_width(&self, width: f32) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_line_width(width)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Outline style of the frames in a state, "normal", "hovered",
+    /// "selected" or "locked", as JSON: `{ "type": "solid" }`,
+    /// `{ "type": "dashed", dash, gap, speed }` or `{ "type": "dotted", gap }`
+    /// with lengths in pixels and the speed of the dashes in pixels per
+    /// second.
+    pub fn get_stroke_style(&self, state: &str) -> Result<String, JsValue> {
+        let state = FrameState::from_name(state).map_err(|e| JsValue::from_str(&e))?;
+        serde_json::to_string(&self.renderer.borrow().get_stroke_style(state))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+    pub fn set_stroke_style(&self, state: &str, json: &str) -> Result<(), JsValue> {
+        let state = FrameState::from_name(state).map_err(|e| JsValue::from_str(&e))?;
+        let style: StrokeStyle =
+            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.renderer
+            .borrow_mut()
+            .set_stroke_style(state, style)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn is_locked(&self, id: u32) -> bool {
+        self.renderer.borrow().is_locked(ElementId(id))
+    }
+    /// Locked elements are not moved by drags or group moves.
+    pub fn set_locked(&self, id: u32, locked: bool) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_locked(ElementId(id), locked)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Fill of an area as `[r, g, b, opacity]`, none when it takes the color
+    /// of its label.
+    pub fn get_fill(&self, id: u32) -> Option<Vec<f32>> {
+        let fill = self.renderer.borrow().get_fill(ElementId(id));
+        fill.map(|fill| fill.to_vec())
+    }
+    /// Tints an area with a `#rrggbb` color, no color goes back to the color
+    /// of its label.
+    pub fn set_fill(&self, id: u32, color: Option<String>, opacity: f32) -> Result<(), JsValue> {
+        let fill = match color {
+            Some(color) => {
+                let [r, g, b, _] = parse_color(&color).map_err(|e| JsValue::from_str(&e))?;
+                Some([r, g, b, opacity])
+            }
+            None => None,
+        };
+        self.edit(|renderer| renderer.set_fill(ElementId(id), fill))
+    }
+    /// Opacity of the area fills between 0 and 1, 0 hides them.
+    pub fn get_overlay_opacity(&self) -> f32 {
+        self.renderer.borrow().get_overlay_opacity()
+    }
+    pub fn set_overlay_opacity(&self, opacity: f32) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_overlay_opacity(opacity)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Advances the animations by `dt` seconds, once a frame.
+    pub fn update(&self, dt: f32) {
+        self.renderer.borrow_mut().update(dt);
+    }
+    pub fn get_hover(&self) -> Option<u32> {
+        self.renderer.borrow().get_hover().map(|id| id.0)
+    }
+    pub fn get_selected(&self) -> Option<u32> {
+        self.renderer.borrow().get_selected().map(|id| id.0)
+    }
+    pub fn set_selected(&self, id: Option<u32>) {
+        self.renderer.borrow_mut().set_selected(id.map(ElementId));
+    }
+    pub fn get_selection(&self) -> Vec<u32> {
+        let renderer = self.renderer.borrow();
+        renderer.get_selection().iter().map(|id| id.0).collect()
+    }
+    pub fn set_selection(&self, ids: Vec<u32>) {
+        let ids: Vec<ElementId> = ids.into_iter().map(ElementId).collect();
+        self.renderer.borrow_mut().set_selection(&ids);
+    }
+    pub fn select_all(&self) {
+        self.renderer.borrow_mut().select_all();
+    }
+    /// Removes the selected elements, undone in one step.
+    pub fn remove_selection(&self) -> bool {
+        let removed = self.renderer.borrow_mut().remove_selection();
+        Renderer::notify_change(&self.renderer);
+        removed
+    }
+    pub fn move_selection(&self, dx: f32, dy: f32) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.move_selection(dx, dy))
+    }
+    /// Gives the same value to every selected point.
+    pub fn set_selection_value(&self, value: f32) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.set_selection_value(value))
+    }
+    /// Label classes as a JSON array of
+    /// `{ id, name, color, hotkey, attributes }`.
+    pub fn get_labels(&self) -> Result<String, JsValue> {
+        serde_json::to_string(self.renderer.borrow().get_labels())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+    /// Adds a label class, or replaces the one with the same id. `color` is
+    /// `#rrggbb`, `hotkey` assigns the class to the selection. A replaced
+    /// class keeps its attributes.
+    pub fn define_label(
+        &self,
+        id: u32,
+        name: String,
+        color: String,
+        hotkey: Option<String>,
+    ) -> Result<(), JsValue> {
+        self.edit(|renderer| {
+            let attributes = renderer
+                .get_labels()
+                .iter()
+                .find(|class| class.id == id)
+                .map(|class| class.attributes.clone())
+                .unwrap_or_default();
+            renderer.define_label(LabelClass {
+                id,
+                name,
+                color,
+                hotkey,
+                attributes,
+            })
+        })
+    }
+    /// Sets the attributes of a label class from a JSON array of
+    /// `{ name, type }`, where type is "bool", "number", "string" or "enum"
+    /// with `options`.
+    pub fn set_label_attributes(&self, id: u32, json: &str) -> Result<(), JsValue> {
+        let specs: Vec<AttributeSpec> =
+            serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.edit(|renderer| {
+            let mut class = renderer
+                .get_labels()
+                .iter()
+                .find(|class| class.id == id)
+                .cloned()
+                .ok_or_else(|| format!("no label with id {}", id))?;
+            class.attributes = specs;
+            renderer.define_label(class)
+        })
+    }
+    /// Attributes of an element as a JSON object.
+    pub fn get_attributes(&self, id: u32) -> Option<String> {
+        let renderer = self.renderer.borrow();
+        let attributes = renderer.get_attributes(ElementId(id))?;
+        serde_json::to_string(attributes).ok()
+    }
+    pub fn get_attribute(&self, id: u32, name: &str) -> JsValue {
+        let renderer = self.renderer.borrow();
+        match renderer
+            .get_attributes(ElementId(id))
+            .and_then(|attributes| attributes.get(name))
+        {
+            Some(AttributeValue::Bool(value)) => JsValue::from_bool(*value),
+            Some(AttributeValue::Number(value)) => JsValue::from_f64(*value as f64),
+            Some(AttributeValue::String(value)) => JsValue::from_str(value),
+            None => JsValue::UNDEFINED,
+        }
+    }
+    /// Sets an attribute of a labeled element, `null` or `undefined` removes
+    /// it. The value must match the schema of the label class.
+    pub fn set_attribute(&self, id: u32, name: &str, value: JsValue) -> Result<(), JsValue> {
+        let value = if value.is_null() || value.is_undefined() {
+            None
+        } else if let Some(value) = value.as_bool() {
+            Some(AttributeValue::Bool(value))
+        } else if let Some(value) = value.as_f64() {
+            Some(AttributeValue::Number(value as f32))
+        } else if let Some(value) = value.as_string() {
+            Some(AttributeValue::String(value))
+        } else {
+            return Err(JsValue::from_str(
+                "expected a boolean, a number or a string",
+            ));
+        };
+        self.edit(|renderer| renderer.set_attribute(ElementId(id), name, value))
+    }
+    /// Removes a label class no element uses.
+    pub fn remove_label(&self, id: u32) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.remove_label(id))
+    }
+    pub fn get_label(&self, id: u32) -> Option<u32> {
+        self.renderer.borrow().get_label(ElementId(id))
+    }
+    /// Assigns a class to an area or a point, `undefined` clears it.
+    pub fn set_label(&self, id: u32, label: Option<u32>) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.set_label(ElementId(id), label))
+    }
+    pub fn set_selection_label(&self, label: Option<u32>) -> Result<(), JsValue> {
+        self.edit(|renderer| renderer.set_selection_label(label))
+    }
+    /// Class given to the elements drawn with the tools.
+    pub fn get_active_label(&self) -> Option<u32> {
+        self.renderer.borrow().get_active_label()
+    }
+    pub fn set_active_label(&self, label: Option<u32>) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_active_label(label)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+    pub fn get_dragged(&self) -> Option<u32> {
+        self.renderer.borrow().get_dragged().map(|id| id.0)
+    }
+    /// One of "select", "rectangle", "polygon" or "point".
+    pub fn set_tool(&self, name: &str) -> Result<(), JsValue> {
+        let tool = Tool::from_name(name).map_err(|e| JsValue::from_str(&e))?;
+        self.renderer.borrow_mut().set_tool(tool);
+        Ok(())
+    }
+    pub fn get_tool(&self) -> String {
+        self.renderer.borrow().get_tool().get_name().into()
+    }
+    /// "2d" looks straight down at the plane, "3d" uses a perspective camera
+    /// that ctrl + drag orbits. The switch keeps the plane where it was.
+    pub fn set_render_mode(&self, name: &str) -> Result<(), JsValue> {
+        let mode = RenderMode::from_name(name).map_err(|e| JsValue::from_str(&e))?;
+        self.renderer.borrow_mut().set_render_mode(mode);
+        Ok(())
+    }
+    pub fn get_render_mode(&self) -> String {
+        self.renderer.borrow().get_render_mode().get_name().into()
+    }
+    /// Closest and furthest the 3D camera gets to the point it orbits.
+    pub fn set_orbit_distance_limits(&self, min: f32, max: f32) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_orbit_distance_limits(min, max)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Range of the angle of the 3D camera from the y axis, in degrees.
+    pub fn set_orbit_polar_limits(&self, min: f32, max: f32) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_orbit_polar_limits(min, max)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// How the held keys move the view: `acceleration` and `max_speed` in
+    /// canvas pixels and seconds, `damping` the rate it slows down at.
+    pub fn set_camera_motion(
+        &self,
+        acceleration: f32,
+        max_speed: f32,
+        damping: f32,
+    ) -> Result<(), JsValue> {
+        let params = MotionParams {
+            acceleration,
+            max_speed,
+            damping,
+        };
+        self.renderer
+            .borrow_mut()
+            .set_motion_params(params)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn get_inertia(&self) -> bool {
+        self.renderer.borrow().get_inertia()
+    }
+    /// Whether the view keeps gliding after a right button pan.
+    pub fn set_inertia(&self, inertia: bool) {
+        self.renderer.borrow_mut().set_inertia(inertia);
+    }
+    /// Flies the camera to look at `(x, y)` with `zoom`, the half width of
+    /// the 2D view. `easing` is "linear", "ease-in", "ease-out" or the
+    /// default "ease-in-out". Any other camera control stops the flight.
+    pub fn fly_to(&self, x: f32, y: f32, zoom: f32, easing: Option<String>) -> Result<(), JsValue> {
+        let easing = get_easing(easing)?;
+        self.renderer
+            .borrow_mut()
+            .fly_to(x, y, zoom, easing)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Flies the camera to frame a rectangle of the plane, see `fly_to`.
+    pub fn fit_to(
+        &self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        easing: Option<String>,
+    ) -> Result<(), JsValue> {
+        let easing = get_easing(easing)?;
+        self.renderer
+            .borrow_mut()
+            .fit_to(&Rectangle::new(x, y, width, height), easing)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    /// Flies the camera to frame the plane, an area or a point.
+    pub fn fit_to_element(&self, id: u32, easing: Option<String>) -> Result<(), JsValue> {
+        let easing = get_easing(easing)?;
+        self.renderer
+            .borrow_mut()
+            .fit_to_element(ElementId(id), easing)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+    pub fn is_flying(&self) -> bool {
+        self.renderer.borrow().is_flying()
+    }
+    /// Narrowest and widest the 2D view gets, as half its width in plane
+    /// units.
+    pub fn set_zoom_limits(&self, min: f32, max: f32) -> Result<(), JsValue> {
+        self.renderer
+            .borrow_mut()
+            .set_zoom_limits(min, max)
+            .map_err(|s| JsValue::from_str(&s))
+    }
+
+    pub fn draw(&self) -> Result<(), JsValue> {
+        self.renderer.borrow().draw();
+        Ok(())
+    }
 }
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Number(f32),
+    /// Free text, or one of the options of an enum attribute.
+    String(String),
+}
+
+/// Attributes of an element by name.
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AttributeType {
+    Bool,
+    Number,
+    String,
+    Enum { options: Vec<String> },
+}
+
+/// An attribute the elements of a label class can have.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AttributeSpec {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: AttributeType,
+}
+
+impl AttributeSpec {
+    pub fn check(&self, value: &AttributeValue) -> Result<(), String> {
+        let valid = match (&self.kind, value) {
+            (AttributeType::Bool, AttributeValue::Bool(_)) => true,
+            (AttributeType::Number, AttributeValue::Number(number)) => number.is_finite(),
+            (AttributeType::String, AttributeValue::String(_)) => true,
+            (AttributeType::Enum { options }, AttributeValue::String(option)) => {
+                options.contains(option)
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(format!("{:?} is not a valid {}", value, self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Checks the specs of a label class: names are unique and enums have
+/// options.
+pub fn check_schema(specs: &[AttributeSpec]) -> Result<(), String> {
+    for (i, spec) in specs.iter().enumerate() {
+        if spec.name.is_empty() {
+            return Err("an attribute needs a name".into());
+        }
+        if specs[..i].iter().any(|other| other.name == spec.name) {
+            return Err(format!("attribute {} is defined twice", spec.name));
+        }
+        if let AttributeType::Enum { options } = &spec.kind {
+            if options.is_empty() {
+                return Err(format!("enum {} has no options", spec.name));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn check(specs: &[AttributeSpec], name: &str, value: &AttributeValue) -> Result<(), String> {
+    specs
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| format!("unknown attribute {}", name))?
+        .check(value)
+}
+
+/// The attributes that match `specs`, the others are dropped.
+pub fn retain_valid(specs: &[AttributeSpec], attributes: &Attributes) -> Attributes {
+    attributes
+        .iter()
+        .filter(|(name, value)| check(specs, name, value).is_ok())
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+#[test]
+fn values_are_checked_against_the_schema() {
+    let specs: Vec<AttributeSpec> = serde_json::from_str(
+        r#"[
+            { "name": "occluded", "type": "bool" },
+            { "name": "confidence", "type": "number" },
+            { "name": "note", "type": "string" },
+            { "name": "pose", "type": "enum", "options": ["front", "side"] }
+        ]"#,
+    )
+    .unwrap();
+    check_schema(&specs).unwrap();
+    let value = |json: &str| serde_json::from_str::<AttributeValue>(json).unwrap();
+    check(&specs, "occluded", &value("true")).unwrap();
+    check(&specs, "confidence", &value("0.5")).unwrap();
+    check(&specs, "note", &value("\"half hidden\"")).unwrap();
+    check(&specs, "pose", &value("\"side\"")).unwrap();
+    assert!(check(&specs, "pose", &value("\"back\"")).is_err());
+    assert!(check(&specs, "occluded", &value("1")).is_err());
+    assert!(check(&specs, "color", &value("true")).is_err());
+
+    let mut attributes = Attributes::new();
+    attributes.insert("occluded".into(), value("true"));
+    attributes.insert("pose".into(), value("\"back\""));
+    let valid = retain_valid(&specs, &attributes);
+    assert_eq!(valid.keys().collect::<Vec<_>>(), ["occluded"]);
+
+    let twice = [specs[0].clone(), specs[0].clone()];
+    assert!(check_schema(&twice).is_err());
+    let empty = AttributeSpec {
+        name: "pose".into(),
+        kind: AttributeType::Enum { options: vec![] },
+    };
+    assert!(check_schema(&[empty]).is_err());
+}
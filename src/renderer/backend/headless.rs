@@ -0,0 +1,278 @@
+use super::*;
+use std::cell::{Cell, RefCell};
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    CreateBuffer(BufferId),
+    DeleteBuffer(BufferId),
+    BindBuffer(BufferTarget, Option<BufferId>),
+    BufferDataF32(BufferTarget, Vec<f32>),
+    BufferDataU32(BufferTarget, Vec<u32>),
+    CreateVertexArray(VertexArrayId),
+    DeleteVertexArray(VertexArrayId),
+    BindVertexArray(Option<VertexArrayId>),
+    VertexAttribPointer { index: u32, size: i32 },
+    EnableVertexAttribArray(u32),
+    CreateShader(ShaderId, ShaderType),
+    CreateProgram(ProgramId, Vec<ShaderId>),
+    UseProgram(Option<ProgramId>),
+    GetUniformLocation(ProgramId, String, UniformLocation),
+    UniformMatrix4fv(Option<UniformLocation>, bool, Vec<f32>),
+    Uniform4fv(Option<UniformLocation>, Vec<f32>),
+    CreateTexture(TextureId),
+    DeleteTexture(TextureId),
+    ActiveTexture(u32),
+    BindTexture(Option<TextureId>),
+    TexImage { width: u32, height: u32 },
+    TextureWrapS(TextureWrap),
+    GenerateMipmap,
+    Enable(Capability),
+    Disable(Capability),
+    LineWidth(f32),
+    ClearColor(f32, f32, f32, f32),
+    Clear { color: bool, depth: bool },
+    DrawElements { mode: Primitive, count: i32 },
+}
+
+/// A backend that performs no rendering and only records every command it
+/// receives, so the engine's output can be inspected in plain `cargo test`.
+#[allow(dead_code)]
+pub struct HeadlessBackend {
+    width: u32,
+    height: u32,
+    next_id: Cell<u32>,
+    commands: RefCell<Vec<Command>>,
+    /// Name of every uniform location handed out, they outlive
+    /// `clear_commands`.
+    uniform_names: RefCell<Vec<(UniformLocation, String)>>,
+}
+
+#[allow(dead_code)]
+impl HeadlessBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        HeadlessBackend {
+            width,
+            height,
+            next_id: Cell::new(0),
+            commands: RefCell::new(Vec::new()),
+            uniform_names: RefCell::new(Vec::new()),
+        }
+    }
+    pub fn commands(&self) -> Vec<Command> {
+        self.commands.borrow().clone()
+    }
+    pub fn clear_commands(&self) {
+        self.commands.borrow_mut().clear();
+    }
+    pub fn draw_calls(&self) -> Vec<(Primitive, i32)> {
+        self.commands
+            .borrow()
+            .iter()
+            .filter_map(|command| match command {
+                Command::DrawElements { mode, count } => Some((*mode, *count)),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Vectors loaded in the uniforms called `name`, of any program.
+    pub fn uniform_values(&self, name: &str) -> Vec<Vec<f32>> {
+        let names = self.uniform_names.borrow();
+        let named = |location: &Option<UniformLocation>| {
+            names
+                .iter()
+                .any(|(other, other_name)| Some(*other) == *location && other_name == name)
+        };
+        self.commands
+            .borrow()
+            .iter()
+            .filter_map(|command| match command {
+                Command::Uniform4fv(location, values) if named(location) => Some(values.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+    fn record(&self, command: Command) {
+        self.commands.borrow_mut().push(command);
+    }
+    fn next_id(&self) -> u32 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+}
+
+impl GraphicsBackend for HeadlessBackend {
+    fn drawing_buffer_size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    fn create_buffer(&self) -> Result<BufferId, String> {
+        let id = BufferId(self.next_id());
+        self.record(Command::CreateBuffer(id));
+        Ok(id)
+    }
+    fn delete_buffer(&self, buffer: BufferId) {
+        self.record(Command::DeleteBuffer(buffer));
+    }
+    fn bind_buffer(&self, target: BufferTarget, buffer: Option<BufferId>) {
+        self.record(Command::BindBuffer(target, buffer));
+    }
+    fn buffer_data_f32(&self, target: BufferTarget, data: &[f32]) {
+        self.record(Command::BufferDataF32(target, data.to_vec()));
+    }
+    fn buffer_data_u32(&self, target: BufferTarget, data: &[u32]) {
+        self.record(Command::BufferDataU32(target, data.to_vec()));
+    }
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String> {
+        let id = VertexArrayId(self.next_id());
+        self.record(Command::CreateVertexArray(id));
+        Ok(id)
+    }
+    fn delete_vertex_array(&self, vao: VertexArrayId) {
+        self.record(Command::DeleteVertexArray(vao));
+    }
+    fn bind_vertex_array(&self, vao: Option<VertexArrayId>) {
+        self.record(Command::BindVertexArray(vao));
+    }
+    fn vertex_attrib_pointer(&self, index: u32, size: i32) {
+        self.record(Command::VertexAttribPointer { index, size });
+    }
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.record(Command::EnableVertexAttribArray(index));
+    }
+    fn create_shader(&self, shader_type: ShaderType, _source: &str) -> Result<ShaderId, String> {
+        let id = ShaderId(self.next_id());
+        self.record(Command::CreateShader(id, shader_type));
+        Ok(id)
+    }
+    fn create_program(&self, shaders: &[ShaderId]) -> Result<ProgramId, String> {
+        let id = ProgramId(self.next_id());
+        self.record(Command::CreateProgram(id, shaders.to_vec()));
+        Ok(id)
+    }
+    fn use_program(&self, program: Option<ProgramId>) {
+        self.record(Command::UseProgram(program));
+    }
+    fn get_uniform_location(&self, program: ProgramId, name: &str) -> Option<UniformLocation> {
+        let id = UniformLocation(self.next_id());
+        self.uniform_names.borrow_mut().push((id, name.to_string()));
+        self.record(Command::GetUniformLocation(program, name.to_string(), id));
+        Some(id)
+    }
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, data: &[f32]) {
+        self.record(Command::UniformMatrix4fv(
+            location,
+            transpose,
+            data.to_vec(),
+        ));
+    }
+    fn uniform4fv(&self, location: Option<UniformLocation>, data: &[f32]) {
+        self.record(Command::Uniform4fv(location, data.to_vec()));
+    }
+    fn create_texture(&self) -> Result<TextureId, String> {
+        let id = TextureId(self.next_id());
+        self.record(Command::CreateTexture(id));
+        Ok(id)
+    }
+    fn delete_texture(&self, texture: TextureId) {
+        self.record(Command::DeleteTexture(texture));
+    }
+    fn active_texture(&self, unit: u32) {
+        self.record(Command::ActiveTexture(unit));
+    }
+    fn bind_texture(&self, texture: Option<TextureId>) {
+        self.record(Command::BindTexture(texture));
+    }
+    fn tex_image_rgba(&self, width: u32, height: u32, pixels: &[u8]) -> Result<(), String> {
+        if pixels.len() != (width * height * 4) as usize {
+            return Err(format!(
+                "expected {} bytes of pixel data, got {}",
+                width * height * 4,
+                pixels.len()
+            ));
+        }
+        self.record(Command::TexImage { width, height });
+        Ok(())
+    }
+    fn texture_wrap_s(&self, wrap: TextureWrap) {
+        self.record(Command::TextureWrapS(wrap));
+    }
+    fn generate_mipmap(&self) {
+        self.record(Command::GenerateMipmap);
+    }
+    fn enable(&self, capability: Capability) {
+        self.record(Command::Enable(capability));
+    }
+    fn disable(&self, capability: Capability) {
+        self.record(Command::Disable(capability));
+    }
+    fn line_width(&self, width: f32) {
+        self.record(Command::LineWidth(width));
+    }
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.record(Command::ClearColor(r, g, b, a));
+    }
+    fn clear(&self, color: bool, depth: bool) {
+        self.record(Command::Clear { color, depth });
+    }
+    fn draw_elements(&self, mode: Primitive, count: i32) {
+        self.record(Command::DrawElements { mode, count });
+    }
+}
+
+#[test]
+fn vao_records_buffer_uploads_and_draws() {
+    use crate::renderer::{meshes, Vao};
+    use std::rc::Rc;
+
+    let headless = Rc::new(HeadlessBackend::new(800, 600));
+    let backend: Rc<dyn GraphicsBackend> = headless.clone();
+    let vao = Vao::new(&backend, meshes::line_rectangle(2.0, 1.0)).unwrap();
+    let commands = headless.commands();
+    assert_eq!(commands[0], Command::CreateVertexArray(vao.get_vao()));
+    assert!(commands.contains(&Command::BufferDataU32(
+        BufferTarget::ElementArray,
+        vec![0, 1, 3, 2]
+    )));
+    assert!(commands.contains(&Command::VertexAttribPointer { index: 0, size: 3 }));
+
+    headless.clear_commands();
+    vao.draw_line_loop();
+    assert_eq!(headless.draw_calls(), vec![(Primitive::LineLoop, 4)]);
+}
+
+#[test]
+fn dropped_vaos_and_textures_free_their_objects() {
+    use crate::renderer::{meshes, Texture, Vao};
+    use std::rc::Rc;
+
+    let headless = Rc::new(HeadlessBackend::new(800, 600));
+    let backend: Rc<dyn GraphicsBackend> = headless.clone();
+    let vao = Vao::new(&backend, meshes::stroke_rectangle(2.0, 1.0)).unwrap();
+    let created: Vec<Command> = headless
+        .commands()
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::CreateBuffer(id) => Some(Command::DeleteBuffer(id)),
+            Command::CreateVertexArray(id) => Some(Command::DeleteVertexArray(id)),
+            _ => None,
+        })
+        .collect();
+    headless.clear_commands();
+    drop(vao);
+    let deleted = headless.commands();
+    assert_eq!(deleted.len(), created.len());
+    assert!(created.iter().all(|command| deleted.contains(command)));
+
+    // clones share the texture, the last one deletes it
+    let texture = Texture::from_rgba(&backend, 1, 1, &[255; 4]).unwrap();
+    let copy = texture.clone();
+    headless.clear_commands();
+    drop(texture);
+    assert!(headless.commands().is_empty());
+    drop(copy);
+    assert!(matches!(
+        headless.commands()[..],
+        [Command::DeleteTexture(_)]
+    ));
+}
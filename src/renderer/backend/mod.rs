@@ -0,0 +1,97 @@
+mod headless;
+mod software;
+mod webgl;
+
+#[allow(unused_imports)]
+pub use headless::*;
+#[allow(unused_imports)]
+pub use software::*;
+pub use webgl::*;
+
+macro_rules! handle {
+    ($name:ident) => {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub struct $name(pub u32);
+    };
+}
+
+handle!(BufferId);
+handle!(VertexArrayId);
+handle!(ShaderId);
+handle!(ProgramId);
+handle!(UniformLocation);
+handle!(TextureId);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BufferTarget {
+    Array,
+    ElementArray,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShaderType {
+    Vertex,
+    Fragment,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Primitive {
+    Triangles,
+    LineLoop,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Capability {
+    DepthTest,
+    /// Straight alpha blending, `src * a + dst * (1 - a)`.
+    Blend,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextureWrap {
+    ClampToEdge,
+    Repeat,
+}
+
+/// The set of graphics commands the engine issues. It mirrors the subset of
+/// WebGL2 used by `Vao`, `Texture` and `ShaderProgram`, with resources
+/// referred to by opaque handles so it can be implemented off the browser.
+pub trait GraphicsBackend {
+    fn drawing_buffer_size(&self) -> (u32, u32);
+
+    fn create_buffer(&self) -> Result<BufferId, String>;
+    fn delete_buffer(&self, buffer: BufferId);
+    fn bind_buffer(&self, target: BufferTarget, buffer: Option<BufferId>);
+    fn buffer_data_f32(&self, target: BufferTarget, data: &[f32]);
+    fn buffer_data_u32(&self, target: BufferTarget, data: &[u32]);
+
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String>;
+    fn delete_vertex_array(&self, vao: VertexArrayId);
+    fn bind_vertex_array(&self, vao: Option<VertexArrayId>);
+    fn vertex_attrib_pointer(&self, index: u32, size: i32);
+    fn enable_vertex_attrib_array(&self, index: u32);
+
+    /// Creates and compiles a shader, returning the info log on failure.
+    fn create_shader(&self, shader_type: ShaderType, source: &str) -> Result<ShaderId, String>;
+    /// Creates a program from compiled shaders and links it.
+    fn create_program(&self, shaders: &[ShaderId]) -> Result<ProgramId, String>;
+    fn use_program(&self, program: Option<ProgramId>);
+    fn get_uniform_location(&self, program: ProgramId, name: &str) -> Option<UniformLocation>;
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, data: &[f32]);
+    fn uniform4fv(&self, location: Option<UniformLocation>, data: &[f32]);
+
+    fn create_texture(&self) -> Result<TextureId, String>;
+    fn delete_texture(&self, texture: TextureId);
+    fn active_texture(&self, unit: u32);
+    fn bind_texture(&self, texture: Option<TextureId>);
+    fn tex_image_rgba(&self, width: u32, height: u32, pixels: &[u8]) -> Result<(), String>;
+    fn texture_wrap_s(&self, wrap: TextureWrap);
+    fn generate_mipmap(&self);
+
+    fn enable(&self, capability: Capability);
+    fn disable(&self, capability: Capability);
+    fn line_width(&self, width: f32);
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32);
+    fn clear(&self, color: bool, depth: bool);
+    fn draw_elements(&self, mode: Primitive, count: i32);
+}
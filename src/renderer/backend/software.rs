@@ -0,0 +1,826 @@
+use super::*;
+use crate::alg::{Mat4, Vec4};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+const DEFAULT_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const MAX_ATTRIBUTES: usize = 5;
+/// Longest miter of a stroke, in half line widths, as in `line.vert`.
+const MITER_LIMIT: f32 = 4.0;
+
+pub struct Framebuffer {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA pixels, top row first.
+    pub pixels: Vec<u8>,
+    depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    fn new(width: u32, height: u32) -> Self {
+        let size = (width * height) as usize;
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; size * 4],
+            depth: vec![1.0; size],
+        }
+    }
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+    fn write(&mut self, x: u32, y: u32, depth: f32, state: (bool, bool), color: [f32; 4]) {
+        let (depth_test, blend) = state;
+        let i = (y * self.width + x) as usize;
+        if depth_test {
+            if depth >= self.depth[i] {
+                return;
+            }
+            self.depth[i] = depth;
+        }
+        let alpha = color[3].clamp(0.0, 1.0);
+        for (c, value) in color.iter().enumerate() {
+            let pixel = &mut self.pixels[i * 4 + c];
+            let mut value = value.clamp(0.0, 1.0);
+            if blend {
+                let destination = *pixel as f32 / 255.0;
+                value = if c == 3 {
+                    alpha + destination * (1.0 - alpha)
+                } else {
+                    value * alpha + destination * (1.0 - alpha)
+                };
+            }
+            *pixel = (value * 255.0).round() as u8;
+        }
+    }
+}
+
+enum BufferData {
+    Empty,
+    F32(Vec<f32>),
+    U32(Vec<u32>),
+}
+
+#[derive(Default)]
+struct VertexArray {
+    attributes: [Option<(BufferId, i32)>; MAX_ATTRIBUTES],
+    enabled: [bool; MAX_ATTRIBUTES],
+    element_buffer: Option<BufferId>,
+}
+
+struct TextureImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+struct Vertex {
+    // screen space x, y, ndc depth and 1/w for perspective correct interpolation
+    position: [f32; 4],
+    texture_coords: Option<[f32; 2]>,
+    /// Distance along a stroke, in pixels.
+    dash_distance: Option<f32>,
+}
+
+#[derive(Copy, Clone)]
+enum Uniform {
+    Matrix(Mat4),
+    Vector([f32; 4]),
+}
+
+struct State {
+    framebuffer: Framebuffer,
+    buffers: Vec<BufferData>,
+    vaos: Vec<VertexArray>,
+    programs: Vec<HashMap<String, UniformLocation>>,
+    uniforms: Vec<Option<Uniform>>,
+    textures: Vec<Option<TextureImage>>,
+    array_buffer: Option<BufferId>,
+    vao: Option<VertexArrayId>,
+    program: Option<ProgramId>,
+    texture: Option<TextureId>,
+    clear_color: [f32; 4],
+    depth_test: bool,
+    blend: bool,
+    shaders: u32,
+}
+
+/// A pure Rust reference rasterizer that executes draw calls into an
+/// in-memory RGBA framebuffer.
+///
+/// The vertex stage applies the `projectionMatrix * viewMatrix *
+/// transformationMatrix` uniforms shared by the engine's programs. The
+/// fragment stage is fixed function: meshes with texture coordinates sample
+/// the bound texture (nearest), everything else is drawn with the `color`
+/// uniform of the line shader. Lines are one pixel wide, as WebGL2 clamps `lineWidth`;
+/// strokes built by `meshes::stroke` are widened and dashed like `line.vert`
+/// and `line.frag` do.
+pub struct SoftwareBackend {
+    state: RefCell<State>,
+}
+
+#[allow(dead_code)]
+impl SoftwareBackend {
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareBackend {
+            state: RefCell::new(State {
+                framebuffer: Framebuffer::new(width, height),
+                buffers: Vec::new(),
+                vaos: Vec::new(),
+                programs: Vec::new(),
+                uniforms: Vec::new(),
+                textures: Vec::new(),
+                array_buffer: None,
+                vao: None,
+                program: None,
+                texture: None,
+                clear_color: [0.0; 4],
+                depth_test: false,
+                blend: false,
+                shaders: 0,
+            }),
+        }
+    }
+    pub fn pixels(&self) -> Vec<u8> {
+        self.state.borrow().framebuffer.pixels.clone()
+    }
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        self.state.borrow().framebuffer.pixel(x, y)
+    }
+}
+
+impl State {
+    fn uniform(&self, name: &str) -> Option<Uniform> {
+        self.program
+            .and_then(|program| self.programs[program.0 as usize].get(name))
+            .and_then(|location| self.uniforms[location.0 as usize])
+    }
+    fn uniform_matrix(&self, name: &str) -> Mat4 {
+        match self.uniform(name) {
+            Some(Uniform::Matrix(matrix)) => matrix,
+            _ => Mat4::identity(),
+        }
+    }
+    /// Whether the dash pattern of `line.frag` leaves a gap at `distance`.
+    fn in_gap(&self, distance: f32) -> bool {
+        match self.uniform("dash") {
+            Some(Uniform::Vector([dash, gap, offset, _])) if gap > 0.0 => {
+                (distance - offset).rem_euclid(dash + gap) >= dash
+            }
+            _ => false,
+        }
+    }
+    fn color(&self) -> [f32; 4] {
+        match self.uniform("color") {
+            Some(Uniform::Vector(color)) => color,
+            _ => DEFAULT_COLOR,
+        }
+    }
+    /// Screen space offset, in pixels, pushing a stroke vertex to its side
+    /// of the line, see `line.vert`.
+    fn stroke_offset(
+        &self,
+        mvp: &Mat4,
+        current: [f32; 2],
+        side: f32,
+        neighbours: &[f32],
+    ) -> [f32; 2] {
+        let stroke = match self.uniform("stroke") {
+            Some(Uniform::Vector(stroke)) => stroke,
+            _ => return [0.0, 0.0],
+        };
+        let screen = |x: f32, y: f32| {
+            let clip = mvp.mul(&Vec4([x, y, 0.0, 1.0]));
+            [
+                clip[0] / clip[3] * stroke[0] * 0.5,
+                clip[1] / clip[3] * stroke[1] * 0.5,
+            ]
+        };
+        let normalize = |v: [f32; 2]| {
+            let length = (v[0] * v[0] + v[1] * v[1]).sqrt();
+            if length == 0.0 {
+                [0.0, 0.0]
+            } else {
+                [v[0] / length, v[1] / length]
+            }
+        };
+        let previous = screen(neighbours[0], neighbours[1]);
+        let next = screen(neighbours[2], neighbours[3]);
+        let incoming = normalize([current[0] - previous[0], current[1] - previous[1]]);
+        let outgoing = normalize([next[0] - current[0], next[1] - current[1]]);
+        let normal = [-incoming[1], incoming[0]];
+        let tangent = [incoming[0] + outgoing[0], incoming[1] + outgoing[1]];
+        let miter = if tangent[0].hypot(tangent[1]) < 1e-4 {
+            normal
+        } else {
+            let tangent = normalize(tangent);
+            [-tangent[1], tangent[0]]
+        };
+        let dot = miter[0] * normal[0] + miter[1] * normal[1];
+        let length = (1.0 / dot.max(1e-4)).min(MITER_LIMIT) * stroke[2] * 0.5 * side;
+        [miter[0] * length, miter[1] * length]
+    }
+    fn attribute(&self, vao: &VertexArray, index: usize) -> Option<(&[f32], usize)> {
+        if !vao.enabled[index] {
+            return None;
+        }
+        let (buffer, size) = vao.attributes[index]?;
+        match self.buffers[buffer.0 as usize] {
+            BufferData::F32(ref data) => Some((data.as_slice(), size as usize)),
+            _ => None,
+        }
+    }
+    fn vertices(&self, count: i32) -> Vec<Vertex> {
+        let vao = match self.vao {
+            Some(vao) => &self.vaos[vao.0 as usize],
+            None => return Vec::new(),
+        };
+        let indices = match vao.element_buffer.map(|b| &self.buffers[b.0 as usize]) {
+            Some(BufferData::U32(indices)) => indices,
+            _ => return Vec::new(),
+        };
+        let positions = match self.attribute(vao, 0) {
+            Some(positions) => positions,
+            None => return Vec::new(),
+        };
+        let texture_coords = self.attribute(vao, 1);
+        let neighbours = self.attribute(vao, 3);
+        let distances = self.attribute(vao, 4);
+        let dash_scale = match self.uniform("dash") {
+            Some(Uniform::Vector(dash)) => dash[3],
+            _ => 0.0,
+        };
+        let mvp = self.uniform_matrix("projectionMatrix")
+            * self.uniform_matrix("viewMatrix")
+            * self.uniform_matrix("transformationMatrix");
+        let (width, height) = (
+            self.framebuffer.width as f32,
+            self.framebuffer.height as f32,
+        );
+
+        indices
+            .iter()
+            .take(count as usize)
+            .map(|&index| {
+                let index = index as usize;
+                let (data, size) = positions;
+                let p = &data[index * size..index * size + size];
+                let z = p.get(2).copied().unwrap_or(0.0);
+                let clip = match neighbours {
+                    Some(_) => mvp.mul(&Vec4([p[0], p[1], 0.0, 1.0])),
+                    None => mvp.mul(&Vec4([p[0], p[1], z, 1.0])),
+                };
+                let w = clip[3];
+                let (mut x, mut y) = (clip[0] / w, clip[1] / w);
+                if let Some((data, size)) = neighbours.filter(|_| z != 0.0) {
+                    let current = [x * width * 0.5, y * height * 0.5];
+                    let n = &data[index * size..index * size + size];
+                    let offset = self.stroke_offset(&mvp, current, z, n);
+                    x += offset[0] / (width * 0.5);
+                    y += offset[1] / (height * 0.5);
+                }
+                let z = clip[2] / w;
+                Vertex {
+                    position: [
+                        (x + 1.0) * 0.5 * width,
+                        (1.0 - y) * 0.5 * height,
+                        z,
+                        1.0 / w,
+                    ],
+                    texture_coords: texture_coords.map(|(data, size)| {
+                        let t = &data[index * size..index * size + size];
+                        [t[0], t[1]]
+                    }),
+                    dash_distance: distances.map(|(data, size)| data[index * size] * dash_scale),
+                }
+            })
+            .collect()
+    }
+    fn sample(&self, coords: Option<[f32; 2]>) -> [f32; 4] {
+        let coords = match coords {
+            Some(coords) => coords,
+            None => return self.color(),
+        };
+        let image = match self
+            .texture
+            .and_then(|texture| self.textures[texture.0 as usize].as_ref())
+        {
+            Some(image) => image,
+            None => return [0.0, 0.0, 0.0, 1.0],
+        };
+        let texel =
+            |coord: f32, size: u32| ((coord.clamp(0.0, 1.0) * size as f32) as u32).min(size - 1);
+        let x = texel(coords[0], image.width);
+        let y = texel(coords[1], image.height);
+        let i = ((y * image.width + x) * 4) as usize;
+        let mut color = [0.0; 4];
+        for (c, value) in color.iter_mut().enumerate() {
+            *value = image.pixels[i + c] as f32 / 255.0;
+        }
+        color
+    }
+    fn draw_triangle(&mut self, v: [&Vertex; 3]) {
+        // no near plane clipping: triangles crossing the camera are dropped
+        if v.iter().any(|v| v.position[3] <= 0.0) {
+            return;
+        }
+        let [a, b, c] = [v[0].position, v[1].position, v[2].position];
+        let edge = |p: [f32; 4], q: [f32; 4], x: f32, y: f32| {
+            (q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0])
+        };
+        let area = edge(a, b, c[0], c[1]);
+        if area == 0.0 {
+            return;
+        }
+        let (width, height) = (self.framebuffer.width, self.framebuffer.height);
+        let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+        let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+        let max_x = (a[0].max(b[0]).max(c[0]).ceil().max(0.0) as u32).min(width);
+        let max_y = (a[1].max(b[1]).max(c[1]).ceil().max(0.0) as u32).min(height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = edge(b, c, px, py) / area;
+                let w1 = edge(c, a, px, py) / area;
+                let w2 = edge(a, b, px, py) / area;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                let z = w0 * a[2] + w1 * b[2] + w2 * c[2];
+                if !(-1.0..=1.0).contains(&z) {
+                    continue;
+                }
+                let coords = match (
+                    v[0].texture_coords,
+                    v[1].texture_coords,
+                    v[2].texture_coords,
+                ) {
+                    (Some(t0), Some(t1), Some(t2)) => {
+                        let (p0, p1, p2) = (w0 * a[3], w1 * b[3], w2 * c[3]);
+                        let sum = p0 + p1 + p2;
+                        Some([
+                            (p0 * t0[0] + p1 * t1[0] + p2 * t2[0]) / sum,
+                            (p0 * t0[1] + p1 * t1[1] + p2 * t2[1]) / sum,
+                        ])
+                    }
+                    _ => None,
+                };
+                if let (Some(d0), Some(d1), Some(d2)) =
+                    (v[0].dash_distance, v[1].dash_distance, v[2].dash_distance)
+                {
+                    let (p0, p1, p2) = (w0 * a[3], w1 * b[3], w2 * c[3]);
+                    if self.in_gap((p0 * d0 + p1 * d1 + p2 * d2) / (p0 + p1 + p2)) {
+                        continue;
+                    }
+                }
+                let color = self.sample(coords);
+                let state = (self.depth_test, self.blend);
+                self.framebuffer.write(x, y, z, state, color);
+            }
+        }
+    }
+    fn draw_line(&mut self, from: &Vertex, to: &Vertex) {
+        if from.position[3] <= 0.0 || to.position[3] <= 0.0 {
+            return;
+        }
+        let (a, b) = (from.position, to.position);
+        let steps = (b[0] - a[0]).abs().max((b[1] - a[1]).abs()).ceil().max(1.0) as u32;
+        let (width, height) = (
+            self.framebuffer.width as f32,
+            self.framebuffer.height as f32,
+        );
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let x = a[0] + (b[0] - a[0]) * t;
+            let y = a[1] + (b[1] - a[1]) * t;
+            let z = a[2] + (b[2] - a[2]) * t;
+            if x < 0.0 || y < 0.0 || x >= width || y >= height || !(-1.0..=1.0).contains(&z) {
+                continue;
+            }
+            let (state, color) = ((self.depth_test, self.blend), self.color());
+            self.framebuffer.write(x as u32, y as u32, z, state, color);
+        }
+    }
+}
+
+impl GraphicsBackend for SoftwareBackend {
+    fn drawing_buffer_size(&self) -> (u32, u32) {
+        let state = self.state.borrow();
+        (state.framebuffer.width, state.framebuffer.height)
+    }
+    fn create_buffer(&self) -> Result<BufferId, String> {
+        let mut state = self.state.borrow_mut();
+        state.buffers.push(BufferData::Empty);
+        Ok(BufferId(state.buffers.len() as u32 - 1))
+    }
+    /// Drops the data, the handles are not reused.
+    fn delete_buffer(&self, buffer: BufferId) {
+        if let Some(data) = self.state.borrow_mut().buffers.get_mut(buffer.0 as usize) {
+            *data = BufferData::Empty;
+        }
+    }
+    fn bind_buffer(&self, target: BufferTarget, buffer: Option<BufferId>) {
+        let mut state = self.state.borrow_mut();
+        match target {
+            BufferTarget::Array => state.array_buffer = buffer,
+            BufferTarget::ElementArray => {
+                if let Some(vao) = state.vao {
+                    state.vaos[vao.0 as usize].element_buffer = buffer;
+                }
+            }
+        }
+    }
+    fn buffer_data_f32(&self, target: BufferTarget, data: &[f32]) {
+        let mut state = self.state.borrow_mut();
+        let buffer = match target {
+            BufferTarget::Array => state.array_buffer,
+            BufferTarget::ElementArray => state
+                .vao
+                .and_then(|vao| state.vaos[vao.0 as usize].element_buffer),
+        };
+        if let Some(buffer) = buffer {
+            state.buffers[buffer.0 as usize] = BufferData::F32(data.to_vec());
+        }
+    }
+    fn buffer_data_u32(&self, target: BufferTarget, data: &[u32]) {
+        let mut state = self.state.borrow_mut();
+        let buffer = match target {
+            BufferTarget::Array => state.array_buffer,
+            BufferTarget::ElementArray => state
+                .vao
+                .and_then(|vao| state.vaos[vao.0 as usize].element_buffer),
+        };
+        if let Some(buffer) = buffer {
+            state.buffers[buffer.0 as usize] = BufferData::U32(data.to_vec());
+        }
+    }
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String> {
+        let mut state = self.state.borrow_mut();
+        state.vaos.push(VertexArray::default());
+        Ok(VertexArrayId(state.vaos.len() as u32 - 1))
+    }
+    fn delete_vertex_array(&self, vao: VertexArrayId) {
+        if let Some(vao) = self.state.borrow_mut().vaos.get_mut(vao.0 as usize) {
+            *vao = VertexArray::default();
+        }
+    }
+    fn bind_vertex_array(&self, vao: Option<VertexArrayId>) {
+        self.state.borrow_mut().vao = vao;
+    }
+    fn vertex_attrib_pointer(&self, index: u32, size: i32) {
+        let mut state = self.state.borrow_mut();
+        if let (Some(vao), Some(buffer)) = (state.vao, state.array_buffer) {
+            if let Some(attribute) = state.vaos[vao.0 as usize]
+                .attributes
+                .get_mut(index as usize)
+            {
+                *attribute = Some((buffer, size));
+            }
+        }
+    }
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        let mut state = self.state.borrow_mut();
+        if let Some(vao) = state.vao {
+            if let Some(enabled) = state.vaos[vao.0 as usize].enabled.get_mut(index as usize) {
+                *enabled = true;
+            }
+        }
+    }
+    fn create_shader(&self, _shader_type: ShaderType, _source: &str) -> Result<ShaderId, String> {
+        let mut state = self.state.borrow_mut();
+        state.shaders += 1;
+        Ok(ShaderId(state.shaders - 1))
+    }
+    fn create_program(&self, _shaders: &[ShaderId]) -> Result<ProgramId, String> {
+        let mut state = self.state.borrow_mut();
+        state.programs.push(HashMap::new());
+        Ok(ProgramId(state.programs.len() as u32 - 1))
+    }
+    fn use_program(&self, program: Option<ProgramId>) {
+        self.state.borrow_mut().program = program;
+    }
+    fn get_uniform_location(&self, program: ProgramId, name: &str) -> Option<UniformLocation> {
+        let mut state = self.state.borrow_mut();
+        let next = UniformLocation(state.uniforms.len() as u32);
+        let location = *state
+            .programs
+            .get_mut(program.0 as usize)?
+            .entry(name.to_string())
+            .or_insert(next);
+        if location == next {
+            state.uniforms.push(None);
+        }
+        Some(location)
+    }
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, data: &[f32]) {
+        let mut state = self.state.borrow_mut();
+        if let Some(location) = location {
+            let mut matrix = Mat4::new();
+            matrix.0.copy_from_slice(&data[..16]);
+            // the engine's matrices are row major, GL expects column major
+            let matrix = if transpose {
+                matrix
+            } else {
+                matrix.transpose()
+            };
+            state.uniforms[location.0 as usize] = Some(Uniform::Matrix(matrix));
+        }
+    }
+    fn uniform4fv(&self, location: Option<UniformLocation>, data: &[f32]) {
+        let mut state = self.state.borrow_mut();
+        if let Some(location) = location {
+            let mut vector = [0.0; 4];
+            vector.copy_from_slice(&data[..4]);
+            state.uniforms[location.0 as usize] = Some(Uniform::Vector(vector));
+        }
+    }
+    fn create_texture(&self) -> Result<TextureId, String> {
+        let mut state = self.state.borrow_mut();
+        state.textures.push(None);
+        Ok(TextureId(state.textures.len() as u32 - 1))
+    }
+    fn delete_texture(&self, texture: TextureId) {
+        if let Some(image) = self.state.borrow_mut().textures.get_mut(texture.0 as usize) {
+            *image = None;
+        }
+    }
+    fn active_texture(&self, _unit: u32) {}
+    fn bind_texture(&self, texture: Option<TextureId>) {
+        self.state.borrow_mut().texture = texture;
+    }
+    fn tex_image_rgba(&self, width: u32, height: u32, pixels: &[u8]) -> Result<(), String> {
+        if pixels.len() != (width * height * 4) as usize {
+            return Err(format!(
+                "expected {} bytes of pixel data, got {}",
+                width * height * 4,
+                pixels.len()
+            ));
+        }
+        let mut state = self.state.borrow_mut();
+        let texture = state.texture.ok_or("no texture bound")?;
+        state.textures[texture.0 as usize] = Some(TextureImage {
+            width,
+            height,
+            pixels: pixels.to_vec(),
+        });
+        Ok(())
+    }
+    fn texture_wrap_s(&self, _wrap: TextureWrap) {}
+    fn generate_mipmap(&self) {}
+    fn enable(&self, capability: Capability) {
+        let mut state = self.state.borrow_mut();
+        match capability {
+            Capability::DepthTest => state.depth_test = true,
+            Capability::Blend => state.blend = true,
+        }
+    }
+    fn disable(&self, capability: Capability) {
+        let mut state = self.state.borrow_mut();
+        match capability {
+            Capability::DepthTest => state.depth_test = false,
+            Capability::Blend => state.blend = false,
+        }
+    }
+    fn line_width(&self, _width: f32) {}
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.state.borrow_mut().clear_color = [r, g, b, a];
+    }
+    fn clear(&self, color: bool, depth: bool) {
+        let mut state = self.state.borrow_mut();
+        let clear_color = state
+            .clear_color
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        let framebuffer = &mut state.framebuffer;
+        if color {
+            for pixel in framebuffer.pixels.chunks_mut(4) {
+                pixel.copy_from_slice(&clear_color);
+            }
+        }
+        if depth {
+            framebuffer.depth.iter_mut().for_each(|d| *d = 1.0);
+        }
+    }
+    fn draw_elements(&self, mode: Primitive, count: i32) {
+        let mut state = self.state.borrow_mut();
+        let vertices = state.vertices(count);
+        match mode {
+            Primitive::Triangles => {
+                for triangle in vertices.chunks_exact(3) {
+                    state.draw_triangle([&triangle[0], &triangle[1], &triangle[2]]);
+                }
+            }
+            Primitive::LineLoop => {
+                for (i, from) in vertices.iter().enumerate() {
+                    let to = &vertices[(i + 1) % vertices.len()];
+                    state.draw_line(from, to);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn load_png(path: &std::path::Path) -> (u32, u32, Vec<u8>) {
+    let file = std::fs::File::open(path).expect("cannot open png");
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    pixels.truncate(info.buffer_size());
+    assert_eq!(info.color_type, png::ColorType::Rgba, "{:?}", path);
+    (info.width, info.height, pixels)
+}
+
+/// Compares the framebuffer with `golden/<name>.png`. Run the tests with
+/// `UPDATE_GOLDEN=1` to (re)generate the reference images.
+#[cfg(test)]
+fn assert_matches_golden(backend: &SoftwareBackend, name: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(format!("{}.png", name));
+    let (width, height) = backend.drawing_buffer_size();
+    let pixels = backend.pixels();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = std::fs::File::create(&path).unwrap();
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels).unwrap();
+        return;
+    }
+    if !path.exists() {
+        panic!(
+            "no golden image {:?}, run the tests with UPDATE_GOLDEN=1 to create it",
+            path
+        );
+    }
+    let (golden_width, golden_height, golden) = load_png(&path);
+    assert_eq!((width, height), (golden_width, golden_height));
+    let mismatches = pixels
+        .iter()
+        .zip(golden.iter())
+        .filter(|(a, b)| (**a as i32 - **b as i32).abs() > 1)
+        .count();
+    assert_eq!(mismatches, 0, "{} differs from {:?}", name, path);
+}
+
+#[cfg(test)]
+fn squidgame_renderer() -> (std::rc::Rc<SoftwareBackend>, crate::renderer::Renderer) {
+    use crate::renderer::{test_renderer, Camera, Texture};
+    use std::rc::Rc;
+
+    let software = Rc::new(SoftwareBackend::new(160, 120));
+    let mut renderer = test_renderer(software.clone());
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("squidgame.png");
+    let (width, height, pixels) = load_png(&path);
+    let texture = Texture::from_rgba(renderer.get_backend(), width, height, &pixels).unwrap();
+    renderer.create_plane_from_texture(texture).unwrap();
+    renderer.camera = Camera::new(Vec4([0.0, 0.0, 1.0, 1.0]), 0.0, 0.0, 0.0, 220.0);
+    (software, renderer)
+}
+
+#[test]
+fn software_rasterizer_draws_textured_triangles_and_line_loops() {
+    use crate::renderer::{meshes, Vao};
+    use std::rc::Rc;
+
+    let software = Rc::new(SoftwareBackend::new(4, 4));
+    let backend: Rc<dyn GraphicsBackend> = software.clone();
+    let texture = crate::renderer::Texture::from_rgba(&backend, 1, 1, &[0, 255, 0, 255]).unwrap();
+    let vao = Vao::new(&backend, meshes::textured_rectangle(texture).unwrap()).unwrap();
+    vao.draw();
+    assert_eq!(software.pixel(0, 0), [0, 0, 0, 0]);
+    assert_eq!(software.pixel(1, 1), [0, 255, 0, 255]);
+    assert_eq!(software.pixel(2, 2), [0, 255, 0, 255]);
+
+    backend.clear_color(0.0, 0.0, 0.0, 1.0);
+    backend.clear(true, true);
+    let frame = Vao::new(&backend, meshes::line_rectangle(1.0, 1.0)).unwrap();
+    frame.draw_line_loop();
+    assert_eq!(software.pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(software.pixel(1, 1), [255, 0, 0, 255]);
+    assert_eq!(software.pixel(2, 1), [255, 0, 0, 255]);
+    assert_eq!(software.pixel(2, 2), [0, 0, 0, 255]);
+}
+
+#[test]
+fn software_rasterizer_widens_strokes_in_pixels() {
+    use crate::renderer::{meshes, ShaderProgram, StdUniforms, Vao};
+    use std::rc::Rc;
+
+    let software = Rc::new(SoftwareBackend::new(20, 20));
+    let backend: Rc<dyn GraphicsBackend> = software.clone();
+    let program = ShaderProgram::<StdUniforms>::from_source(
+        &backend,
+        include_str!("../../../line.vert"),
+        include_str!("../../../line.frag"),
+    )
+    .unwrap();
+    program.use_program();
+    program.load_stroke((20, 20), 4.0);
+    // a square from pixel 5 to 15, its border 2 pixels on each side
+    let frame = Vao::new(&backend, meshes::stroke_rectangle(1.0, 1.0)).unwrap();
+    frame.draw();
+    let red = [255, 0, 0, 255];
+    for x in 3..7 {
+        assert_eq!(software.pixel(x, 10), red, "{}", x);
+    }
+    assert_eq!(software.pixel(2, 10), [0; 4]);
+    assert_eq!(software.pixel(7, 10), [0; 4]);
+    assert_eq!(software.pixel(10, 10), [0; 4]);
+    // the miter fills the corner
+    assert_eq!(software.pixel(3, 3), red);
+    assert_eq!(software.pixel(16, 16), red);
+}
+
+#[test]
+fn software_rasterizer_leaves_the_gaps_of_dashed_strokes() {
+    use crate::renderer::{meshes, ShaderProgram, StdUniforms, Vao};
+    use std::rc::Rc;
+
+    let software = Rc::new(SoftwareBackend::new(20, 20));
+    let backend: Rc<dyn GraphicsBackend> = software.clone();
+    let program = ShaderProgram::<StdUniforms>::from_source(
+        &backend,
+        include_str!("../../../line.vert"),
+        include_str!("../../../line.frag"),
+    )
+    .unwrap();
+    program.use_program();
+    program.load_stroke((20, 20), 2.0);
+    // from pixel 5 to 15, a unit of the mesh is 10 pixels
+    let line = meshes::stroke(&[(-0.5, 0.0), (0.5, 0.0)], false, meshes::Join::Miter);
+    let line = Vao::new(&backend, line).unwrap();
+    let row = |software: &SoftwareBackend| -> Vec<bool> {
+        (5..15).map(|x| software.pixel(x, 10)[3] > 0).collect()
+    };
+    program.load_dash([2.0, 2.0, 0.0], 10.0);
+    line.draw();
+    let dashes = [
+        true, true, false, false, true, true, false, false, true, true,
+    ];
+    assert_eq!(row(&software), dashes);
+
+    // the dashes march along the line with the offset
+    backend.clear(true, true);
+    program.load_dash([2.0, 2.0, 1.0], 10.0);
+    line.draw();
+    let marched = [
+        false, true, true, false, false, true, true, false, false, true,
+    ];
+    assert_eq!(row(&software), marched);
+}
+
+#[test]
+fn plane_matches_golden_image() {
+    let (software, renderer) = squidgame_renderer();
+    renderer.prepare();
+    renderer.draw();
+    assert_matches_golden(&software, "plane");
+}
+
+#[test]
+fn plane_after_key_move_2d_matches_golden_image() {
+    let (software, mut renderer) = squidgame_renderer();
+    renderer.camera.key_move_2d("d");
+    renderer.camera.key_move_2d("w");
+    renderer.prepare();
+    renderer.draw();
+    assert_matches_golden(&software, "plane_moved");
+}
+
+#[test]
+fn area_fills_blend_over_the_plane() {
+    use crate::alg::Polygon;
+    use crate::renderer::{test_renderer, Camera, Texture};
+    use std::rc::Rc;
+
+    let software = Rc::new(SoftwareBackend::new(80, 60));
+    let mut renderer = test_renderer(software.clone());
+    let white = Texture::from_rgba(renderer.get_backend(), 40, 30, &[255; 40 * 30 * 4]).unwrap();
+    renderer.create_plane_from_texture(white).unwrap();
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    renderer.set_fill(area, Some([1.0, 0.0, 0.0, 0.5])).unwrap();
+    renderer.camera = Camera::new(Vec4([0.0, 0.0, 1.0, 1.0]), 0.0, 0.0, 0.0, 40.0);
+    renderer.prepare();
+    renderer.draw();
+    // the canvas center looks at the middle of the area
+    assert_eq!(software.pixel(40, 30), [255, 128, 128, 255]);
+
+    renderer.set_overlay_opacity(0.0).unwrap();
+    renderer.prepare();
+    renderer.draw();
+    assert_eq!(software.pixel(40, 30), [255, 255, 255, 255]);
+}
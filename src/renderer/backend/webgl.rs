@@ -0,0 +1,316 @@
+use super::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlProgram, WebGlShader, WebGlTexture,
+    WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+/// Objects behind the handles, the slots of deleted ones are reused.
+struct Store<T> {
+    list: RefCell<Vec<Option<T>>>,
+    free: RefCell<Vec<u32>>,
+}
+impl<T: Clone> Store<T> {
+    fn new() -> Store<T> {
+        Store {
+            list: RefCell::new(Vec::new()),
+            free: RefCell::new(Vec::new()),
+        }
+    }
+    fn add(&self, obj: T) -> u32 {
+        let mut list = self.list.borrow_mut();
+        if let Some(id) = self.free.borrow_mut().pop() {
+            list[id as usize] = Some(obj);
+            return id;
+        }
+        list.push(Some(obj));
+        (list.len() - 1) as u32
+    }
+    fn get(&self, id: u32) -> Option<T> {
+        self.list.borrow().get(id as usize).cloned().flatten()
+    }
+    fn remove(&self, id: u32) -> Option<T> {
+        let obj = self.list.borrow_mut().get_mut(id as usize)?.take()?;
+        self.free.borrow_mut().push(id);
+        Some(obj)
+    }
+}
+
+pub struct WebGlBackend {
+    context: WebGl2RenderingContext,
+    buffers: Store<WebGlBuffer>,
+    vaos: Store<WebGlVertexArrayObject>,
+    shaders: Store<WebGlShader>,
+    programs: Store<WebGlProgram>,
+    uniforms: Store<WebGlUniformLocation>,
+    /// Location handed out for each program and uniform name.
+    uniform_ids: RefCell<HashMap<(u32, String), UniformLocation>>,
+    textures: Store<WebGlTexture>,
+}
+
+impl WebGlBackend {
+    pub fn new(context: WebGl2RenderingContext) -> Self {
+        WebGlBackend {
+            context,
+            buffers: Store::new(),
+            vaos: Store::new(),
+            shaders: Store::new(),
+            programs: Store::new(),
+            uniforms: Store::new(),
+            uniform_ids: RefCell::new(HashMap::new()),
+            textures: Store::new(),
+        }
+    }
+    fn buffer_target(target: BufferTarget) -> u32 {
+        match target {
+            BufferTarget::Array => WebGl2RenderingContext::ARRAY_BUFFER,
+            BufferTarget::ElementArray => WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
+        }
+    }
+}
+
+impl GraphicsBackend for WebGlBackend {
+    fn drawing_buffer_size(&self) -> (u32, u32) {
+        (
+            self.context.drawing_buffer_width() as u32,
+            self.context.drawing_buffer_height() as u32,
+        )
+    }
+    fn create_buffer(&self) -> Result<BufferId, String> {
+        let buffer = self
+            .context
+            .create_buffer()
+            .ok_or("Failed to create buffer")?;
+        Ok(BufferId(self.buffers.add(buffer)))
+    }
+    fn delete_buffer(&self, buffer: BufferId) {
+        if let Some(buffer) = self.buffers.remove(buffer.0) {
+            self.context.delete_buffer(Some(&buffer));
+        }
+    }
+    fn bind_buffer(&self, target: BufferTarget, buffer: Option<BufferId>) {
+        let buffer = buffer.and_then(|id| self.buffers.get(id.0));
+        self.context
+            .bind_buffer(Self::buffer_target(target), buffer.as_ref());
+    }
+    fn buffer_data_f32(&self, target: BufferTarget, data: &[f32]) {
+        unsafe {
+            let array_buf_view = js_sys::Float32Array::view(data);
+
+            self.context.buffer_data_with_array_buffer_view(
+                Self::buffer_target(target),
+                &array_buf_view,
+                WebGl2RenderingContext::STATIC_DRAW,
+            );
+        };
+    }
+    fn buffer_data_u32(&self, target: BufferTarget, data: &[u32]) {
+        unsafe {
+            let array_buf_view = js_sys::Uint32Array::view(data);
+
+            self.context.buffer_data_with_array_buffer_view(
+                Self::buffer_target(target),
+                &array_buf_view,
+                WebGl2RenderingContext::STATIC_DRAW,
+            );
+        };
+    }
+    fn create_vertex_array(&self) -> Result<VertexArrayId, String> {
+        let vao = self
+            .context
+            .create_vertex_array()
+            .ok_or("Could not create vertex array object")?;
+        Ok(VertexArrayId(self.vaos.add(vao)))
+    }
+    fn delete_vertex_array(&self, vao: VertexArrayId) {
+        if let Some(vao) = self.vaos.remove(vao.0) {
+            self.context.delete_vertex_array(Some(&vao));
+        }
+    }
+    fn bind_vertex_array(&self, vao: Option<VertexArrayId>) {
+        let vao = vao.and_then(|id| self.vaos.get(id.0));
+        self.context.bind_vertex_array(vao.as_ref());
+    }
+    fn vertex_attrib_pointer(&self, index: u32, size: i32) {
+        self.context.vertex_attrib_pointer_with_i32(
+            index,
+            size,
+            WebGl2RenderingContext::FLOAT,
+            false,
+            0,
+            0,
+        );
+    }
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.context.enable_vertex_attrib_array(index);
+    }
+    fn create_shader(&self, shader_type: ShaderType, source: &str) -> Result<ShaderId, String> {
+        let shader_type = match shader_type {
+            ShaderType::Vertex => WebGl2RenderingContext::VERTEX_SHADER,
+            ShaderType::Fragment => WebGl2RenderingContext::FRAGMENT_SHADER,
+        };
+        let context = &self.context;
+        let shader = context
+            .create_shader(shader_type)
+            .ok_or_else(|| "Unable to create a shader object".to_string())?;
+
+        context.shader_source(&shader, source);
+        context.compile_shader(&shader);
+
+        if context
+            .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(ShaderId(self.shaders.add(shader)))
+        } else {
+            Err(context
+                .get_shader_info_log(&shader)
+                .unwrap_or_else(|| "Unknown error creating shader".to_string()))
+        }
+    }
+    fn create_program(&self, shaders: &[ShaderId]) -> Result<ProgramId, String> {
+        let context = &self.context;
+        let program = context
+            .create_program()
+            .ok_or_else(|| "Unable to create the program".to_string())?;
+        for shader in shaders {
+            let shader = self.shaders.get(shader.0).ok_or("Unknown shader")?;
+            context.attach_shader(&program, &shader)
+        }
+        context.link_program(&program);
+
+        if context
+            .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            Ok(ProgramId(self.programs.add(program)))
+        } else {
+            Err(context
+                .get_program_info_log(&program)
+                .unwrap_or_else(|| "Unknown error creating the program".to_string()))
+        }
+    }
+    fn use_program(&self, program: Option<ProgramId>) {
+        let program = program.and_then(|id| self.programs.get(id.0));
+        self.context.use_program(program.as_ref());
+    }
+    fn get_uniform_location(&self, program: ProgramId, name: &str) -> Option<UniformLocation> {
+        let key = (program.0, name.to_string());
+        if let Some(&id) = self.uniform_ids.borrow().get(&key) {
+            return Some(id);
+        }
+        let program = self.programs.get(program.0)?;
+        let location = self.context.get_uniform_location(&program, name)?;
+        let id = UniformLocation(self.uniforms.add(location));
+        self.uniform_ids.borrow_mut().insert(key, id);
+        Some(id)
+    }
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, data: &[f32]) {
+        let location = location.and_then(|id| self.uniforms.get(id.0));
+        self.context
+            .uniform_matrix4fv_with_f32_array(location.as_ref(), transpose, data);
+    }
+    fn uniform4fv(&self, location: Option<UniformLocation>, data: &[f32]) {
+        let location = location.and_then(|id| self.uniforms.get(id.0));
+        self.context
+            .uniform4fv_with_f32_array(location.as_ref(), data);
+    }
+    fn create_texture(&self) -> Result<TextureId, String> {
+        let texture = self
+            .context
+            .create_texture()
+            .ok_or("cannot create a texture")?;
+        Ok(TextureId(self.textures.add(texture)))
+    }
+    fn delete_texture(&self, texture: TextureId) {
+        if let Some(texture) = self.textures.remove(texture.0) {
+            self.context.delete_texture(Some(&texture));
+        }
+    }
+    fn active_texture(&self, unit: u32) {
+        self.context
+            .active_texture(WebGl2RenderingContext::TEXTURE0 + unit);
+    }
+    fn bind_texture(&self, texture: Option<TextureId>) {
+        let texture = texture.and_then(|id| self.textures.get(id.0));
+        self.context
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, texture.as_ref());
+    }
+    fn tex_image_rgba(&self, width: u32, height: u32, pixels: &[u8]) -> Result<(), String> {
+        self.context
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                WebGl2RenderingContext::TEXTURE_2D,
+                0,
+                WebGl2RenderingContext::RGBA as i32,
+                width as i32,
+                height as i32,
+                0,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::UNSIGNED_BYTE,
+                Some(pixels),
+            )
+            .map_err(|_| "cannot upload the texture data".to_string())
+    }
+    fn texture_wrap_s(&self, wrap: TextureWrap) {
+        let wrap = match wrap {
+            TextureWrap::ClampToEdge => WebGl2RenderingContext::CLAMP_TO_EDGE,
+            TextureWrap::Repeat => WebGl2RenderingContext::REPEAT,
+        };
+        self.context.tex_parameteri(
+            WebGl2RenderingContext::TEXTURE_2D,
+            WebGl2RenderingContext::TEXTURE_WRAP_S,
+            wrap as i32,
+        );
+    }
+    fn generate_mipmap(&self) {
+        self.context
+            .generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
+    }
+    fn enable(&self, capability: Capability) {
+        let capability = match capability {
+            Capability::DepthTest => WebGl2RenderingContext::DEPTH_TEST,
+            Capability::Blend => {
+                self.context.blend_func(
+                    WebGl2RenderingContext::SRC_ALPHA,
+                    WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA,
+                );
+                WebGl2RenderingContext::BLEND
+            }
+        };
+        self.context.enable(capability);
+    }
+    fn disable(&self, capability: Capability) {
+        let capability = match capability {
+            Capability::DepthTest => WebGl2RenderingContext::DEPTH_TEST,
+            Capability::Blend => WebGl2RenderingContext::BLEND,
+        };
+        self.context.disable(capability);
+    }
+    fn line_width(&self, width: f32) {
+        self.context.line_width(width);
+    }
+    fn clear_color(&self, r: f32, g: f32, b: f32, a: f32) {
+        self.context.clear_color(r, g, b, a);
+    }
+    fn clear(&self, color: bool, depth: bool) {
+        let mut mask = 0;
+        if color {
+            mask |= WebGl2RenderingContext::COLOR_BUFFER_BIT;
+        }
+        if depth {
+            mask |= WebGl2RenderingContext::DEPTH_BUFFER_BIT;
+        }
+        self.context.clear(mask);
+    }
+    fn draw_elements(&self, mode: Primitive, count: i32) {
+        let mode = match mode {
+            Primitive::Triangles => WebGl2RenderingContext::TRIANGLES,
+            Primitive::LineLoop => WebGl2RenderingContext::LINE_LOOP,
+        };
+        self.context
+            .draw_elements_with_i32(mode, count, WebGl2RenderingContext::UNSIGNED_INT, 0);
+    }
+}
@@ -1,5 +1,7 @@
 use super::super::alg::{utils, Mat4, Vec4};
+use super::element::Rectangle;
 
+#[derive(Clone, Debug)]
 pub struct Camera {
     position: Vec4,
     pitch: f32,
@@ -29,10 +31,42 @@ impl Camera {
             zoom,
         }
     }
+    /// Camera facing the plane at `(x, y)` and showing as much of it as the
+    /// 2D `zoom`. `spread` is the tangent of half the vertical field of view
+    /// in perspective, `None` in orthographic.
+    pub fn facing(x: f32, y: f32, zoom: f32, aspect_ratio: f32, spread: Option<f32>) -> Self {
+        let z = spread.map_or(1.0, |spread| zoom / aspect_ratio / spread);
+        Camera::new(Vec4([x, y, z, 1.0]), 0.0, 0.0, 0.0, zoom)
+    }
+    /// Camera facing `rect` with all of it in view, `padding` is the part of
+    /// its size left free on each side.
+    pub fn fit_to(rect: &Rectangle, aspect_ratio: f32, padding: f32, spread: Option<f32>) -> Self {
+        let (width, height) = rect.dimensions;
+        let zoom = (width * 0.5).max(height * 0.5 * aspect_ratio) * (1.0 + 2.0 * padding);
+        let (x, y) = rect.get_center();
+        Camera::facing(x, y, zoom, aspect_ratio, spread)
+    }
+    /// Camera `t` of the way from this one to `to`. The zoom changes
+    /// geometrically so the view grows at a steady pace, and the yaw turns
+    /// the short way round.
+    pub fn interpolate(&self, to: &Camera, t: f32) -> Self {
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        let turn = (to.yaw - self.yaw + 180.0).rem_euclid(360.0) - 180.0;
+        Camera {
+            position: self.position + (to.position - self.position).mul(t),
+            pitch: lerp(self.pitch, to.pitch),
+            yaw: self.yaw + turn * t,
+            roll: lerp(self.roll, to.roll),
+            zoom: self.zoom * (to.zoom / self.zoom).powf(t),
+        }
+    }
     #[allow(dead_code)]
     pub fn get_zoom(&self) -> f32 {
         self.zoom
     }
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
     #[allow(dead_code)]
     pub fn get_position(&self) -> Vec4 {
         self.position
@@ -70,13 +104,13 @@ impl Camera {
     }
     #[allow(dead_code)]
     pub fn mouse_move_2d(&mut self, _dx: f32, dy: f32) {
-        let increment = (1.05 as f32).powf(-dy * 0.5);
+        let increment = 1.05_f32.powf(-dy * 0.5);
         self.increment_zoom(increment);
     }
     #[allow(dead_code)]
     pub fn mouse_move_3d(&mut self, dx: f32, dy: f32) {
         self.increment_pitch(dy * -0.1);
-        self.increment_yaw(dx as f32 * 0.1);
+        self.increment_yaw(dx * 0.1);
     }
     #[allow(dead_code)]
     pub fn key_move_2d(&mut self, key: &str) {
@@ -121,3 +155,117 @@ impl Camera {
         utils::view_matrix(&self.position, self.pitch, self.yaw, self.roll)
     }
 }
+
+/// Pace of an animation, maps the elapsed part of it to the part done.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn from_name(name: &str) -> Result<Easing, String> {
+        match name {
+            "linear" => Ok(Easing::Linear),
+            "ease-in" => Ok(Easing::EaseIn),
+            "ease-out" => Ok(Easing::EaseOut),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            _ => Err(format!("unknown easing {}", name)),
+        }
+    }
+    /// Cubic curves, `t` is clamped between 0 and 1.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+/// Animated move from one camera to another.
+#[derive(Clone, Debug)]
+pub struct Flight {
+    from: Camera,
+    to: Camera,
+    /// In seconds.
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl Flight {
+    pub fn new(from: Camera, to: Camera, duration: f32, easing: Easing) -> Self {
+        Flight {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing,
+        }
+    }
+    /// Camera `dt` seconds further along the flight.
+    pub fn advance(&mut self, dt: f32) -> Camera {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        if self.is_done() {
+            return self.to.clone();
+        }
+        let t = self.easing.apply(self.elapsed / self.duration);
+        self.from.interpolate(&self.to, t)
+    }
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[test]
+fn fitted_cameras_frame_the_rectangle_in_both_projections() {
+    let rect = Rectangle::new(10.0, 20.0, 100.0, 40.0);
+    // wide enough for the width, the height has room to spare
+    let flat = Camera::fit_to(&rect, 2.0, 0.0, None);
+    assert_eq!(flat.get_position().data(), &[60.0, 40.0, 1.0, 1.0]);
+    assert_eq!(flat.get_zoom(), 50.0);
+    // a wide view is bound by the height, with the padding around it
+    let wide = Camera::fit_to(&rect, 4.0, 0.25, None);
+    assert_eq!(wide.get_zoom(), 120.0);
+    // in perspective the half height of the view is 25 at the distance of
+    // the plane, so 25 / distance is the spread
+    let deep = Camera::fit_to(&rect, 2.0, 0.0, Some(0.5));
+    assert_eq!(deep.get_position()[2], 50.0);
+    assert_eq!(deep.get_zoom(), flat.get_zoom());
+}
+
+#[test]
+fn flights_ease_between_the_cameras() {
+    assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    assert_eq!(Easing::EaseIn.apply(0.5), 0.125);
+    assert_eq!(Easing::EaseOut.apply(0.5), 0.875);
+    for easing in [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ] {
+        assert_eq!(easing.apply(-1.0), 0.0);
+        assert_eq!(easing.apply(2.0), 1.0);
+    }
+    assert!(Easing::from_name("bounce").is_err());
+
+    let from = Camera::new(Vec4([0.0, 0.0, 1.0, 1.0]), 0.0, 350.0, 0.0, 100.0);
+    let to = Camera::new(Vec4([40.0, -20.0, 1.0, 1.0]), 0.0, 10.0, 0.0, 400.0);
+    let mut flight = Flight::new(from, to, 2.0, Easing::Linear);
+    let halfway = flight.advance(1.0);
+    assert_eq!(halfway.get_position().data(), &[20.0, -10.0, 1.0, 1.0]);
+    assert_eq!(halfway.get_zoom(), 200.0);
+    assert_eq!(halfway.get_yaw(), 360.0);
+    assert!(!flight.is_done());
+    let end = flight.advance(5.0);
+    assert!(flight.is_done());
+    assert_eq!(end.get_position().data(), &[40.0, -20.0, 1.0, 1.0]);
+    assert_eq!(end.get_zoom(), 400.0);
+}
@@ -1,105 +1,255 @@
-use super::meshes::*;
-use super::texture::Texture;
-use super::texture_coords::TextureCoords;
+use super::attributes::Attributes;
+use super::backend::GraphicsBackend;
+use super::meshes;
+use super::scene::ElementId;
 use super::vao::Vao;
-use crate::Renderer;
-use wasm_bindgen::prelude::*;
+use crate::alg::{Mat4, Polygon, Vec4};
+use std::rc::Rc;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rectangle {
     pub down_left_corner: (f32, f32),
     pub dimensions: (f32, f32),
 }
 
 impl Rectangle {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Rectangle {
+            down_left_corner: (x, y),
+            dimensions: (width, height),
+        }
+    }
+    pub fn bounding(polygon: &Polygon) -> Self {
+        let (min, max) = polygon.bounding_box();
+        Self::new(min.0, min.1, max.0 - min.0, max.1 - min.1)
+    }
+    /// Square of side `size` centered on `(x, y)`.
+    pub fn centered(x: f32, y: f32, size: f32) -> Self {
+        Self::new(x - size / 2.0, y - size / 2.0, size, size)
+    }
+    #[allow(dead_code)]
     pub fn get_height(&self) -> f32 {
         self.dimensions.1
     }
+    #[allow(dead_code)]
     pub fn get_width(&self) -> f32 {
         self.dimensions.0
     }
+    pub fn get_center(&self) -> (f32, f32) {
+        (
+            self.down_left_corner.0 + self.dimensions.0 / 2.0,
+            self.down_left_corner.1 + self.dimensions.1 / 2.0,
+        )
+    }
     pub fn contains(&self, x: f32, y: f32) -> bool {
         (self.down_left_corner.0..self.down_left_corner.0 + self.dimensions.0).contains(&x)
             && (self.down_left_corner.1..self.down_left_corner.1 + self.dimensions.1).contains(&y)
     }
+    /// Whether `other` lies entirely inside this rectangle, borders included.
+    pub fn contains_rect(&self, other: &Rectangle) -> bool {
+        let (x, y) = self.down_left_corner;
+        let (w, h) = self.dimensions;
+        let (ox, oy) = other.down_left_corner;
+        let (ow, oh) = other.dimensions;
+        ox >= x && oy >= y && ox + ow <= x + w && oy + oh <= y + h
+    }
+    /// Whether the two rectangles overlap, touching borders included.
+    pub fn intersects(&self, other: &Rectangle) -> bool {
+        let (x, y) = self.down_left_corner;
+        let (w, h) = self.dimensions;
+        let (ox, oy) = other.down_left_corner;
+        let (ow, oh) = other.dimensions;
+        ox <= x + w && x <= ox + ow && oy <= y + h && y <= oy + oh
+    }
 }
 
 pub struct Element {
-    pub vao: Vao,
+    pub vao: Option<Vao>,
     pub frame: Vao,
     pub rect: Rectangle,
-    hover: bool,
+    /// Size of the rectangle the meshes were built with, used to stretch
+    /// them over `rect`.
+    mesh_dimensions: (f32, f32),
+    pub parent: Option<ElementId>,
+    /// Class of an area or a point, see `LabelClass`.
+    pub label: Option<u32>,
+    /// Values of the attributes of the label class.
+    pub attributes: Attributes,
+    /// Color and opacity of an area over the plane, its label color when
+    /// `None`.
+    pub fill: Option<[f32; 4]>,
     pub kind: ElementKind,
 }
 
 impl Element {
     pub fn plane(vao: Vao, frame: Vao, width: f32, height: f32) -> Self {
         Element {
-            vao,
+            vao: Some(vao),
             frame,
             rect: Rectangle {
                 dimensions: (width, height),
                 down_left_corner: (-width / 2.0, -height / 2.0),
             },
-            hover: false,
-            kind: ElementKind::PLANE(Plane { areas: Vec::new() }),
+            mesh_dimensions: (width, height),
+            parent: None,
+            label: None,
+            attributes: Attributes::new(),
+            fill: None,
+            kind: ElementKind::PLANE(Plane {
+                image_name: String::new(),
+                areas: Vec::new(),
+            }),
         }
     }
-    pub fn area(vao: Vao, frame: Vao, width: f32, height: f32) -> Self {
-        Element {
+    /// Area outlined by `polygon`, in world coordinates. The meshes are built
+    /// around the center of its bounding box.
+    pub fn area(backend: &Rc<dyn GraphicsBackend>, polygon: Polygon) -> Result<Self, String> {
+        let rect = Rectangle::bounding(&polygon);
+        let (width, height) = rect.dimensions;
+        // the meshes are stretched by the ratio of the sizes
+        if !(width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite()) {
+            return Err("the area has no size".into());
+        }
+        let (x, y) = rect.get_center();
+        let local = polygon.translate(-x, -y);
+        let vao = Vao::new(backend, meshes::polygon(&local))?;
+        let frame = Vao::new(backend, meshes::stroke_polygon(&local))?;
+        Ok(Element {
+            vao: Some(vao),
+            frame,
+            rect,
+            mesh_dimensions: rect.dimensions,
+            parent: None,
+            label: None,
+            attributes: Attributes::new(),
+            fill: None,
+            kind: ElementKind::AREA(Area {
+                polygon,
+                points: Vec::new(),
+            }),
+        })
+    }
+    pub fn point(
+        backend: &Rc<dyn GraphicsBackend>,
+        x: f32,
+        y: f32,
+        value: f32,
+    ) -> Result<Self, String> {
+        let frame = Vao::new(backend, meshes::stroke_rectangle(1.0, 1.0))?;
+        Ok(Element {
+            vao: None,
+            frame,
+            rect: Rectangle::centered(x, y, Point::SIZE),
+            mesh_dimensions: (1.0, 1.0),
+            parent: None,
+            label: None,
+            attributes: Attributes::new(),
+            fill: None,
+            kind: ElementKind::POINT(Point { value }),
+        })
+    }
+    /// Moves the element, its meshes are built around its center so they do
+    /// not change.
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        let (x, y) = self.rect.down_left_corner;
+        self.rect.down_left_corner = (x + dx, y + dy);
+        if let ElementKind::AREA(ref mut area) = self.kind {
+            area.polygon = area.polygon.translate(dx, dy);
+        }
+    }
+    /// Gives an area a new outline, rebuilding its meshes.
+    pub fn set_polygon(
+        &mut self,
+        backend: &Rc<dyn GraphicsBackend>,
+        polygon: Polygon,
+    ) -> Result<(), String> {
+        if !matches!(self.kind, ElementKind::AREA(_)) {
+            return Err("only areas have a polygon".into());
+        }
+        let Element {
             vao,
             frame,
-            rect: Rectangle {
-                dimensions: (width, height),
-                down_left_corner: (-width / 2.0, -height / 2.0),
-            },
-            hover: false,
-            kind: ElementKind::PLANE(Plane { areas: Vec::new() }),
+            rect,
+            mesh_dimensions,
+            kind,
+            ..
+        } = Element::area(backend, polygon)?;
+        if let (ElementKind::AREA(area), ElementKind::AREA(new)) = (&mut self.kind, kind) {
+            area.polygon = new.polygon;
+        }
+        self.vao = vao;
+        self.frame = frame;
+        self.rect = rect;
+        self.mesh_dimensions = mesh_dimensions;
+        Ok(())
+    }
+    /// Hit test, the bounding box rejects most of the points before the
+    /// polygon of an area is checked.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        if !self.rect.contains(x, y) {
+            return false;
+        }
+        match self.kind {
+            ElementKind::AREA(ref area) => area.polygon.contains(x, y),
+            _ => true,
         }
     }
+    /// How much the meshes are stretched to fill the bounds.
+    pub fn get_scale(&self) -> (f32, f32) {
+        let (width, height) = self.rect.dimensions;
+        let (mesh_width, mesh_height) = self.mesh_dimensions;
+        (width / mesh_width, height / mesh_height)
+    }
+    /// Model matrix placing the meshes, built around the origin, over `rect`.
+    pub fn get_transformation_matrix(&self) -> Mat4 {
+        let (x, y) = self.rect.get_center();
+        let (scale_x, scale_y) = self.get_scale();
+        Mat4::identity()
+            .translate(&Vec4([x, y, 0.0, 1.0]))
+            .scale(&Vec4([scale_x, scale_y, 1.0, 1.0]))
+    }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum ElementKind {
     PLANE(Plane),
     AREA(Area),
     POINT(Point),
 }
 impl Element {
-    pub fn select(&self, x: f32, y: f32) -> Option<&Element> {
-        if !self.rect.contains(x, y) {
-            return None;
+    pub fn children(&self) -> &[ElementId] {
+        match self.kind {
+            ElementKind::PLANE(ref plane) => &plane.areas,
+            ElementKind::AREA(ref area) => &area.points,
+            ElementKind::POINT(_) => &[],
+        }
+    }
+    pub fn children_mut(&mut self) -> Option<&mut Vec<ElementId>> {
+        match self.kind {
+            ElementKind::PLANE(ref mut plane) => Some(&mut plane.areas),
+            ElementKind::AREA(ref mut area) => Some(&mut area.points),
+            ElementKind::POINT(_) => None,
         }
-        let element = match self.kind {
-            ElementKind::PLANE(ref plane) => {
-                let inside = plane
-                    .areas
-                    .iter()
-                    .find(|ref area| area.select(x, y).is_some());
-                inside.or(Some(self))
-            }
-            ElementKind::AREA(ref plane) => {
-                let inside = plane
-                    .points
-                    .iter()
-                    .find(|ref point| point.select(x, y).is_some());
-                inside.or(Some(self))
-            }
-            ElementKind::POINT(ref _point) => Some(self),
-        };
-        element
     }
 }
 
 pub struct Plane {
-    pub areas: Vec<Element>,
+    pub image_name: String,
+    pub areas: Vec<ElementId>,
 }
 
-struct Area {
-    points: Vec<Element>,
+pub struct Area {
+    pub polygon: Polygon,
+    pub points: Vec<ElementId>,
 }
 
-struct Point {
-    value: f32,
+pub struct Point {
+    pub value: f32,
+}
+
+impl Point {
+    /// Side, in world units, of the square used to draw and pick a point.
+    pub const SIZE: f32 = 6.0;
 }
 
 #[test]
@@ -114,3 +264,16 @@ fn test() {
     // println!("{}", rect.contains(9.0, 10.));
     println!("{}", rect.contains(0.0, 11.));
 }
+
+#[test]
+fn areas_without_a_size_are_refused() {
+    use super::backend::HeadlessBackend;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    for &(width, height) in &[(0.0, 0.0), (f32::NAN, 5.0), (5.0, 0.0)] {
+        let polygon = Polygon::rectangle(0.0, 0.0, width, height);
+        assert!(Element::area(&backend, polygon).is_err());
+    }
+    let area = Element::area(&backend, Polygon::rectangle(0.0, 0.0, 4.0, 2.0)).unwrap();
+    assert_eq!(area.get_scale(), (1.0, 1.0));
+}
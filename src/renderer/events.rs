@@ -0,0 +1,135 @@
+use super::Renderer;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, KeyboardEvent, MouseEvent, WheelEvent};
+
+/// Forwards the canvas input events to the renderer. The listeners only hold
+/// a weak reference, so they become no-ops once the renderer is dropped.
+pub fn listen(renderer: &Rc<RefCell<Renderer>>, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
+        if let Some(renderer) = weak.upgrade() {
+            // the right button pans the view
+            if event.buttons() & 2 != 0 {
+                renderer
+                    .borrow_mut()
+                    .pan(event.movement_x() as f32, event.movement_y() as f32);
+                return;
+            }
+            renderer.borrow_mut().mouse_move(
+                event.offset_x(),
+                event.offset_y(),
+                event.movement_x() as f32,
+                event.movement_y() as f32,
+                event.ctrl_key(),
+            );
+            // dragging edits the tree
+            Renderer::notify_change(&renderer);
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
+        if let (Some(renderer), 0) = (weak.upgrade(), event.button()) {
+            renderer
+                .borrow_mut()
+                .mouse_down(event.offset_x(), event.offset_y(), event.shift_key());
+            Renderer::notify_change(&renderer);
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure =
+        Closure::wrap(Box::new(
+            move |event: MouseEvent| match (weak.upgrade(), event.button()) {
+                (Some(renderer), 0) => {
+                    renderer
+                        .borrow_mut()
+                        .mouse_up(event.offset_x(), event.offset_y());
+                    Renderer::notify_change(&renderer);
+                }
+                (Some(renderer), 2) => renderer.borrow_mut().pan_end(),
+                _ => {}
+            },
+        ) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
+        if let (Some(renderer), 0) = (weak.upgrade(), event.button()) {
+            renderer
+                .borrow_mut()
+                .double_click(event.offset_x(), event.offset_y());
+            Renderer::notify_change(&renderer);
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("dblclick", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    // keeps the menu from opening at the end of a pan
+    let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
+        event.prevent_default();
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("contextmenu", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |event: WheelEvent| {
+        if let Some(renderer) = weak.upgrade() {
+            event.prevent_default();
+            // lines and pages as pixels
+            let delta = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => event.delta_y() * 16.0,
+                WheelEvent::DOM_DELTA_PAGE => event.delta_y() * 800.0,
+                _ => event.delta_y(),
+            };
+            // trackpad pinches come with ctrl held
+            renderer.borrow_mut().wheel(
+                event.offset_x(),
+                event.offset_y(),
+                delta as f32,
+                event.ctrl_key(),
+            );
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("wheel", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+        if let Some(renderer) = weak.upgrade() {
+            renderer
+                .borrow_mut()
+                .key_down(event.key().as_ref(), event.ctrl_key() || event.meta_key());
+            Renderer::notify_change(&renderer);
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+        if let Some(renderer) = weak.upgrade() {
+            renderer.borrow_mut().key_up(event.key().as_ref());
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("keyup", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    let weak = Rc::downgrade(renderer);
+    let closure = Closure::wrap(Box::new(move |_: JsValue| {
+        if let Some(renderer) = weak.upgrade() {
+            renderer.borrow_mut().release_keys();
+        }
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("blur", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    Ok(())
+}
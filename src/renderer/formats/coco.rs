@@ -0,0 +1,302 @@
+use super::{AreaData, ImageFrame, PointData};
+use crate::alg::Polygon;
+use crate::renderer::attributes::{self, Attributes};
+use crate::renderer::labels::Labels;
+use serde::{Deserialize, Serialize};
+
+const IMAGE_ID: u64 = 1;
+/// Category of the areas without a label, when no class is defined.
+const CATEGORY_ID: u64 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Coco {
+    pub images: Vec<Image>,
+    pub annotations: Vec<Annotation>,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Image {
+    pub id: u64,
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Category {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub supercategory: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Segmentation {
+    Polygons(Vec<Vec<f32>>),
+    /// Run length encoded masks are read but not supported, the bounding box
+    /// is used instead.
+    Rle(serde_json::Value),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub id: u64,
+    pub image_id: u64,
+    pub category_id: u64,
+    pub bbox: [f32; 4],
+    pub segmentation: Segmentation,
+    pub area: f32,
+    #[serde(default)]
+    pub iscrowd: u8,
+    /// `x, y, visibility` triplets, every exported point is labeled and
+    /// visible.
+    #[serde(default)]
+    pub keypoints: Vec<f32>,
+    #[serde(default)]
+    pub num_keypoints: u32,
+    /// Values of the points in the order of `keypoints`. It is not part of
+    /// COCO, other tools ignore it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub point_values: Vec<f32>,
+    /// Attributes of the area, as written by CVAT.
+    #[serde(default, skip_serializing_if = "Attributes::is_empty")]
+    pub attributes: Attributes,
+}
+
+/// Label classes become categories with the same id. Areas without a label
+/// share an "area" category, after the classes.
+pub fn export(image: &ImageFrame, areas: &[AreaData], labels: &Labels) -> Coco {
+    let unlabeled = labels
+        .get_classes()
+        .iter()
+        .map(|class| class.id as u64 + 1)
+        .max()
+        .unwrap_or(CATEGORY_ID);
+    let category_of = |area: &AreaData| {
+        area.label
+            .filter(|&label| labels.get(label).is_some())
+            .map_or(unlabeled, u64::from)
+    };
+    let mut categories: Vec<Category> = labels
+        .get_classes()
+        .iter()
+        .map(|class| Category {
+            id: class.id as u64,
+            name: class.name.clone(),
+            supercategory: String::new(),
+        })
+        .collect();
+    if categories.is_empty() || areas.iter().any(|area| category_of(area) == unlabeled) {
+        categories.push(Category {
+            id: unlabeled,
+            name: "area".into(),
+            supercategory: String::new(),
+        });
+    }
+    let annotations = areas
+        .iter()
+        .enumerate()
+        .map(|(i, area)| {
+            let segmentation: Vec<f32> = area
+                .polygon
+                .get_vertices()
+                .iter()
+                .flat_map(|&(x, y)| {
+                    let (x, y) = image.to_pixels(x, y);
+                    vec![x, y]
+                })
+                .collect();
+            let [left, top, right, bottom] = image.to_pixel_box(&area.polygon);
+            let keypoints = area
+                .points
+                .iter()
+                .flat_map(|point| {
+                    let (x, y) = image.to_pixels(point.x, point.y);
+                    vec![x, y, 2.0]
+                })
+                .collect();
+            Annotation {
+                id: i as u64 + 1,
+                image_id: IMAGE_ID,
+                category_id: category_of(area),
+                bbox: [left, top, right - left, bottom - top],
+                area: Polygon::new(pixel_vertices(&segmentation))
+                    .map(|polygon| polygon.signed_area().abs())
+                    .unwrap_or(0.0),
+                segmentation: Segmentation::Polygons(vec![segmentation]),
+                iscrowd: 0,
+                keypoints,
+                num_keypoints: area.points.len() as u32,
+                point_values: area.points.iter().map(|point| point.value).collect(),
+                attributes: area.attributes.clone(),
+            }
+        })
+        .collect();
+    Coco {
+        images: vec![Image {
+            id: IMAGE_ID,
+            file_name: image.file_name.clone(),
+            width: image.width,
+            height: image.height,
+        }],
+        annotations,
+        categories,
+    }
+}
+
+fn pixel_vertices(coords: &[f32]) -> Vec<(f32, f32)> {
+    coords.chunks_exact(2).map(|v| (v[0], v[1])).collect()
+}
+
+/// Rebuilds the areas annotated on `image`. The image is looked up by file
+/// name, a file with a single image is used whatever its name. Categories
+/// are matched to the label classes by name.
+pub fn import(coco: &Coco, image: &ImageFrame, labels: &Labels) -> Result<Vec<AreaData>, String> {
+    let entry = match coco.images.iter().find(|i| i.file_name == image.file_name) {
+        Some(entry) => entry,
+        None if coco.images.len() == 1 => &coco.images[0],
+        None => return Err(format!("no image named {} in the file", image.file_name)),
+    };
+    let frame = image.with_size(entry.width, entry.height);
+    let to_world = |coords: Vec<(f32, f32)>| -> Vec<(f32, f32)> {
+        coords
+            .into_iter()
+            .map(|(x, y)| frame.to_world(x, y))
+            .collect()
+    };
+    let mut areas = Vec::new();
+    for annotation in coco.annotations.iter().filter(|a| a.image_id == entry.id) {
+        let polygon = match annotation.segmentation {
+            Segmentation::Polygons(ref polygons) if !polygons.is_empty() => {
+                Polygon::new(to_world(pixel_vertices(&polygons[0])))
+            }
+            _ => {
+                let [x, y, width, height] = annotation.bbox;
+                frame.to_world_box([x, y, x + width, y + height])
+            }
+        }
+        .map_err(|e| format!("annotation {}: {}", annotation.id, e))?;
+
+        let points = annotation
+            .keypoints
+            .chunks_exact(3)
+            .enumerate()
+            // a visibility of 0 marks a keypoint that is not labeled
+            .filter(|(_, keypoint)| keypoint[2] != 0.0)
+            .map(|(i, keypoint)| {
+                let (x, y) = frame.to_world(keypoint[0], keypoint[1]);
+                let value = annotation
+                    .point_values
+                    .get(i)
+                    .copied()
+                    // other tools only write the visibility, not a value
+                    .unwrap_or(0.0);
+                PointData {
+                    x,
+                    y,
+                    value,
+                    label: None,
+                    attributes: Attributes::new(),
+                }
+            })
+            .collect();
+        let class = coco
+            .categories
+            .iter()
+            .find(|category| category.id == annotation.category_id)
+            .and_then(|category| labels.get_by_name(&category.name));
+        // attributes the class does not define are dropped
+        let attributes = class.map_or_else(Attributes::new, |class| {
+            attributes::retain_valid(&class.attributes, &annotation.attributes)
+        });
+        areas.push(AreaData {
+            polygon,
+            points,
+            label: class.map(|class| class.id),
+            attributes,
+            fill: None,
+        });
+    }
+    Ok(areas)
+}
+
+#[test]
+fn export_converts_to_top_left_pixels() {
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-50.0, 15.0, 10.0, 10.0))
+        .unwrap();
+    renderer.create_point(area, -45.0, 20.0, 0.0).unwrap();
+
+    let coco: Coco = serde_json::from_str(&renderer.export_coco().unwrap()).unwrap();
+    assert_eq!(coco.images[0].width, 100);
+    assert_eq!(coco.images[0].height, 50);
+    let annotation = &coco.annotations[0];
+    assert_eq!(annotation.bbox, [0.0, 0.0, 10.0, 10.0]);
+    assert_eq!(annotation.area, 100.0);
+    assert_eq!(
+        annotation.segmentation,
+        Segmentation::Polygons(vec![vec![0.0, 10.0, 10.0, 10.0, 10.0, 0.0, 0.0, 0.0]])
+    );
+    assert_eq!(annotation.keypoints, vec![5.0, 5.0, 2.0]);
+    assert_eq!(annotation.point_values, vec![0.0]);
+}
+
+#[test]
+fn import_rebuilds_exported_areas() {
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    let triangle = Polygon::new(vec![(-20.0, -20.0), (30.0, -10.0), (0.0, 20.0)]).unwrap();
+    let area = renderer.create_area(triangle).unwrap();
+    renderer.create_point(area, 0.0, 0.0, 4.5).unwrap();
+    renderer
+        .create_area(Polygon::rectangle(-50.0, -25.0, 20.0, 20.0))
+        .unwrap();
+    let json = renderer.export_coco().unwrap();
+    let exported = super::collect_areas(renderer.get_scene());
+
+    let (_headless, mut other) = super::super::headless_renderer_with_image(100, 50);
+    other
+        .create_area(Polygon::rectangle(0.0, 0.0, 5.0, 5.0))
+        .unwrap();
+    other.import_coco(&json).unwrap();
+    assert_eq!(super::collect_areas(other.get_scene()), exported);
+
+    // files written against a resized image are scaled back
+    let mut coco: Coco = serde_json::from_str(&json).unwrap();
+    coco.images[0].width = 200;
+    coco.images[0].height = 100;
+    coco.annotations[1].segmentation = Segmentation::Polygons(vec![]);
+    coco.annotations[1].bbox = [0.0, 60.0, 40.0, 40.0];
+    other
+        .import_coco(&serde_json::to_string(&coco).unwrap())
+        .unwrap();
+    let areas = super::collect_areas(other.get_scene());
+    assert_eq!(
+        areas[1].polygon.bounding_box(),
+        ((-50.0, -25.0), (-30.0, -5.0))
+    );
+}
+
+#[test]
+fn keypoints_from_other_tools_get_no_value() {
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-20.0, -20.0, 40.0, 40.0))
+        .unwrap();
+    renderer.create_point(area, 0.0, 0.0, 4.5).unwrap();
+    renderer.create_point(area, 10.0, 10.0, 3.0).unwrap();
+    let mut coco: Coco = serde_json::from_str(&renderer.export_coco().unwrap()).unwrap();
+    coco.annotations[0].point_values.clear();
+    assert_eq!(coco.annotations[0].keypoints[2], 2.0);
+
+    let (_headless, mut other) = super::super::headless_renderer_with_image(100, 50);
+    other
+        .import_coco(&serde_json::to_string(&coco).unwrap())
+        .unwrap();
+    let areas = super::collect_areas(other.get_scene());
+    let values: Vec<f32> = areas[0].points.iter().map(|point| point.value).collect();
+    assert_eq!(values, vec![0.0, 0.0]);
+}
@@ -0,0 +1,154 @@
+pub mod coco;
+pub mod project;
+pub mod voc;
+pub mod yolo;
+
+use super::attributes::Attributes;
+use super::element::{Element, ElementKind, Rectangle};
+use super::scene::Scene;
+use crate::alg::Polygon;
+
+/// Copy of a point detached from the graphics backend, in world coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointData {
+    pub x: f32,
+    pub y: f32,
+    pub value: f32,
+    pub label: Option<u32>,
+    pub attributes: Attributes,
+}
+
+/// Copy of an area and its points detached from the graphics backend, in
+/// world coordinates. Exporters read it and importers produce it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AreaData {
+    pub polygon: Polygon,
+    pub points: Vec<PointData>,
+    pub label: Option<u32>,
+    pub attributes: Attributes,
+    /// Color set for the area, `None` keeps its label's.
+    pub fill: Option<[f32; 4]>,
+}
+
+/// The image shown by the plane. World coordinates are centered on the plane
+/// with y pointing up, pixels start from the top left corner of the image
+/// with y pointing down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageFrame {
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+    rect: Rectangle,
+}
+
+impl ImageFrame {
+    pub fn of_plane(scene: &Scene) -> Result<Self, String> {
+        let plane = scene
+            .get_plane()
+            .and_then(|id| scene.get(id))
+            .ok_or("the plane is not created")?;
+        let file_name = match plane.kind {
+            ElementKind::PLANE(ref plane) => plane.image_name.clone(),
+            _ => String::new(),
+        };
+        let (width, height) = plane.rect.dimensions;
+        Ok(ImageFrame {
+            file_name,
+            width: width.round() as u32,
+            height: height.round() as u32,
+            rect: plane.rect,
+        })
+    }
+    /// Same image stretched to a different size in pixels, for files written
+    /// against a resized copy of it.
+    pub fn with_size(&self, width: u32, height: u32) -> Self {
+        ImageFrame {
+            width,
+            height,
+            ..self.clone()
+        }
+    }
+    /// Size of the plane in world units.
+    pub fn get_dimensions(&self) -> (f32, f32) {
+        self.rect.dimensions
+    }
+    fn scale(&self) -> (f32, f32) {
+        (
+            self.width as f32 / self.rect.dimensions.0,
+            self.height as f32 / self.rect.dimensions.1,
+        )
+    }
+    pub fn to_pixels(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sx, sy) = self.scale();
+        let (left, bottom) = self.rect.down_left_corner;
+        let top = bottom + self.rect.dimensions.1;
+        ((x - left) * sx, (top - y) * sy)
+    }
+    pub fn to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let (sx, sy) = self.scale();
+        let (left, bottom) = self.rect.down_left_corner;
+        let top = bottom + self.rect.dimensions.1;
+        (left + x / sx, top - y / sy)
+    }
+    /// Bounding box of `polygon` in pixels, as `[left, top, right, bottom]`.
+    pub fn to_pixel_box(&self, polygon: &Polygon) -> [f32; 4] {
+        let ((min_x, min_y), (max_x, max_y)) = polygon.bounding_box();
+        let (left, top) = self.to_pixels(min_x, max_y);
+        let (right, bottom) = self.to_pixels(max_x, min_y);
+        [left, top, right, bottom]
+    }
+    /// Rectangle in world coordinates covering a box given in pixels.
+    pub fn to_world_box(&self, [left, top, right, bottom]: [f32; 4]) -> Result<Polygon, String> {
+        let (x0, y0) = self.to_world(left, bottom);
+        let (x1, y1) = self.to_world(right, top);
+        if x1 <= x0 || y1 <= y0 {
+            return Err(format!("empty box {} {} {} {}", left, top, right, bottom));
+        }
+        Ok(Polygon::rectangle(x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+/// Areas of the plane in the order they were added.
+pub fn collect_areas(scene: &Scene) -> Vec<AreaData> {
+    let mut areas = Vec::new();
+    for &id in scene.get_areas() {
+        let (polygon, label, attributes, fill) = match scene.get(id) {
+            Some(Element {
+                kind: ElementKind::AREA(area),
+                label,
+                attributes,
+                fill,
+                ..
+            }) => (area.polygon.clone(), *label, attributes.clone(), *fill),
+            _ => continue,
+        };
+        let points = scene
+            .get_points(id)
+            .iter()
+            .filter_map(|&point| {
+                let element = scene.get(point)?;
+                match element.kind {
+                    ElementKind::POINT(ref point) => {
+                        let (x, y) = element.rect.get_center();
+                        Some(PointData {
+                            x,
+                            y,
+                            value: point.value,
+                            label: element.label,
+                            attributes: element.attributes.clone(),
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        areas.push(AreaData {
+            polygon,
+            points,
+            label,
+            attributes,
+            fill,
+        });
+    }
+    areas
+}
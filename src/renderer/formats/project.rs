@@ -0,0 +1,396 @@
+use super::{AreaData, PointData};
+use crate::alg::{Polygon, Vec4};
+use crate::renderer::attributes::Attributes;
+use crate::renderer::labels::LabelClass;
+use crate::renderer::Camera;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Upgrades a project document by one version.
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[i]` upgrades a document from version `i + 1` to `i + 2`.
+/// Changing the document means bumping the version by adding a migration.
+const MIGRATIONS: &[Migration] = &[add_labels, add_attributes, add_fills];
+
+pub const VERSION: u64 = MIGRATIONS.len() as u64 + 1;
+
+/// Native document saving a whole session. Coordinates are in world units,
+/// centered on the plane.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Project {
+    pub version: u64,
+    pub image: ProjectImage,
+    pub camera: CameraState,
+    pub labels: Vec<LabelClass>,
+    pub areas: Vec<ProjectArea>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectImage {
+    pub file_name: String,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CameraState {
+    pub position: [f32; 3],
+    pub pitch: f32,
+    pub yaw: f32,
+    pub roll: f32,
+    pub zoom: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectArea {
+    pub polygon: Vec<[f32; 2]>,
+    pub points: Vec<ProjectPoint>,
+    pub label: Option<u32>,
+    pub attributes: Attributes,
+    pub fill: Option<[f32; 4]>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectPoint {
+    pub x: f32,
+    pub y: f32,
+    pub value: f32,
+    pub label: Option<u32>,
+    pub attributes: Attributes,
+}
+
+impl CameraState {
+    pub fn of(camera: &Camera) -> Self {
+        let position = camera.get_position();
+        CameraState {
+            position: [position[0], position[1], position[2]],
+            pitch: camera.get_pitch(),
+            yaw: camera.get_yaw(),
+            roll: camera.get_roll(),
+            zoom: camera.get_zoom(),
+        }
+    }
+    /// Fails unless the position and angles are finite and the zoom is
+    /// positive.
+    pub fn to_camera(&self) -> Result<Camera, String> {
+        let [x, y, z] = self.position;
+        let finite = [x, y, z, self.pitch, self.yaw, self.roll, self.zoom]
+            .iter()
+            .all(|value| value.is_finite());
+        if !finite || self.zoom <= 0.0 {
+            return Err("the camera of the project is invalid".into());
+        }
+        Ok(Camera::new(
+            Vec4([x, y, z, 1.0]),
+            self.pitch,
+            self.yaw,
+            self.roll,
+            self.zoom,
+        ))
+    }
+}
+
+impl Project {
+    pub fn new(
+        image: ProjectImage,
+        camera: &Camera,
+        labels: &[LabelClass],
+        areas: &[AreaData],
+    ) -> Self {
+        let areas = areas
+            .iter()
+            .map(|area| ProjectArea {
+                polygon: area
+                    .polygon
+                    .get_vertices()
+                    .iter()
+                    .map(|&(x, y)| [x, y])
+                    .collect(),
+                points: area
+                    .points
+                    .iter()
+                    .map(|point| ProjectPoint {
+                        x: point.x,
+                        y: point.y,
+                        value: point.value,
+                        label: point.label,
+                        attributes: point.attributes.clone(),
+                    })
+                    .collect(),
+                label: area.label,
+                attributes: area.attributes.clone(),
+                fill: area.fill,
+            })
+            .collect();
+        Project {
+            version: VERSION,
+            image,
+            camera: CameraState::of(camera),
+            labels: labels.to_vec(),
+            areas,
+        }
+    }
+    /// Areas stretched from the saved image size to a plane of
+    /// `width` x `height`, in case the image changed since the project was
+    /// saved.
+    pub fn get_areas(&self, width: f32, height: f32) -> Result<Vec<AreaData>, String> {
+        let positive = |size: f32| size > 0.0 && size.is_finite();
+        if !positive(self.image.width) || !positive(self.image.height) {
+            return Err("the image of the project has no size".into());
+        }
+        let sx = width / self.image.width;
+        let sy = height / self.image.height;
+        self.areas
+            .iter()
+            .map(|area| {
+                let vertices = area.polygon.iter().map(|&[x, y]| (x * sx, y * sy));
+                let points = area.points.iter().map(|point| PointData {
+                    x: point.x * sx,
+                    y: point.y * sy,
+                    value: point.value,
+                    label: point.label,
+                    attributes: point.attributes.clone(),
+                });
+                Ok(AreaData {
+                    polygon: Polygon::new(vertices.collect())?,
+                    points: points.collect(),
+                    label: area.label,
+                    attributes: area.attributes.clone(),
+                    fill: area.fill,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Version 2 gives projects a label taxonomy and a class to each area and
+/// point.
+fn add_labels(mut value: Value) -> Result<Value, String> {
+    value["labels"] = Value::Array(Vec::new());
+    let areas = value["areas"]
+        .as_array_mut()
+        .ok_or("the project has no areas")?;
+    for area in areas {
+        area["label"] = Value::Null;
+        for point in area["points"].as_array_mut().into_iter().flatten() {
+            point["label"] = Value::Null;
+        }
+    }
+    Ok(value)
+}
+
+/// Version 3 gives areas and points attributes, and label classes their
+/// schema.
+fn add_attributes(mut value: Value) -> Result<Value, String> {
+    for class in value["labels"].as_array_mut().into_iter().flatten() {
+        class["attributes"] = Value::Array(Vec::new());
+    }
+    let areas = value["areas"]
+        .as_array_mut()
+        .ok_or("the project has no areas")?;
+    for area in areas {
+        area["attributes"] = serde_json::json!({});
+        for point in area["points"].as_array_mut().into_iter().flatten() {
+            point["attributes"] = serde_json::json!({});
+        }
+    }
+    Ok(value)
+}
+
+/// Version 4 gives areas their own fill color.
+fn add_fills(mut value: Value) -> Result<Value, String> {
+    let areas = value["areas"]
+        .as_array_mut()
+        .ok_or("the project has no areas")?;
+    for area in areas {
+        area["fill"] = Value::Null;
+    }
+    Ok(value)
+}
+
+fn migrate(mut value: Value, migrations: &[Migration]) -> Result<Value, String> {
+    let latest = migrations.len() as u64 + 1;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("the project has no version")?;
+    if version == 0 || version > latest {
+        return Err(format!("unsupported project version {}", version));
+    }
+    for (i, migration) in migrations.iter().enumerate().skip(version as usize - 1) {
+        value = migration(value)?;
+        value["version"] = Value::from(i as u64 + 2);
+    }
+    Ok(value)
+}
+
+/// Parses a project saved by any version, upgrading it to the current one.
+pub fn from_str(json: &str) -> Result<Project, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let value = migrate(value, MIGRATIONS)?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+pub fn to_string(project: &Project) -> Result<String, String> {
+    serde_json::to_string(project).map_err(|e| e.to_string())
+}
+
+#[test]
+fn migrations_run_in_order_from_the_saved_version() {
+    fn rename_shapes(mut value: Value) -> Result<Value, String> {
+        let shapes = value["shapes"].take();
+        value["regions"] = shapes;
+        Ok(value)
+    }
+    fn wrap_regions(mut value: Value) -> Result<Value, String> {
+        let regions = value["regions"].take();
+        value["areas"] = serde_json::json!({ "list": regions });
+        Ok(value)
+    }
+    let migrations: &[Migration] = &[rename_shapes, wrap_regions];
+
+    let old = serde_json::json!({ "version": 1, "shapes": [1, 2] });
+    let new = migrate(old, migrations).unwrap();
+    assert_eq!(new["version"], 3);
+    assert_eq!(new["areas"]["list"], serde_json::json!([1, 2]));
+
+    let middle = serde_json::json!({ "version": 2, "regions": [3] });
+    let new = migrate(middle, migrations).unwrap();
+    assert_eq!(new["areas"]["list"], serde_json::json!([3]));
+
+    assert!(migrate(serde_json::json!({ "version": 4 }), migrations).is_err());
+    assert!(migrate(serde_json::json!({ "version": 0 }), migrations).is_err());
+    assert!(migrate(serde_json::json!({}), migrations).is_err());
+}
+
+#[test]
+fn saved_projects_restore_areas_and_camera() {
+    use crate::renderer::Texture;
+
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    let triangle = Polygon::new(vec![(-20.0, -20.0), (30.0, -10.0), (0.0, 20.0)]).unwrap();
+    let area = renderer.create_area(triangle).unwrap();
+    renderer.create_point(area, 0.0, 0.0, 4.5).unwrap();
+    renderer
+        .set_fill(area, Some([0.0, 0.5, 1.0, 0.25]))
+        .unwrap();
+    renderer.key_down("d", false);
+    renderer.update(0.5);
+    renderer.mouse_move(0, 0, 0.0, 10.0, true);
+    let json = renderer.save_project().unwrap();
+    let areas = super::collect_areas(renderer.get_scene());
+
+    let project = from_str(&json).unwrap();
+    assert_eq!(project.version, VERSION);
+    let (_headless, mut other) = super::super::headless_renderer();
+    let texture = Texture::from_rgba(other.get_backend(), 100, 50, &[255; 100 * 50 * 4]).unwrap();
+    other.load_project(&project, texture).unwrap();
+    assert_eq!(super::collect_areas(other.get_scene()), areas);
+    assert_eq!(areas[0].fill, Some([0.0, 0.5, 1.0, 0.25]));
+    assert_eq!(
+        CameraState::of(&other.camera),
+        CameraState::of(&renderer.camera)
+    );
+
+    // a bigger copy of the image stretches the areas with it
+    let texture = Texture::from_rgba(other.get_backend(), 200, 100, &[255; 200 * 100 * 4]).unwrap();
+    other.load_project(&project, texture).unwrap();
+    let stretched = super::collect_areas(other.get_scene());
+    assert_eq!(
+        stretched[0].polygon.bounding_box(),
+        ((-40.0, -40.0), (60.0, 40.0))
+    );
+    assert_eq!(
+        stretched[0].points[0],
+        PointData {
+            x: 0.0,
+            y: 0.0,
+            value: 4.5,
+            label: None,
+            attributes: Attributes::new()
+        }
+    );
+}
+
+#[test]
+fn version_1_projects_get_labels() {
+    let json = r#"{
+        "version": 1,
+        "image": { "file_name": "a.png", "width": 100, "height": 50 },
+        "camera": { "position": [0, 0, 1], "pitch": 0, "yaw": 0, "roll": 0, "zoom": 1 },
+        "areas": [{
+            "polygon": [[-10, -10], [10, -10], [10, 10], [-10, 10]],
+            "points": [{ "x": 0, "y": 0, "value": 2 }]
+        }]
+    }"#;
+    let project = from_str(json).unwrap();
+    assert_eq!(project.version, VERSION);
+    assert!(project.labels.is_empty());
+    assert_eq!(project.areas[0].label, None);
+    assert_eq!(project.areas[0].points[0].label, None);
+    assert!(project.areas[0].attributes.is_empty());
+    assert!(project.areas[0].points[0].attributes.is_empty());
+    assert_eq!(project.areas[0].fill, None);
+}
+
+#[test]
+fn invalid_projects_leave_the_session_untouched() {
+    use crate::renderer::Texture;
+
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    let square = Polygon::rectangle(-10.0, -10.0, 20.0, 20.0);
+    renderer.create_area(square).unwrap();
+    let json = renderer.save_project().unwrap();
+    let mut project = from_str(&json).unwrap();
+    let plane = renderer.get_scene().get_plane();
+    let areas = super::collect_areas(renderer.get_scene());
+
+    // a point outside of its area
+    project.areas[0].points.push(ProjectPoint {
+        x: 40.0,
+        y: 0.0,
+        value: 1.0,
+        label: None,
+        attributes: Attributes::new(),
+    });
+    let texture = Texture::from_rgba(renderer.get_backend(), 80, 80, &[255; 80 * 80 * 4]).unwrap();
+    assert!(renderer.load_project(&project, texture).is_err());
+    assert_eq!(renderer.get_scene().get_plane(), plane);
+    assert_eq!(super::collect_areas(renderer.get_scene()), areas);
+    assert!(renderer.can_undo());
+
+    // an area with a class the project does not define
+    project.areas[0].points.clear();
+    project.areas[0].label = Some(7);
+    let texture = Texture::from_rgba(renderer.get_backend(), 80, 80, &[255; 80 * 80 * 4]).unwrap();
+    assert!(renderer.load_project(&project, texture).is_err());
+    assert_eq!(renderer.get_scene().get_plane(), plane);
+    assert_eq!(super::collect_areas(renderer.get_scene()), areas);
+
+    // a camera that cannot be drawn, an image without a size or a fill the
+    // API would refuse
+    project.areas[0].label = None;
+    let camera = CameraState::of(&renderer.camera);
+    for broken in 0..4 {
+        let mut project = project.clone();
+        match broken {
+            0 => project.camera.zoom = 0.0,
+            1 => project.camera.position[0] = f32::NAN,
+            2 => project.image.width = 0.0,
+            _ => project.areas[0].fill = Some([5.0, -1.0, 0.0, 9.0]),
+        }
+        let texture =
+            Texture::from_rgba(renderer.get_backend(), 80, 80, &[255; 80 * 80 * 4]).unwrap();
+        assert!(renderer.load_project(&project, texture).is_err());
+        assert_eq!(renderer.get_scene().get_plane(), plane);
+        assert_eq!(CameraState::of(&renderer.camera), camera);
+    }
+
+    // the zoom is kept within the limits of the session
+    renderer.set_zoom_limits(50.0, 400.0).unwrap();
+    project.camera.zoom = 1000.0;
+    let texture = Texture::from_rgba(renderer.get_backend(), 80, 80, &[255; 80 * 80 * 4]).unwrap();
+    renderer.load_project(&project, texture).unwrap();
+    assert_eq!(renderer.camera.get_zoom(), 400.0);
+}
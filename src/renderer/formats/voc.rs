@@ -0,0 +1,205 @@
+use super::{AreaData, ImageFrame};
+use crate::renderer::attributes::Attributes;
+use crate::renderer::labels::Labels;
+use serde::{Deserialize, Serialize};
+
+const OBJECT_NAME: &str = "area";
+
+/// Pascal VOC annotation of a single image. Boxes are in pixels from the top
+/// left corner of the image.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename = "annotation")]
+pub struct Voc {
+    #[serde(default)]
+    pub folder: String,
+    pub filename: String,
+    pub size: Size,
+    #[serde(default)]
+    pub segmented: u8,
+    #[serde(rename = "object", default)]
+    pub objects: Vec<Object>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub depth: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Object {
+    pub name: String,
+    #[serde(default)]
+    pub pose: String,
+    #[serde(default)]
+    pub truncated: u8,
+    #[serde(default)]
+    pub difficult: u8,
+    pub bndbox: BndBox,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BndBox {
+    pub xmin: f32,
+    pub ymin: f32,
+    pub xmax: f32,
+    pub ymax: f32,
+}
+
+/// VOC only knows boxes, areas are exported as their bounding box and their
+/// points are dropped. Objects are named after the class of the area.
+pub fn export(image: &ImageFrame, areas: &[AreaData], labels: &Labels) -> Voc {
+    let objects = areas
+        .iter()
+        .map(|area| {
+            let [xmin, ymin, xmax, ymax] = image.to_pixel_box(&area.polygon);
+            let name = area
+                .label
+                .and_then(|label| labels.get(label))
+                .map_or(OBJECT_NAME, |class| &class.name);
+            Object {
+                name: name.into(),
+                pose: "Unspecified".into(),
+                truncated: 0,
+                difficult: 0,
+                bndbox: BndBox {
+                    xmin,
+                    ymin,
+                    xmax,
+                    ymax,
+                },
+            }
+        })
+        .collect();
+    Voc {
+        folder: String::new(),
+        filename: image.file_name.clone(),
+        size: Size {
+            width: image.width,
+            height: image.height,
+            depth: 3,
+        },
+        segmented: 0,
+        objects,
+    }
+}
+
+pub fn to_string(voc: &Voc) -> Result<String, String> {
+    let mut xml = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut xml);
+    serializer.indent(' ', 2);
+    voc.serialize(serializer).map_err(|e| e.to_string())?;
+    Ok(xml)
+}
+
+pub fn from_str(xml: &str) -> Result<Voc, String> {
+    quick_xml::de::from_str(xml).map_err(|e| e.to_string())
+}
+
+/// Rebuilds the boxes as rectangular areas, scaled from the size written in
+/// the file to the plane. Object names are matched to the label classes.
+pub fn import(voc: &Voc, image: &ImageFrame, labels: &Labels) -> Result<Vec<AreaData>, String> {
+    let frame = image.with_size(voc.size.width, voc.size.height);
+    voc.objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| {
+            let BndBox {
+                xmin,
+                ymin,
+                xmax,
+                ymax,
+            } = object.bndbox;
+            let polygon = frame
+                .to_world_box([xmin, ymin, xmax, ymax])
+                .map_err(|e| format!("object {}: {}", i, e))?;
+            Ok(AreaData {
+                polygon,
+                points: Vec::new(),
+                label: labels.get_by_name(&object.name).map(|class| class.id),
+                attributes: Attributes::new(),
+                fill: None,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn boxes_round_trip_through_xml() {
+    use crate::alg::Polygon;
+
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    renderer
+        .create_area(Polygon::rectangle(-50.0, 15.0, 10.0, 10.0))
+        .unwrap();
+    renderer
+        .create_area(Polygon::rectangle(-12.5, -20.0, 37.5, 22.25))
+        .unwrap();
+    let exported = super::collect_areas(renderer.get_scene());
+    let xml = renderer.export_voc().unwrap();
+    let voc = from_str(&xml).unwrap();
+    assert_eq!(voc.size.width, 100);
+    assert_eq!(
+        voc.objects[0].bndbox,
+        BndBox {
+            xmin: 0.0,
+            ymin: 0.0,
+            xmax: 10.0,
+            ymax: 10.0
+        }
+    );
+
+    let (_headless, mut other) = super::super::headless_renderer_with_image(100, 50);
+    other.import_voc(&xml).unwrap();
+    assert_eq!(super::collect_areas(other.get_scene()), exported);
+}
+
+#[test]
+fn polygons_are_exported_as_their_bounding_box() {
+    use crate::alg::Polygon;
+
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    let triangle = Polygon::new(vec![(-20.0, -20.0), (30.0, -10.0), (0.0, 20.0)]).unwrap();
+    let area = renderer.create_area(triangle).unwrap();
+    renderer.create_point(area, 0.0, 0.0, 1.0).unwrap();
+    renderer
+        .import_voc(&renderer.export_voc().unwrap())
+        .unwrap();
+
+    let areas = super::collect_areas(renderer.get_scene());
+    assert_eq!(areas.len(), 1);
+    assert_eq!(
+        areas[0].polygon,
+        Polygon::rectangle(-20.0, -20.0, 50.0, 40.0)
+    );
+    assert!(areas[0].points.is_empty());
+}
+
+#[test]
+fn files_from_other_tools_are_read() {
+    let xml = r#"<?xml version="1.0"?>
+<annotation>
+  <folder>VOC2012</folder>
+  <filename>2007_000027.jpg</filename>
+  <source><database>The VOC2007 Database</database></source>
+  <size><width>200</width><height>100</height><depth>3</depth></size>
+  <segmented>0</segmented>
+  <object>
+    <name>person</name>
+    <pose>Unspecified</pose>
+    <truncated>0</truncated>
+    <difficult>0</difficult>
+    <bndbox><xmin>20</xmin><ymin>10</ymin><xmax>60</xmax><ymax>50</ymax></bndbox>
+    <part><name>head</name><bndbox><xmin>1</xmin><ymin>1</ymin><xmax>2</xmax><ymax>2</ymax></bndbox></part>
+  </object>
+</annotation>"#;
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    renderer.import_voc(xml).unwrap();
+    let areas = super::collect_areas(renderer.get_scene());
+    assert_eq!(
+        areas[0].polygon.bounding_box(),
+        ((-40.0, 0.0), (-20.0, 20.0))
+    );
+}
@@ -0,0 +1,146 @@
+use super::{AreaData, ImageFrame};
+use crate::renderer::attributes::Attributes;
+use crate::renderer::labels::Labels;
+
+/// Class of the areas without a label when no class is defined.
+const CLASS_ID: u32 = 0;
+
+/// One `class center_x center_y width height` line per area, normalized to
+/// the size of the image. Like VOC, areas become their bounding box and
+/// their points are dropped. The class is the id of the label, areas without
+/// one share the id after the last class so they are not mistaken for a
+/// class when read back.
+pub fn export(image: &ImageFrame, areas: &[AreaData], labels: &Labels) -> String {
+    let unlabeled = labels
+        .get_classes()
+        .iter()
+        .map(|class| class.id + 1)
+        .max()
+        .unwrap_or(CLASS_ID);
+    let (width, height) = (image.width as f32, image.height as f32);
+    let mut text = String::new();
+    for area in areas {
+        let [left, top, right, bottom] = image.to_pixel_box(&area.polygon);
+        let class = area
+            .label
+            .filter(|&label| labels.get(label).is_some())
+            .unwrap_or(unlabeled);
+        text.push_str(&format!(
+            "{} {} {} {} {}\n",
+            class,
+            (left + right) / 2.0 / width,
+            (top + bottom) / 2.0 / height,
+            (right - left) / width,
+            (bottom - top) / height
+        ));
+    }
+    text
+}
+
+/// Rebuilds the boxes as rectangular areas. Blank lines are skipped and
+/// classes that are not defined leave the area without a label.
+pub fn import(text: &str, image: &ImageFrame, labels: &Labels) -> Result<Vec<AreaData>, String> {
+    let (width, height) = (image.width as f32, image.height as f32);
+    let mut areas = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() != 5 {
+            return Err(format!("line {}: expected 5 fields", i + 1));
+        }
+        let class = fields[0]
+            .parse::<u32>()
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        let mut numbers = [0.0; 4];
+        for (number, field) in numbers.iter_mut().zip(&fields[1..]) {
+            *number = field
+                .parse::<f32>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        }
+        let [x, y, w, h] = numbers;
+        let pixel_box = [
+            (x - w / 2.0) * width,
+            (y - h / 2.0) * height,
+            (x + w / 2.0) * width,
+            (y + h / 2.0) * height,
+        ];
+        let polygon = image
+            .to_world_box(pixel_box)
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+        areas.push(AreaData {
+            polygon,
+            points: Vec::new(),
+            label: labels.get(class).map(|class| class.id),
+            attributes: Attributes::new(),
+            fill: None,
+        });
+    }
+    Ok(areas)
+}
+
+#[test]
+fn boxes_are_normalized_and_round_trip() {
+    use crate::alg::Polygon;
+
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    renderer
+        .create_area(Polygon::rectangle(-50.0, 15.0, 10.0, 10.0))
+        .unwrap();
+    renderer
+        .create_area(Polygon::rectangle(-25.0, -25.0, 50.0, 25.0))
+        .unwrap();
+    let exported = super::collect_areas(renderer.get_scene());
+    let text = renderer.export_yolo().unwrap();
+    assert_eq!(text, "0 0.05 0.1 0.1 0.2\n0 0.5 0.75 0.5 0.5\n");
+
+    let (_headless, mut other) = super::super::headless_renderer_with_image(100, 50);
+    other.import_yolo(&text).unwrap();
+    assert_eq!(super::collect_areas(other.get_scene()), exported);
+}
+
+#[test]
+fn unlabeled_areas_do_not_take_the_id_of_a_class() {
+    use crate::alg::Polygon;
+
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    renderer
+        .define_label(super::super::label_class(0, "car", "#ff0000", None))
+        .unwrap();
+    renderer
+        .define_label(super::super::label_class(4, "person", "#00ff00", None))
+        .unwrap();
+    let car = renderer
+        .create_area(Polygon::rectangle(-50.0, 15.0, 10.0, 10.0))
+        .unwrap();
+    renderer.set_label(car, Some(0)).unwrap();
+    let other = renderer
+        .create_area(Polygon::rectangle(-25.0, -25.0, 50.0, 25.0))
+        .unwrap();
+    renderer.set_label(other, None).unwrap();
+    let text = renderer.export_yolo().unwrap();
+    assert_eq!(text, "0 0.05 0.1 0.1 0.2\n5 0.5 0.75 0.5 0.5\n");
+
+    renderer.import_yolo(&text).unwrap();
+    let areas = super::collect_areas(renderer.get_scene());
+    assert_eq!(areas[0].label, Some(0));
+    assert_eq!(areas[1].label, None);
+}
+
+#[test]
+fn malformed_lines_are_rejected() {
+    let (_headless, mut renderer) = super::super::headless_renderer_with_image(100, 50);
+    renderer.import_yolo("\n1 0.5 0.5 0.2 0.2\n\n").unwrap();
+    assert_eq!(renderer.get_scene().get_areas().len(), 1);
+
+    let error = renderer.import_yolo("0 0.5 0.5 0.2\n").unwrap_err();
+    assert!(error.starts_with("line 1"));
+    let error = renderer
+        .import_yolo("0 0.5 0.5 0.2 0.2\n0 a 0.5 0.2 0.2")
+        .unwrap_err();
+    assert!(error.starts_with("line 2"));
+    assert!(renderer.import_yolo("0 0.5 0.5 0.0 0.2").is_err());
+    // a failed import leaves the areas untouched
+    assert_eq!(renderer.get_scene().get_areas().len(), 1);
+}
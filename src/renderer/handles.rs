@@ -0,0 +1,178 @@
+use super::element::{ElementKind, Rectangle};
+use super::scene::{ElementId, Scene};
+use crate::alg::Polygon;
+
+/// Grips drawn around the frame of the selected area.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+    Rotate,
+}
+
+impl Handle {
+    pub const ALL: [Handle; 9] = [
+        Handle::TopLeft,
+        Handle::Top,
+        Handle::TopRight,
+        Handle::Right,
+        Handle::BottomRight,
+        Handle::Bottom,
+        Handle::BottomLeft,
+        Handle::Left,
+        Handle::Rotate,
+    ];
+    /// Side of a handle in screen pixels.
+    pub const SIZE: f32 = 8.0;
+    /// Distance in screen pixels of the rotation handle from the top edge.
+    pub const ROTATE_OFFSET: f32 = 24.0;
+    /// Smallest side, in world units, an area can be resized to.
+    pub const MIN_SIDE: f32 = 1.0;
+
+    /// Center of the handle, `pixel` is the size of a screen pixel in world
+    /// units.
+    pub fn position(self, rect: &Rectangle, pixel: f32) -> (f32, f32) {
+        let (left, bottom) = rect.down_left_corner;
+        let (right, top) = (left + rect.dimensions.0, bottom + rect.dimensions.1);
+        let (x, y) = rect.get_center();
+        match self {
+            Handle::TopLeft => (left, top),
+            Handle::Top => (x, top),
+            Handle::TopRight => (right, top),
+            Handle::Right => (right, y),
+            Handle::BottomRight => (right, bottom),
+            Handle::Bottom => (x, bottom),
+            Handle::BottomLeft => (left, bottom),
+            Handle::Left => (left, y),
+            Handle::Rotate => (x, top + Self::ROTATE_OFFSET * pixel),
+        }
+    }
+    pub fn at(rect: &Rectangle, pixel: f32, x: f32, y: f32) -> Option<Handle> {
+        let half = Self::SIZE * pixel / 2.0;
+        Self::ALL.iter().copied().find(|handle| {
+            let (hx, hy) = handle.position(rect, pixel);
+            (x - hx).abs() <= half && (y - hy).abs() <= half
+        })
+    }
+    /// Box obtained by dragging this handle of `rect` to `(x, y)`. The
+    /// opposite side stays in place.
+    pub fn resize(self, rect: &Rectangle, x: f32, y: f32) -> Rectangle {
+        let (mut left, mut bottom) = rect.down_left_corner;
+        let (mut right, mut top) = (left + rect.dimensions.0, bottom + rect.dimensions.1);
+        match self {
+            Handle::TopLeft | Handle::Left | Handle::BottomLeft => {
+                left = x.min(right - Self::MIN_SIDE)
+            }
+            Handle::TopRight | Handle::Right | Handle::BottomRight => {
+                right = x.max(left + Self::MIN_SIDE)
+            }
+            _ => {}
+        }
+        match self {
+            Handle::TopLeft | Handle::Top | Handle::TopRight => {
+                top = y.max(bottom + Self::MIN_SIDE)
+            }
+            Handle::BottomLeft | Handle::Bottom | Handle::BottomRight => {
+                bottom = y.min(top - Self::MIN_SIDE)
+            }
+            _ => {}
+        }
+        Rectangle::new(left, bottom, right - left, top - bottom)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DragAction {
+    Move,
+    Resize(Handle),
+    Rotate,
+}
+
+/// Element being dragged and how it looked when the drag started. Every
+/// step is computed from the start, so rounding errors do not pile up.
+#[derive(Clone, Debug)]
+pub struct Drag {
+    pub id: ElementId,
+    pub action: DragAction,
+    pub start: (f32, f32),
+    pub rect: Rectangle,
+    pub polygon: Option<Polygon>,
+    pub points: Vec<(ElementId, (f32, f32))>,
+}
+
+impl Drag {
+    pub fn new(
+        scene: &Scene,
+        id: ElementId,
+        action: DragAction,
+        start: (f32, f32),
+    ) -> Option<Drag> {
+        let element = scene.get(id)?;
+        let polygon = match element.kind {
+            ElementKind::AREA(ref area) => Some(area.polygon.clone()),
+            ElementKind::POINT(_) => None,
+            ElementKind::PLANE(_) => return None,
+        };
+        if polygon.is_none() && action != DragAction::Move {
+            return None;
+        }
+        let points = scene
+            .get_points(id)
+            .iter()
+            .filter_map(|point| Some((*point, scene.get(*point)?.rect.get_center())))
+            .collect();
+        Some(Drag {
+            id,
+            action,
+            start,
+            rect: element.rect,
+            polygon,
+            points,
+        })
+    }
+}
+
+/// Maps a point of `from` to the same relative position in `to`.
+pub fn stretch(from: &Rectangle, to: &Rectangle, (x, y): (f32, f32)) -> (f32, f32) {
+    let sx = to.dimensions.0 / from.dimensions.0;
+    let sy = to.dimensions.1 / from.dimensions.1;
+    (
+        to.down_left_corner.0 + (x - from.down_left_corner.0) * sx,
+        to.down_left_corner.1 + (y - from.down_left_corner.1) * sy,
+    )
+}
+
+/// Rotates `(x, y)` by `angle` radians, counterclockwise, around `center`.
+pub fn rotate(center: (f32, f32), angle: f32, (x, y): (f32, f32)) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    let (dx, dy) = (x - center.0, y - center.1);
+    (
+        center.0 + dx * cos - dy * sin,
+        center.1 + dx * sin + dy * cos,
+    )
+}
+
+#[test]
+fn handles_keep_the_opposite_side_in_place() {
+    let rect = Rectangle::new(0.0, 0.0, 10.0, 20.0);
+    assert_eq!(Handle::at(&rect, 1.0, 10.0, 20.0), Some(Handle::TopRight));
+    assert_eq!(Handle::at(&rect, 1.0, 5.0, 44.0), Some(Handle::Rotate));
+    assert_eq!(Handle::at(&rect, 1.0, 5.0, 10.0), None);
+
+    let resized = Handle::TopRight.resize(&rect, 15.0, 30.0);
+    assert_eq!(resized, Rectangle::new(0.0, 0.0, 15.0, 30.0));
+    let resized = Handle::Left.resize(&rect, -5.0, 100.0);
+    assert_eq!(resized, Rectangle::new(-5.0, 0.0, 15.0, 20.0));
+    // dragging past the opposite side stops at the smallest size
+    let resized = Handle::Bottom.resize(&rect, 0.0, 50.0);
+    assert_eq!(resized, Rectangle::new(0.0, 19.0, 10.0, 1.0));
+
+    let to = Rectangle::new(-5.0, 0.0, 20.0, 10.0);
+    assert_eq!(stretch(&rect, &to, (5.0, 10.0)), (5.0, 5.0));
+}
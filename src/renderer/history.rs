@@ -0,0 +1,555 @@
+use super::attributes::{AttributeValue, Attributes};
+use super::backend::GraphicsBackend;
+use super::element::{Element, ElementKind};
+use super::scene::{ElementId, Scene};
+use crate::alg::Polygon;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Area(Polygon),
+    Point { x: f32, y: f32, value: f32 },
+}
+
+/// Everything needed to put back a removed element and its descendants
+/// under the same ids.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub id: ElementId,
+    pub parent: ElementId,
+    pub index: usize,
+    pub shape: Shape,
+    pub label: Option<u32>,
+    pub attributes: Attributes,
+    pub fill: Option<[f32; 4]>,
+    pub children: Vec<Snapshot>,
+}
+
+impl Snapshot {
+    pub fn take(scene: &Scene, id: ElementId) -> Option<Snapshot> {
+        let element = scene.get(id)?;
+        let shape = match element.kind {
+            ElementKind::AREA(ref area) => Shape::Area(area.polygon.clone()),
+            ElementKind::POINT(ref point) => {
+                let (x, y) = element.rect.get_center();
+                Shape::Point {
+                    x,
+                    y,
+                    value: point.value,
+                }
+            }
+            ElementKind::PLANE(_) => return None,
+        };
+        let children = element
+            .children()
+            .iter()
+            .filter_map(|child| Snapshot::take(scene, *child))
+            .collect();
+        Some(Snapshot {
+            id,
+            parent: element.parent?,
+            index: scene.index_of(id)?,
+            shape,
+            label: element.label,
+            attributes: element.attributes.clone(),
+            fill: element.fill,
+            children,
+        })
+    }
+    fn restore(&self, scene: &mut Scene, backend: &Rc<dyn GraphicsBackend>) -> Result<(), String> {
+        let mut element = match self.shape {
+            Shape::Area(ref polygon) => Element::area(backend, polygon.clone())?,
+            Shape::Point { x, y, value } => Element::point(backend, x, y, value)?,
+        };
+        element.label = self.label;
+        element.attributes = self.attributes.clone();
+        element.fill = self.fill;
+        scene.restore(self.id, self.parent, self.index, element)?;
+        for child in &self.children {
+            child.restore(scene, backend)?;
+        }
+        Ok(())
+    }
+}
+
+/// A reversible change to the element tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    Insert(Snapshot),
+    Remove(Snapshot),
+    Move {
+        id: ElementId,
+        dx: f32,
+        dy: f32,
+    },
+    Reshape {
+        id: ElementId,
+        before: Polygon,
+        after: Polygon,
+    },
+    SetValue {
+        id: ElementId,
+        before: f32,
+        after: f32,
+    },
+    SetLabel {
+        id: ElementId,
+        before: Option<u32>,
+        after: Option<u32>,
+    },
+    SetFill {
+        id: ElementId,
+        before: Option<[f32; 4]>,
+        after: Option<[f32; 4]>,
+    },
+    /// `None` stands for an attribute without a value.
+    SetAttribute {
+        id: ElementId,
+        name: String,
+        before: Option<AttributeValue>,
+        after: Option<AttributeValue>,
+    },
+}
+
+impl Edit {
+    pub fn get_id(&self) -> ElementId {
+        match self {
+            Edit::Insert(snapshot) | Edit::Remove(snapshot) => snapshot.id,
+            Edit::Move { id, .. }
+            | Edit::Reshape { id, .. }
+            | Edit::SetValue { id, .. }
+            | Edit::SetLabel { id, .. }
+            | Edit::SetFill { id, .. }
+            | Edit::SetAttribute { id, .. } => *id,
+        }
+    }
+    pub fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::Insert(snapshot) => Edit::Remove(snapshot),
+            Edit::Remove(snapshot) => Edit::Insert(snapshot),
+            Edit::Move { id, dx, dy } => Edit::Move {
+                id,
+                dx: -dx,
+                dy: -dy,
+            },
+            Edit::Reshape { id, before, after } => Edit::Reshape {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::SetValue { id, before, after } => Edit::SetValue {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::SetLabel { id, before, after } => Edit::SetLabel {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::SetFill { id, before, after } => Edit::SetFill {
+                id,
+                before: after,
+                after: before,
+            },
+            Edit::SetAttribute {
+                id,
+                name,
+                before,
+                after,
+            } => Edit::SetAttribute {
+                id,
+                name,
+                before: after,
+                after: before,
+            },
+        }
+    }
+    pub fn apply(
+        &self,
+        scene: &mut Scene,
+        backend: &Rc<dyn GraphicsBackend>,
+    ) -> Result<(), String> {
+        let missing = |id: ElementId| format!("no element with id {}", id.0);
+        match self {
+            Edit::Insert(snapshot) => snapshot.restore(scene, backend),
+            Edit::Remove(snapshot) => scene
+                .remove(snapshot.id)
+                .map(|_| ())
+                .ok_or_else(|| missing(snapshot.id)),
+            Edit::Move { id, dx, dy } => {
+                if !scene.contains(*id) {
+                    return Err(missing(*id));
+                }
+                scene.translate(*id, *dx, *dy);
+                Ok(())
+            }
+            Edit::Reshape { id, after, .. } => scene.set_polygon(*id, backend, after.clone()),
+            Edit::SetValue { id, after, .. } => {
+                match scene.get_mut(*id).map(|element| &mut element.kind) {
+                    Some(ElementKind::POINT(point)) => {
+                        point.value = *after;
+                        Ok(())
+                    }
+                    _ => Err(format!("element {} is not a point", id.0)),
+                }
+            }
+            Edit::SetLabel { id, after, .. } => {
+                scene.get_mut(*id).ok_or_else(|| missing(*id))?.label = *after;
+                Ok(())
+            }
+            Edit::SetFill { id, after, .. } => {
+                scene.get_mut(*id).ok_or_else(|| missing(*id))?.fill = *after;
+                Ok(())
+            }
+            Edit::SetAttribute {
+                id, name, after, ..
+            } => {
+                let attributes = &mut scene.get_mut(*id).ok_or_else(|| missing(*id))?.attributes;
+                match after {
+                    Some(value) => attributes.insert(name.clone(), value.clone()),
+                    None => attributes.remove(name),
+                };
+                Ok(())
+            }
+        }
+    }
+    /// Folds `next` into this edit when both are of the same type and act on
+    /// the same element, so a drag leaves a single entry.
+    fn merge(&mut self, next: &Edit) -> bool {
+        match (self, next) {
+            (
+                Edit::Move { id, dx, dy },
+                Edit::Move {
+                    id: next,
+                    dx: x,
+                    dy: y,
+                },
+            ) if id == next => {
+                *dx += x;
+                *dy += y;
+                true
+            }
+            (
+                Edit::Reshape { id, after, .. },
+                Edit::Reshape {
+                    id: next,
+                    after: next_after,
+                    ..
+                },
+            ) if id == next => {
+                *after = next_after.clone();
+                true
+            }
+            (
+                Edit::SetValue { id, after, .. },
+                Edit::SetValue {
+                    id: next,
+                    after: next_after,
+                    ..
+                },
+            ) if id == next => {
+                *after = *next_after;
+                true
+            }
+            (
+                Edit::SetLabel { id, after, .. },
+                Edit::SetLabel {
+                    id: next,
+                    after: next_after,
+                    ..
+                },
+            ) if id == next => {
+                *after = *next_after;
+                true
+            }
+            (
+                Edit::SetFill { id, after, .. },
+                Edit::SetFill {
+                    id: next,
+                    after: next_after,
+                    ..
+                },
+            ) if id == next => {
+                *after = *next_after;
+                true
+            }
+            (
+                Edit::SetAttribute {
+                    id, name, after, ..
+                },
+                Edit::SetAttribute {
+                    id: next,
+                    name: next_name,
+                    after: next_after,
+                    ..
+                },
+            ) if id == next && name == next_name => {
+                *after = next_after.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Bounded undo and redo stacks. Each entry is a list of edits undone and
+/// redone together.
+pub struct History {
+    undo: VecDeque<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+    group: Option<Vec<Edit>>,
+    /// Groups opened inside a group end with the outermost one.
+    depth: usize,
+    limit: usize,
+}
+
+impl History {
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn new(limit: usize) -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: None,
+            depth: 0,
+            limit,
+        }
+    }
+    /// Records an edit that was already applied to the scene.
+    pub fn push(&mut self, edit: Edit) {
+        self.redo.clear();
+        match self.group {
+            Some(ref mut group) => {
+                // edits of different elements commute, so the latest edit of
+                // the same element can absorb this one unless the tree
+                // changed in between
+                let merged = group
+                    .iter_mut()
+                    .rev()
+                    .take_while(|e| !matches!(e, Edit::Insert(_) | Edit::Remove(_)))
+                    .find(|e| e.get_id() == edit.get_id())
+                    .is_some_and(|e| e.merge(&edit));
+                if !merged {
+                    group.push(edit);
+                }
+            }
+            None => self.push_entry(vec![edit]),
+        }
+    }
+    fn push_entry(&mut self, entry: Vec<Edit>) {
+        if entry.is_empty() || self.limit == 0 {
+            return;
+        }
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(entry);
+    }
+    /// Edits pushed until `end_group` become a single entry, consecutive
+    /// edits of the same element are merged.
+    pub fn begin_group(&mut self) {
+        self.depth += 1;
+        if self.group.is_none() {
+            self.group = Some(Vec::new());
+        }
+    }
+    pub fn end_group(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.close_group();
+        }
+    }
+    fn close_group(&mut self) {
+        self.depth = 0;
+        if let Some(group) = self.group.take() {
+            self.push_entry(group);
+        }
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.group.as_ref().is_some_and(|g| !g.is_empty())
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    pub fn undo(
+        &mut self,
+        scene: &mut Scene,
+        backend: &Rc<dyn GraphicsBackend>,
+    ) -> Result<bool, String> {
+        self.close_group();
+        let entry = match self.undo.pop_back() {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        for edit in entry.iter().rev() {
+            edit.inverse().apply(scene, backend)?;
+        }
+        self.redo.push(entry);
+        Ok(true)
+    }
+    pub fn redo(
+        &mut self,
+        scene: &mut Scene,
+        backend: &Rc<dyn GraphicsBackend>,
+    ) -> Result<bool, String> {
+        self.close_group();
+        let entry = match self.redo.pop() {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+        for edit in &entry {
+            edit.apply(scene, backend)?;
+        }
+        self.undo.push_back(entry);
+        Ok(true)
+    }
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+        self.depth = 0;
+    }
+}
+
+#[cfg(test)]
+fn area_count(renderer: &super::Renderer) -> usize {
+    renderer.get_scene().get_areas().len()
+}
+
+#[test]
+fn edits_are_undone_and_redone_under_the_same_ids() {
+    let (_headless, mut renderer) = super::headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let point = renderer.create_point(area, 0.0, 0.0, 1.0).unwrap();
+    renderer.set_point_value(point, 2.0).unwrap();
+    renderer.move_element(area, 5.0, 0.0).unwrap();
+    assert!(renderer.remove_element(area));
+    assert_eq!(area_count(&renderer), 0);
+
+    assert!(renderer.undo().unwrap());
+    assert_eq!(renderer.get_scene().get_areas(), &[area]);
+    assert_eq!(renderer.get_scene().get_points(area), &[point]);
+    let rect = renderer.get_scene().get(point).unwrap().rect;
+    assert_eq!(rect.get_center(), (5.0, 0.0));
+
+    assert!(renderer.undo().unwrap());
+    let rect = renderer.get_scene().get(area).unwrap().rect;
+    assert_eq!(rect.down_left_corner, (-10.0, -10.0));
+    assert!(renderer.undo().unwrap());
+    match renderer.get_scene().get(point).unwrap().kind {
+        ElementKind::POINT(ref point) => assert_eq!(point.value, 1.0),
+        _ => unreachable!(),
+    }
+    assert!(renderer.undo().unwrap());
+    assert!(renderer.get_scene().get(point).is_none());
+    assert!(renderer.undo().unwrap());
+    assert_eq!(area_count(&renderer), 0);
+    assert!(!renderer.undo().unwrap());
+
+    for _ in 0..5 {
+        assert!(renderer.redo().unwrap());
+    }
+    assert!(!renderer.redo().unwrap());
+    assert_eq!(area_count(&renderer), 0);
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_scene().get_points(area), &[point]);
+
+    // a new edit drops the redo stack
+    renderer.remove_element(point);
+    assert!(!renderer.redo().unwrap());
+}
+
+#[test]
+fn drags_are_grouped_into_one_entry() {
+    let (_headless, mut renderer) = super::headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    renderer.begin_edit_group();
+    for _ in 0..10 {
+        renderer.move_element(area, 1.0, 0.5).unwrap();
+    }
+    renderer.end_edit_group();
+    let rect = renderer.get_scene().get(area).unwrap().rect;
+    assert_eq!(rect.down_left_corner, (0.0, -5.0));
+
+    renderer.undo().unwrap();
+    let rect = renderer.get_scene().get(area).unwrap().rect;
+    assert_eq!(rect.down_left_corner, (-10.0, -10.0));
+    renderer.undo().unwrap();
+    assert_eq!(area_count(&renderer), 0);
+}
+
+#[test]
+fn history_is_bounded() {
+    let (_headless, mut renderer) = super::headless_renderer_with_image(100, 50);
+    renderer.history = History::new(3);
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    for _ in 0..5 {
+        renderer.move_element(area, 1.0, 0.0).unwrap();
+    }
+    let mut undone = 0;
+    while renderer.undo().unwrap() {
+        undone += 1;
+    }
+    assert_eq!(undone, 3);
+    let rect = renderer.get_scene().get(area).unwrap().rect;
+    assert_eq!(rect.down_left_corner, (-8.0, -10.0));
+}
+
+#[test]
+fn changes_are_notified() {
+    use std::cell::Cell;
+
+    let (_headless, renderer) = super::headless_renderer_with_image(100, 50);
+    let renderer = Rc::new(std::cell::RefCell::new(renderer));
+    let count = Rc::new(Cell::new(0));
+    let counter = count.clone();
+    renderer
+        .borrow_mut()
+        .set_change_listener(Rc::new(move || counter.set(counter.get() + 1)));
+
+    let area = renderer
+        .borrow_mut()
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    super::Renderer::notify_change(&renderer);
+    assert_eq!(count.get(), 1);
+    super::Renderer::notify_change(&renderer);
+    assert_eq!(count.get(), 1);
+
+    assert!(renderer
+        .borrow_mut()
+        .move_element(area, 100.0, 0.0)
+        .is_err());
+    super::Renderer::notify_change(&renderer);
+    assert_eq!(count.get(), 1);
+    renderer.borrow_mut().undo().unwrap();
+    super::Renderer::notify_change(&renderer);
+    assert_eq!(count.get(), 2);
+}
+
+#[test]
+fn an_import_is_undone_in_one_step() {
+    let (_headless, mut renderer) = super::headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    renderer.create_point(area, 0.0, 0.0, 1.0).unwrap();
+    renderer
+        .import_yolo("0 0.1 0.1 0.1 0.1\n0 0.9 0.9 0.1 0.1\n")
+        .unwrap();
+    assert_eq!(area_count(&renderer), 2);
+
+    assert!(renderer.undo().unwrap());
+    assert_eq!(renderer.get_scene().get_areas(), &[area]);
+    assert_eq!(renderer.get_scene().get_points(area).len(), 1);
+    assert!(renderer.redo().unwrap());
+    assert_eq!(area_count(&renderer), 2);
+}
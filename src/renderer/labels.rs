@@ -0,0 +1,152 @@
+use super::attributes::{self, AttributeSpec};
+use serde::{Deserialize, Serialize};
+
+/// A class areas and points can be assigned to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LabelClass {
+    pub id: u32,
+    pub name: String,
+    /// `#rrggbb`
+    pub color: String,
+    /// Key that assigns the class to the selection.
+    #[serde(default)]
+    pub hotkey: Option<String>,
+    /// Attributes the elements of the class can have.
+    #[serde(default)]
+    pub attributes: Vec<AttributeSpec>,
+}
+
+impl LabelClass {
+    pub fn get_color(&self) -> [f32; 4] {
+        parse_color(&self.color).unwrap_or(super::FRAME_COLOR)
+    }
+}
+
+/// Parses a `#rrggbb` color.
+pub fn parse_color(color: &str) -> Result<[f32; 4], String> {
+    let invalid = || format!("invalid color {}, expected #rrggbb", color);
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 {
+        return Err(invalid());
+    }
+    let mut rgba = [1.0; 4];
+    for (i, channel) in rgba.iter_mut().take(3).enumerate() {
+        let byte = hex
+            .get(i * 2..i * 2 + 2)
+            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            .ok_or_else(invalid)?;
+        *channel = byte as f32 / 255.0;
+    }
+    Ok(rgba)
+}
+
+/// The label taxonomy of a project.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Labels {
+    classes: Vec<LabelClass>,
+}
+
+impl Labels {
+    pub fn get_classes(&self) -> &[LabelClass] {
+        &self.classes
+    }
+    pub fn get(&self, id: u32) -> Option<&LabelClass> {
+        self.classes.iter().find(|class| class.id == id)
+    }
+    pub fn get_by_name(&self, name: &str) -> Option<&LabelClass> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+    pub fn get_by_hotkey(&self, key: &str) -> Option<&LabelClass> {
+        self.classes
+            .iter()
+            .find(|class| class.hotkey.as_deref() == Some(key))
+    }
+    /// Adds a class, or replaces the one with the same id. Names and hotkeys
+    /// must be unique.
+    pub fn define(&mut self, class: LabelClass) -> Result<(), String> {
+        if class.name.is_empty() {
+            return Err("a label needs a name".into());
+        }
+        parse_color(&class.color)?;
+        attributes::check_schema(&class.attributes)?;
+        let others = self.classes.iter().filter(|other| other.id != class.id);
+        for other in others {
+            if other.name == class.name {
+                return Err(format!("label {} already exists", class.name));
+            }
+            if other.hotkey.is_some() && other.hotkey == class.hotkey {
+                return Err(format!(
+                    "hotkey {} is taken by {}",
+                    other.hotkey.as_deref().unwrap_or_default(),
+                    other.name
+                ));
+            }
+        }
+        match self.classes.iter_mut().find(|other| other.id == class.id) {
+            Some(other) => *other = class,
+            None => self.classes.push(class),
+        }
+        Ok(())
+    }
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.classes.len();
+        self.classes.retain(|class| class.id != id);
+        self.classes.len() != len
+    }
+    /// Replaces every class at once, the checks of `define` apply.
+    pub fn set_classes(&mut self, classes: &[LabelClass]) -> Result<(), String> {
+        let mut labels = Labels::default();
+        for class in classes {
+            if labels.get(class.id).is_some() {
+                return Err(format!("label id {} is used twice", class.id));
+            }
+            labels.define(class.clone())?;
+        }
+        *self = labels;
+        Ok(())
+    }
+}
+
+#[test]
+fn classes_are_validated() {
+    let class = |id, name: &str, color: &str, hotkey: Option<&str>| LabelClass {
+        id,
+        name: name.into(),
+        color: color.into(),
+        hotkey: hotkey.map(String::from),
+        attributes: Vec::new(),
+    };
+    let mut labels = Labels::default();
+    labels
+        .define(class(1, "car", "#ff8000", Some("1")))
+        .unwrap();
+    labels.define(class(2, "person", "#00ff00", None)).unwrap();
+    assert_eq!(
+        labels.get(1).unwrap().get_color(),
+        [1.0, 128.0 / 255.0, 0.0, 1.0]
+    );
+    assert_eq!(labels.get_by_hotkey("1").unwrap().name, "car");
+
+    assert!(labels.define(class(3, "car", "#000000", None)).is_err());
+    assert!(labels
+        .define(class(3, "bike", "#000000", Some("1")))
+        .is_err());
+    assert!(labels.define(class(3, "bike", "red", None)).is_err());
+    assert!(labels.define(class(3, "bike", "#00000g", None)).is_err());
+    assert!(labels.define(class(3, "", "#000000", None)).is_err());
+
+    // redefining a class keeps its place
+    labels
+        .define(class(1, "truck", "#ff8000", Some("1")))
+        .unwrap();
+    assert_eq!(labels.get_classes()[0].name, "truck");
+    assert!(labels.remove(1));
+    assert!(!labels.remove(1));
+    assert!(labels
+        .set_classes(&[
+            class(4, "a", "#000000", None),
+            class(4, "b", "#000000", None)
+        ])
+        .is_err());
+    assert_eq!(labels.get_classes().len(), 1);
+}
@@ -3,6 +3,7 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Request, RequestInit, RequestMode, Response};
 
+#[allow(deprecated)]
 pub async fn load_file(file_name: &str) -> Result<Vec<u8>, JsValue> {
     let mut opts = RequestInit::new();
     opts.method("GET");
@@ -10,7 +11,7 @@ pub async fn load_file(file_name: &str) -> Result<Vec<u8>, JsValue> {
 
     let url = file_name;
 
-    let request = Request::new_with_str_and_init(&url, &opts)?;
+    let request = Request::new_with_str_and_init(url, &opts)?;
 
     let window = web_sys::window().unwrap();
     let resp_value = JsFuture::from(window.fetch_with_request(&request)).await?;
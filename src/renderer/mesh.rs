@@ -4,5 +4,9 @@ pub struct Mesh {
     pub vertices: Vec<f32>,
     pub texture_coords: Option<TextureCoords>,
     pub normals: Option<Vec<f32>>,
+    /// Previous and next vertex of a stroke, see `meshes::stroke`.
+    pub neighbours: Option<Vec<f32>>,
+    /// Distance of a stroke vertex along its line, in mesh units.
+    pub distances: Option<Vec<f32>>,
     pub indices: Vec<u32>,
 }
@@ -1,6 +1,8 @@
 use super::mesh::Mesh;
+use super::text::Font;
 use super::texture::Texture;
 use super::texture_coords::TextureCoords;
+use crate::alg::Polygon;
 use wasm_bindgen::prelude::*;
 
 #[allow(dead_code)]
@@ -19,6 +21,8 @@ pub fn rectangle(width: f32, height: f32) -> Mesh {
         indices,
         texture_coords: None,
         normals: None,
+        neighbours: None,
+        distances: None,
     }
 }
 #[allow(dead_code)]
@@ -36,6 +40,8 @@ pub fn cube() -> Mesh {
         indices,
         texture_coords: None,
         normals: None,
+        neighbours: None,
+        distances: None,
     }
 }
 #[allow(dead_code)]
@@ -51,18 +57,173 @@ pub fn line_rectangle(width: f32, height: f32) -> Mesh {
         indices,
         texture_coords: None,
         normals: None,
+        neighbours: None,
+        distances: None,
     }
 }
+fn flat_vertices(vertices: &[(f32, f32)]) -> Vec<f32> {
+    vertices
+        .iter()
+        .flat_map(|&(x, y)| vec![x, y, 0.0])
+        .collect()
+}
+pub fn polygon(polygon: &Polygon) -> Mesh {
+    Mesh {
+        vertices: flat_vertices(polygon.get_vertices()),
+        indices: polygon.triangulate(),
+        texture_coords: None,
+        normals: None,
+        neighbours: None,
+        distances: None,
+    }
+}
+/// How the segments of a stroke meet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Join {
+    /// Sharp corners, cut once they get longer than the miter limit of the
+    /// line shader.
+    Miter,
+    /// A disc on every vertex.
+    Round,
+}
+
+/// Triangles in a round join.
+const ROUND_SEGMENTS: u32 = 8;
+
+fn mirror(point: (f32, f32), other: (f32, f32)) -> (f32, f32) {
+    (2.0 * point.0 - other.0, 2.0 * point.1 - other.1)
+}
+
+/// Line through `vertices`, widened into triangles by the line shader so it
+/// keeps its width in pixels whatever the zoom. The z of a vertex is the side
+/// of the line it is pushed to, in half widths, its neighbours give the
+/// direction of the line there and its distance along the line lays the
+/// dashes out.
+pub fn stroke(vertices: &[(f32, f32)], closed: bool, join: Join) -> Mesh {
+    let mut points: Vec<(f32, f32)> = Vec::with_capacity(vertices.len());
+    for &vertex in vertices {
+        if points.last() != Some(&vertex) {
+            points.push(vertex);
+        }
+    }
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    let n = points.len();
+    let segments = match (n, closed) {
+        (0..=1, _) => 0,
+        (2, _) | (_, false) => n - 1,
+        _ => n,
+    };
+    // distance from the first point, the closing segment ends at the length
+    // of the whole outline
+    let mut along = vec![0.0; n + 1];
+    for i in 0..segments {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        along[i + 1] = along[i] + (b.0 - a.0).hypot(b.1 - a.1);
+    }
+    let mut vertices = Vec::new();
+    let mut neighbours = Vec::new();
+    let mut distances = Vec::new();
+    let mut indices = Vec::new();
+    let mut push =
+        |point: (f32, f32), side: f32, previous: (f32, f32), next: (f32, f32), distance| {
+            vertices.extend_from_slice(&[point.0, point.1, side]);
+            neighbours.extend_from_slice(&[previous.0, previous.1, next.0, next.1]);
+            distances.push(distance);
+        };
+    match join {
+        Join::Miter => {
+            // a closed line comes back to a copy of its first point
+            let ends = if segments == n { n + 1 } else { n };
+            for (k, &distance) in along.iter().enumerate().take(ends) {
+                let i = k % n;
+                let previous = match i {
+                    0 if segments == n => points[n - 1],
+                    0 => mirror(points[0], points[1 % n]),
+                    _ => points[i - 1],
+                };
+                let next = match i + 1 {
+                    j if j < n => points[j],
+                    _ if segments == n => points[0],
+                    _ => mirror(points[i], points[(n + i - 1) % n]),
+                };
+                push(points[i], -1.0, previous, next, distance);
+                push(points[i], 1.0, previous, next, distance);
+            }
+            for i in 0..segments as u32 {
+                let (a, b) = (i * 2, (i + 1) * 2);
+                indices.extend_from_slice(&[a, a + 1, b, b, a + 1, b + 1]);
+            }
+        }
+        Join::Round => {
+            let mut first = 0;
+            for i in 0..segments {
+                let (a, b) = (points[i], points[(i + 1) % n]);
+                for side in &[-1.0, 1.0] {
+                    push(a, *side, mirror(a, b), b, along[i]);
+                }
+                for side in &[-1.0, 1.0] {
+                    push(b, *side, a, mirror(b, a), along[i + 1]);
+                }
+                indices.extend_from_slice(&[
+                    first,
+                    first + 1,
+                    first + 2,
+                    first + 2,
+                    first + 1,
+                    first + 3,
+                ]);
+                first += 4;
+            }
+            if segments > 0 {
+                for (i, &point) in points.iter().enumerate() {
+                    push(point, 0.0, point, point, along[i]);
+                    for k in 0..ROUND_SEGMENTS {
+                        let angle = k as f32 * std::f32::consts::TAU / ROUND_SEGMENTS as f32;
+                        let direction = (angle.cos(), angle.sin());
+                        let previous = (point.0 - direction.0, point.1 - direction.1);
+                        let next = (point.0 + direction.0, point.1 + direction.1);
+                        push(point, 1.0, previous, next, along[i]);
+                        let rim = |k: u32| first + 1 + k % ROUND_SEGMENTS;
+                        indices.extend_from_slice(&[first, rim(k), rim(k + 1)]);
+                    }
+                    first += 1 + ROUND_SEGMENTS;
+                }
+            }
+        }
+    }
+    Mesh {
+        vertices,
+        indices,
+        texture_coords: None,
+        normals: None,
+        neighbours: Some(neighbours),
+        distances: Some(distances),
+    }
+}
+pub fn stroke_rectangle(width: f32, height: f32) -> Mesh {
+    let (hw, hh) = (width / 2.0, height / 2.0);
+    stroke(
+        &[(-hw, hh), (hw, hh), (hw, -hh), (-hw, -hh)],
+        true,
+        Join::Miter,
+    )
+}
+/// Outline of an area, its corners can be as sharp as they like.
+pub fn stroke_polygon(polygon: &Polygon) -> Mesh {
+    stroke(polygon.get_vertices(), true, Join::Round)
+}
 #[allow(dead_code)]
 pub fn triangle() -> Mesh {
     let vertices = vec![
         0.0,
         1.0,
         0.0,
-        -0.8660254037844386,
+        -0.866_025_4,
         -0.5,
         0.0,
-        0.8660254037844386,
+        0.866_025_4,
         -0.5,
         0.0,
     ];
@@ -72,6 +233,8 @@ pub fn triangle() -> Mesh {
         indices,
         texture_coords: None,
         normals: None,
+        neighbours: None,
+        distances: None,
     }
 }
 
@@ -86,3 +249,42 @@ pub fn textured_rectangle(texture: Texture) -> Result<Mesh, JsValue> {
     mesh.texture_coords = Some(text_coords);
     Ok(mesh)
 }
+/// Glyph quads of `text`, in ems from the start of the first baseline,
+/// textured with the atlas of `font`.
+pub fn text(font: &Font, text: &str) -> Mesh {
+    let quads = font.layout(text);
+    let mut vertices = Vec::with_capacity(quads.len() * 12);
+    let mut coords = Vec::with_capacity(quads.len() * 8);
+    let mut indices = Vec::with_capacity(quads.len() * 6);
+    for (i, quad) in quads.iter().enumerate() {
+        let [u0, v0, u1, v1] = quad.texture_coords;
+        vertices.extend_from_slice(&[
+            quad.left,
+            quad.top,
+            0.0,
+            quad.right,
+            quad.top,
+            0.0,
+            quad.left,
+            quad.bottom,
+            0.0,
+            quad.right,
+            quad.bottom,
+            0.0,
+        ]);
+        coords.extend_from_slice(&[u0, v0, u1, v0, u0, v1, u1, v1]);
+        let first = i as u32 * 4;
+        indices.extend([0, 1, 2, 2, 1, 3].iter().map(|index| first + index));
+    }
+    Mesh {
+        vertices,
+        indices,
+        texture_coords: Some(TextureCoords {
+            texture: font.atlas.clone(),
+            coords,
+        }),
+        normals: None,
+        neighbours: None,
+        distances: None,
+    }
+}
@@ -1,36 +1,86 @@
 extern crate wasm_bindgen;
 extern crate web_sys;
 
+mod attributes;
+pub mod backend;
 mod camera;
 mod element;
+pub mod events;
+pub mod formats;
+mod handles;
+mod history;
+mod labels;
 mod loader;
 mod mesh;
 pub mod meshes;
+mod motion;
+mod orbit;
+mod scene;
 mod shader_program;
+mod spatial;
+mod styles;
+mod text;
 mod texture;
 mod texture_coords;
+mod tools;
 mod vao;
 
-use super::alg::{utils, Mat4, Vec4};
+use super::alg::{utils, Mat4, Polygon, Vec4};
+pub use attributes::{AttributeSpec, AttributeValue, Attributes};
+use backend::{Capability, GraphicsBackend, WebGlBackend};
 pub use camera::*;
 pub use element::*;
-pub use mesh::*;
+use formats::project::{self, Project, ProjectImage};
+use formats::{coco, voc, yolo, AreaData, ImageFrame};
+use handles::{Drag, DragAction, Handle};
+use history::{Edit, History, Snapshot};
+use labels::Labels;
+pub use labels::{parse_color, LabelClass};
+pub use motion::MotionParams;
+use motion::{InputState, Motion};
+pub use orbit::OrbitController;
+pub use scene::*;
 pub use shader_program::*;
+use styles::StrokeStyles;
+pub use styles::{FrameState, StrokeStyle};
+pub use text::{Font, TextLabel, TextSpace};
 pub use texture::*;
-pub use texture_coords::*;
+pub use tools::Tool;
+use tools::{Created, Tools};
 pub use vao::*;
 
 use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
 
-#[allow(dead_code)]
-enum RenderMode {
+/// Orthographic projection looking straight down at the plane, or
+/// perspective with a free camera.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderMode {
     M2D,
     M3D,
 }
 
+impl RenderMode {
+    pub fn from_name(name: &str) -> Result<RenderMode, String> {
+        match name {
+            "2d" => Ok(RenderMode::M2D),
+            "3d" => Ok(RenderMode::M3D),
+            _ => Err(format!("unknown render mode {}", name)),
+        }
+    }
+    pub fn get_name(self) -> &'static str {
+        match self {
+            RenderMode::M2D => "2d",
+            RenderMode::M3D => "3d",
+        }
+    }
+}
+
 #[wasm_bindgen]
 extern "C" {
     pub fn alert(s: &str);
@@ -39,63 +89,161 @@ extern "C" {
     pub fn log(s: &str);
 }
 
+/// Frames of the annotations, the hovered element and the selection.
+pub const FRAME_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub const HOVER_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+pub const SELECTED_COLOR: [f32; 4] = [0.0, 1.0, 1.0, 1.0];
+/// Height, in canvas pixels, of the captions of the annotations.
+pub const CAPTION_SIZE: f32 = 14.0;
+/// Width, in canvas pixels, of the frames.
+pub const LINE_WIDTH: f32 = 2.0;
+/// Opacity of the areas filled with the color of their label.
+pub const FILL_OPACITY: f32 = 0.25;
+
 #[derive(Serialize)]
 struct ContextOptions {
     antialias: bool,
 }
 
-pub struct Renderer<'a> {
-    context: WebGl2RenderingContext,
-    canvas: HtmlCanvasElement,
+pub struct Renderer {
+    backend: Rc<dyn GraphicsBackend>,
     pub camera: Camera,
     render_mode: RenderMode,
+    /// Drives the camera around the plane in 3D.
+    orbit: OrbitController,
     std_program: Option<Shader>,
     line_program: Option<Shader>,
-    plane: Option<Element>,
-    selected: Option<&'a Element>,
-    dragged: Option<&'a Element>,
-    hover: Option<&'a Element>,
+    scene: Scene,
+    selection: Vec<ElementId>,
+    drags: Vec<Drag>,
+    marquee: Option<((f32, f32), (f32, f32))>,
+    hover: Option<ElementId>,
+    text_program: Option<Shader>,
+    font: Option<Font>,
+    texts: BTreeMap<u32, (TextLabel, Vao)>,
+    next_text: u32,
+    /// Whether class names and point values are drawn.
+    captions: bool,
+    /// Meshes of the captions drawn in the last frame, by text.
+    caption_vaos: RefCell<HashMap<String, Vao>>,
+    history: History,
+    handle: Option<Vao>,
+    tools: Tools,
+    preview: Option<Vao>,
+    /// Width of the frames in canvas pixels, whatever the zoom.
+    line_width: f32,
+    styles: StrokeStyles,
+    /// Elements that drags and group moves leave in place.
+    locked: BTreeSet<ElementId>,
+    /// Seconds elapsed, the marching ants follow it.
+    time: f32,
+    /// Scales the opacity of every fill, 0 hides them.
+    overlay_opacity: f32,
+    /// Smallest and largest zoom of the 2D camera.
+    zoom_limits: (f32, f32),
+    input: InputState,
+    /// Glide of the view, from the held keys or a released pan.
+    motion: Motion,
+    motion_params: MotionParams,
+    /// Whether the view keeps gliding after a pan.
+    inertia: bool,
+    /// Pixels panned since the last frame and the velocity they gave, while
+    /// a pan is going on.
+    pan_drag: Option<((f32, f32), (f32, f32))>,
+    /// Animated move of the camera, any other camera control stops it.
+    flight: Option<Flight>,
+    labels: Labels,
+    /// Class given to the elements drawn with the tools.
+    active_label: Option<u32>,
+    changed: bool,
+    change_listener: Option<Rc<dyn Fn()>>,
     mouse_x: i32,
     mouse_y: i32,
 }
-pub static mut RENDERER: Option<Box<Renderer>> = None;
 
-impl<'a> Renderer<'a> {
+impl Renderer {
     const FOV: f32 = 90.;
     const NEAR_PLANE: f32 = 0.1;
     const FAR_PLANE: f32 = 1000.;
+    /// Zoom of a pixel of wheel scroll, as a power of e.
+    const WHEEL_SPEED: f32 = 0.001;
+    /// Zoom of a pixel of trackpad pinch, which scrolls much less.
+    const PINCH_SPEED: f32 = 0.01;
+    /// Seconds the camera takes to fly to a new view.
+    const FLIGHT_DURATION: f32 = 0.6;
+    /// Room left around the elements the camera fits, as a part of their
+    /// size.
+    const FIT_PADDING: f32 = 0.1;
     #[allow(dead_code)]
     pub fn get_aspect_ratio(&self) -> f32 {
-        self.canvas.width() as f32 / self.canvas.height() as f32
+        self.get_width() as f32 / self.get_height() as f32
     }
-    #[allow(dead_code)]
-    fn check_hover(&'a self) -> Option<&Element> {
-        let camera = &self.camera;
-        let x = self.mouse_x;
-        let y = self.mouse_y;
-        if let Some(ref plane) = self.plane {
-            let mat = self.get_projection_matrix() * camera.get_view_matrix();
-            let mat = mat.inverse().unwrap();
-            let glx = x as f32 * 2.0 / self.get_width() as f32 - 1.0;
-            let gly = y as f32 * 2.0 / self.get_height() as f32 - 1.0;
-            let gly = gly * -1.0;
-
-            // work because two 2d
-            let mwp = mat.mul(&Vec4([glx, gly, 0., 1.0]));
-            let x = mwp[0];
-            let y = mwp[1];
-            let element = plane.select(x, y);
-            return element;
-        }
-        None
+    /// Unprojects a point of the canvas on the z = 0 plane. Past the
+    /// horizon the ray stops at the far plane.
+    fn to_world(&self, x: i32, y: i32) -> (f32, f32) {
+        let aspect_ratio = self.get_aspect_ratio();
+        let glx = x as f32 * 2.0 / self.get_width() as f32 - 1.0;
+        let gly = y as f32 * 2.0 / self.get_height() as f32 - 1.0;
+        let gly = -gly;
+
+        // the ray through the pixel in view space, the camera looks down -z
+        let (origin, direction) = match self.render_mode {
+            RenderMode::M2D => {
+                let zoom = self.camera.get_zoom();
+                let origin = Vec4([glx * zoom, gly * zoom / aspect_ratio, 0.0, 1.0]);
+                (origin, Vec4([0.0, 0.0, -1.0, 0.0]))
+            }
+            RenderMode::M3D => {
+                let spread = utils::to_radians(Self::FOV * 0.5).tan();
+                let direction = Vec4([glx * spread * aspect_ratio, gly * spread, -1.0, 0.0]);
+                (Vec4([0.0, 0.0, 0.0, 1.0]), direction)
+            }
+        };
+        let view = self.camera.get_view_matrix().inverse().unwrap();
+        let (origin, direction) = (view.mul(&origin), view.mul(&direction));
+        let t = if direction[2] != 0.0 {
+            (-origin[2] / direction[2]).clamp(0.0, Self::FAR_PLANE)
+        } else {
+            0.0
+        };
+        (origin[0] + direction[0] * t, origin[1] + direction[1] * t)
+    }
+    /// Part of the plane on the canvas, `None` when the camera looks at it
+    /// in perspective.
+    fn get_view(&self) -> Option<Rectangle> {
+        if !matches!(self.render_mode, RenderMode::M2D) {
+            return None;
+        }
+        let (width, height) = (self.get_width() as i32, self.get_height() as i32);
+        let corners = [(0, 0), (width, 0), (0, height), (width, height)];
+        let corners: Vec<(f32, f32)> = corners.iter().map(|&(x, y)| self.to_world(x, y)).collect();
+        let (mut min, mut max) = (corners[0], corners[0]);
+        for &(x, y) in &corners[1..] {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        Some(Rectangle::new(min.0, min.1, max.0 - min.0, max.1 - min.1))
+    }
+    /// Size of a canvas pixel in world units, at the center of the canvas
+    /// in perspective.
+    fn get_pixel_size(&self) -> f32 {
+        let (x, y) = (self.get_width() as i32 / 2, self.get_height() as i32 / 2);
+        let (x0, y0) = self.to_world(x, y);
+        let (x1, y1) = self.to_world(x + 1, y);
+        (x1 - x0).hypot(y1 - y0)
+    }
+    fn check_hover(&self) -> Option<ElementId> {
+        self.scene.get_plane()?;
+        let (x, y) = self.to_world(self.mouse_x, self.mouse_y);
+        self.scene.select(x, y)
     }
     #[allow(dead_code)]
     pub fn get_width(&self) -> u32 {
-        self.canvas.width()
+        self.backend.drawing_buffer_size().0
     }
     #[allow(dead_code)]
     pub fn get_height(&self) -> u32 {
-        self.canvas.height()
+        self.backend.drawing_buffer_size().1
     }
     #[allow(dead_code)]
     pub fn get_std_program(&self) -> &Shader {
@@ -113,6 +261,282 @@ impl<'a> Renderer<'a> {
     pub fn set_line_program(&mut self, program: Shader) {
         self.line_program = Some(program);
     }
+    /// Program drawing the glyphs of a signed distance field font, text is
+    /// only drawn once it and a font are set.
+    pub fn set_text_program(&mut self, program: Shader) {
+        self.text_program = Some(program);
+    }
+    /// Replaces the font, rebuilding the text labels.
+    pub fn set_font(&mut self, font: Font) -> Result<(), String> {
+        for (label, vao) in self.texts.values_mut() {
+            *vao = Vao::new(&self.backend, meshes::text(&font, &label.text))?;
+        }
+        self.caption_vaos.borrow_mut().clear();
+        self.font = Some(font);
+        Ok(())
+    }
+    /// Places a text label, see `TextLabel`.
+    pub fn add_text(&mut self, label: TextLabel) -> Result<u32, String> {
+        let font = self.font.as_ref().ok_or("no font is loaded")?;
+        if label.size.is_nan() || label.size <= 0.0 {
+            return Err("the text needs a positive size".into());
+        }
+        let vao = Vao::new(&self.backend, meshes::text(font, &label.text))?;
+        let id = self.next_text;
+        self.next_text += 1;
+        self.texts.insert(id, (label, vao));
+        Ok(id)
+    }
+    pub fn remove_text(&mut self, id: u32) -> bool {
+        self.texts.remove(&id).is_some()
+    }
+    pub fn get_captions(&self) -> bool {
+        self.captions
+    }
+    pub fn set_captions(&mut self, captions: bool) {
+        self.captions = captions;
+    }
+    pub fn get_line_width(&self) -> f32 {
+        self.line_width
+    }
+    pub fn set_line_width(&mut self, width: f32) -> Result<(), String> {
+        if width.is_nan() || width <= 0.0 {
+            return Err("the line width must be positive".into());
+        }
+        self.line_width = width;
+        Ok(())
+    }
+    pub fn get_stroke_style(&self, state: FrameState) -> StrokeStyle {
+        self.styles.get(state)
+    }
+    pub fn set_stroke_style(
+        &mut self,
+        state: FrameState,
+        style: StrokeStyle,
+    ) -> Result<(), String> {
+        self.styles.set(state, style)
+    }
+    /// Advances the animations and the camera by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        if let Some((moved, _)) = self.pan_drag.filter(|_| dt > 0.0) {
+            let velocity = (moved.0 / dt, moved.1 / dt);
+            self.pan_drag = Some(((0.0, 0.0), velocity));
+        }
+        if let Some(flight) = &mut self.flight {
+            self.camera = flight.advance(dt);
+            if flight.is_done() {
+                self.flight = None;
+            }
+            if self.render_mode == RenderMode::M3D {
+                // the orbit picks up from wherever the flight is stopped
+                let (pitch, yaw) = (self.camera.get_pitch(), self.camera.get_yaw());
+                let (pitch, yaw) = (utils::to_radians(pitch), utils::to_radians(yaw));
+                let distance = self.camera.get_position()[2] / (pitch.cos() * yaw.cos());
+                if distance > 0.0 {
+                    self.orbit.follow(&self.camera, distance);
+                }
+            }
+            self.hover = self.check_hover();
+        }
+        let thrust = self.input.get_thrust();
+        if self.motion.is_still() && thrust == (0.0, 0.0) {
+            return;
+        }
+        if thrust != (0.0, 0.0) {
+            self.flight = None;
+        }
+        let (motion, moved) = motion::step(self.motion, thrust, dt, &self.motion_params);
+        self.motion = motion;
+        if moved != (0.0, 0.0) {
+            self.move_view(moved.0, moved.1);
+        }
+    }
+    #[allow(dead_code)]
+    pub fn get_motion_params(&self) -> MotionParams {
+        self.motion_params
+    }
+    pub fn set_motion_params(&mut self, params: MotionParams) -> Result<(), String> {
+        params.check()?;
+        self.motion_params = params;
+        Ok(())
+    }
+    pub fn get_inertia(&self) -> bool {
+        self.inertia
+    }
+    pub fn set_inertia(&mut self, inertia: bool) {
+        self.inertia = inertia;
+    }
+    /// Tangent of half the vertical field of view, `None` in 2D.
+    fn get_spread(&self) -> Option<f32> {
+        match self.render_mode {
+            RenderMode::M2D => None,
+            RenderMode::M3D => Some(utils::to_radians(Self::FOV * 0.5).tan()),
+        }
+    }
+    /// Flies the camera to face the plane at `(x, y)` with the 2D `zoom`,
+    /// within the zoom limits.
+    pub fn fly_to(&mut self, x: f32, y: f32, zoom: f32, easing: Easing) -> Result<(), String> {
+        if !x.is_finite() || !y.is_finite() || zoom.is_nan() || zoom <= 0.0 {
+            return Err(format!("cannot fly to {}, {} with zoom {}", x, y, zoom));
+        }
+        let (min, max) = self.zoom_limits;
+        let zoom = zoom.clamp(min, max);
+        let to = Camera::facing(x, y, zoom, self.get_aspect_ratio(), self.get_spread());
+        let from = self.camera.clone();
+        self.flight = Some(Flight::new(from, to, Self::FLIGHT_DURATION, easing));
+        self.motion = Motion::default();
+        Ok(())
+    }
+    /// Flies the camera to frame `rect`, see `fly_to`.
+    pub fn fit_to(&mut self, rect: &Rectangle, easing: Easing) -> Result<(), String> {
+        let aspect_ratio = self.get_aspect_ratio();
+        let camera = Camera::fit_to(rect, aspect_ratio, Self::FIT_PADDING, None);
+        let (x, y) = rect.get_center();
+        self.fly_to(x, y, camera.get_zoom(), easing)
+    }
+    /// Frames the plane, an area or a point.
+    pub fn fit_to_element(&mut self, id: ElementId, easing: Easing) -> Result<(), String> {
+        let rect = self
+            .scene
+            .get(id)
+            .ok_or_else(|| format!("no element with id {}", id.0))?
+            .rect;
+        self.fit_to(&rect, easing)
+    }
+    pub fn is_flying(&self) -> bool {
+        self.flight.is_some()
+    }
+    pub fn get_render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+    /// Switches the projection and the camera controls. The new camera
+    /// looks at the same point of the plane and shows it at the same size,
+    /// going back to 2D drops the tilt.
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        if mode == self.render_mode {
+            return;
+        }
+        let aspect_ratio = self.get_aspect_ratio();
+        // half the height of the view over its distance in perspective
+        let spread = utils::to_radians(Self::FOV * 0.5).tan();
+        let zoom = self.camera.get_zoom();
+        self.flight = None;
+        self.camera = match mode {
+            RenderMode::M3D => {
+                let position = self.camera.get_position();
+                let camera =
+                    Camera::facing(position[0], position[1], zoom, aspect_ratio, Some(spread));
+                self.orbit.follow(&camera, camera.get_position()[2]);
+                camera
+            }
+            RenderMode::M2D => {
+                let (width, height) = (self.get_width() as i32, self.get_height() as i32);
+                let (x, y) = self.to_world(width / 2, height / 2);
+                let position = self.camera.get_position();
+                let distance = Vec4([position[0] - x, position[1] - y, position[2], 0.0]).modulo();
+                let zoom = if distance > 0.0 {
+                    distance * spread * aspect_ratio
+                } else {
+                    zoom
+                };
+                Camera::facing(x, y, zoom, aspect_ratio, None)
+            }
+        };
+        self.render_mode = mode;
+        self.hover = self.check_hover();
+    }
+    #[allow(dead_code)]
+    pub fn get_orbit(&self) -> &OrbitController {
+        &self.orbit
+    }
+    pub fn set_orbit_distance_limits(&mut self, min: f32, max: f32) -> Result<(), String> {
+        self.orbit.set_distance_limits(min, max)
+    }
+    pub fn set_orbit_polar_limits(&mut self, min: f32, max: f32) -> Result<(), String> {
+        self.orbit.set_polar_limits(min, max)
+    }
+    /// Applies a gesture of the orbit controller to the 3D camera, taking
+    /// over wherever the camera was moved since.
+    fn move_orbit(&mut self, gesture: impl FnOnce(&mut OrbitController)) {
+        self.orbit.follow(&self.camera, self.orbit.get_distance());
+        gesture(&mut self.orbit);
+        self.camera = self.orbit.get_camera(self.camera.get_zoom());
+        self.hover = self.check_hover();
+    }
+    /// Drags the view by `(dx, dy)` pixels, in 3D the orbit target follows.
+    /// The view glides on after `pan_end` with inertia.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.flight = None;
+        self.motion = Motion::default();
+        let (moved, velocity) = self.pan_drag.unwrap_or_default();
+        self.pan_drag = Some(((moved.0 + dx, moved.1 + dy), velocity));
+        self.move_view(dx, dy);
+    }
+    pub fn pan_end(&mut self) {
+        if let Some((_, velocity)) = self.pan_drag.take().filter(|_| self.inertia) {
+            self.motion = Motion { velocity };
+        }
+    }
+    fn move_view(&mut self, dx: f32, dy: f32) {
+        match self.render_mode {
+            RenderMode::M2D => {
+                let pixel_size = self.get_pixel_size();
+                let step = Vec4([-dx * pixel_size, dy * pixel_size, 0.0, 0.0]);
+                self.camera.set_position(self.camera.get_position() + step);
+                self.hover = self.check_hover();
+            }
+            RenderMode::M3D => {
+                let spread = utils::to_radians(Self::FOV * 0.5).tan();
+                let height = self.get_height() as f32;
+                self.move_orbit(|orbit| {
+                    let pixel_size = orbit.get_distance() * spread * 2.0 / height;
+                    orbit.pan(dx, dy, pixel_size)
+                });
+            }
+        }
+    }
+    /// Zooms by a wheel scroll of `delta` pixels at `(x, y)`, keeping the
+    /// point of the plane under the cursor in place. Trackpads pinch with
+    /// `ctrl_key`. In 3D the camera moves towards the orbit target.
+    pub fn wheel(&mut self, x: i32, y: i32, delta: f32, ctrl_key: bool) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        let speed = if ctrl_key {
+            Self::PINCH_SPEED
+        } else {
+            Self::WHEEL_SPEED
+        };
+        let factor = (delta * speed).exp();
+        self.flight = None;
+        match self.render_mode {
+            RenderMode::M2D => {
+                let before = self.to_world(x, y);
+                let (min, max) = self.zoom_limits;
+                let zoom = (self.camera.get_zoom() * factor).clamp(min, max);
+                self.camera.set_zoom(zoom);
+                let after = self.to_world(x, y);
+                let step = Vec4([before.0 - after.0, before.1 - after.1, 0.0, 0.0]);
+                self.camera.set_position(self.camera.get_position() + step);
+                self.hover = self.check_hover();
+            }
+            RenderMode::M3D => self.move_orbit(|orbit| orbit.dolly(factor)),
+        }
+    }
+    #[allow(dead_code)]
+    pub fn get_zoom_limits(&self) -> (f32, f32) {
+        self.zoom_limits
+    }
+    /// Narrowest and widest 2D views, as the zoom of the camera.
+    pub fn set_zoom_limits(&mut self, min: f32, max: f32) -> Result<(), String> {
+        if min.is_nan() || min <= 0.0 || max.is_nan() || max < min {
+            return Err(format!("invalid zoom limits {} and {}", min, max));
+        }
+        self.zoom_limits = (min, max);
+        let zoom = self.camera.get_zoom().clamp(min, max);
+        self.camera.set_zoom(zoom);
+        Ok(())
+    }
     #[allow(dead_code)]
     pub fn get_projection_matrix(&self) -> Mat4 {
         let aspect_ratio = self.get_aspect_ratio();
@@ -131,164 +555,2233 @@ impl<'a> Renderer<'a> {
             ),
         }
     }
-    fn new() -> Result<Renderer<'a>, JsValue> {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let canvas = document.get_element_by_id("canvas").unwrap();
-        let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
+    pub fn from_canvas(canvas: &HtmlCanvasElement) -> Result<Renderer, JsValue> {
         let context_options = ContextOptions { antialias: true };
+        #[allow(deprecated)]
         let context_options =
             JsValue::from_serde(&context_options).expect("could not serialize context options");
         let context = canvas
             .get_context_with_context_options("webgl2", &context_options)?
-            .unwrap()
+            .ok_or_else(|| JsValue::from_str("webgl2 is not supported"))?
             .dyn_into::<WebGl2RenderingContext>()?;
-
-        let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
-            // const LEFT_BUTTON: u16 = 1;
-            let renderer = Renderer::get_instance();
-            renderer.mouse_x = event.offset_x();
-            renderer.mouse_y = event.offset_y();
-            let element = renderer.check_hover();
-
-            let renderer = Renderer::get_instance();
-            renderer.hover = element;
-            let camera = &mut renderer.camera;
-            // left button not pressed
-            // if event.buttons() & LEFT_BUTTON == 0 {
-            //     return;
-            // }
-            if !event.ctrl_key() {
-                return;
-            }
-            match renderer.render_mode {
-                RenderMode::M2D => {
-                    camera.mouse_move_2d(event.movement_x() as f32, event.movement_y() as f32);
-                }
-                RenderMode::M3D => {
-                    camera.mouse_move_3d(event.movement_x() as f32, event.movement_y() as f32);
-                }
-            }
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())?;
-        closure.forget();
-
-        let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
-            let renderer = Renderer::get_instance();
-            let camera = &mut renderer.camera;
-            let key = event.key();
-            match renderer.render_mode {
-                RenderMode::M2D => {
-                    camera.key_move_2d(key.as_ref());
-                }
-                RenderMode::M3D => {
-                    camera.key_move_3d(key.as_ref());
-                }
-            }
-
-            let element = renderer.check_hover();
-            let renderer = Renderer::get_instance();
-            renderer.hover = element;
-        }) as Box<dyn FnMut(_)>);
-        canvas.add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())?;
-        closure.forget();
-        context.enable(WebGl2RenderingContext::DEPTH_TEST);
-        return Ok(Renderer {
-            context,
-            canvas,
+        Ok(Self::with_backend(Rc::new(WebGlBackend::new(context))))
+    }
+    pub fn with_backend(backend: Rc<dyn GraphicsBackend>) -> Renderer {
+        backend.enable(Capability::DepthTest);
+        let handle = Vao::new(&backend, meshes::stroke_rectangle(1.0, 1.0)).ok();
+        Renderer {
+            backend,
             camera: Camera::default(),
             render_mode: RenderMode::M2D,
+            orbit: OrbitController::new(Vec4([0.0, 0.0, 0.0, 1.0]), 1.0),
             std_program: None,
             line_program: None,
-            plane: None,
-            selected: None,
-            dragged: None,
+            scene: Scene::new(),
+            selection: Vec::new(),
+            drags: Vec::new(),
+            marquee: None,
             hover: None,
+            text_program: None,
+            font: None,
+            texts: BTreeMap::new(),
+            next_text: 0,
+            captions: true,
+            caption_vaos: RefCell::new(HashMap::new()),
+            history: History::new(History::DEFAULT_LIMIT),
+            handle,
+            tools: Tools::new(),
+            preview: None,
+            line_width: LINE_WIDTH,
+            styles: StrokeStyles::default(),
+            locked: BTreeSet::new(),
+            time: 0.0,
+            overlay_opacity: 1.0,
+            zoom_limits: (1.0, 100_000.0),
+            input: InputState::default(),
+            motion: Motion::default(),
+            motion_params: MotionParams::default(),
+            inertia: true,
+            pan_drag: None,
+            flight: None,
+            labels: Labels::default(),
+            active_label: None,
+            changed: false,
+            change_listener: None,
             mouse_x: 0,
             mouse_y: 0,
-        });
+        }
+    }
+    pub fn mouse_move(&mut self, x: i32, y: i32, dx: f32, dy: f32, ctrl_key: bool) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        if !self.drags.is_empty() {
+            let drags = self.drags.clone();
+            let (x, y) = self.to_world(x, y);
+            // a step that would break the tree is skipped, the next one may
+            // still be valid
+            let _ = self.drag_to(&drags, x, y);
+            return;
+        }
+        if let Some((start, _)) = self.marquee {
+            self.marquee = Some((start, self.to_world(x, y)));
+            self.update_preview();
+        }
+        if self.tools.get_sketch().is_some() {
+            let (x, y) = self.to_plane(x, y);
+            self.tools.motion(x, y);
+            self.update_preview();
+        }
+        self.hover = self.check_hover();
+        if !ctrl_key {
+            return;
+        }
+        self.flight = None;
+        match self.render_mode {
+            RenderMode::M2D => {
+                self.camera.mouse_move_2d(dx, dy);
+                let (min, max) = self.zoom_limits;
+                let zoom = self.camera.get_zoom().clamp(min, max);
+                self.camera.set_zoom(zoom);
+            }
+            RenderMode::M3D => self.move_orbit(|orbit| orbit.rotate(dx, dy)),
+        }
+    }
+    /// Hands the click to the active tool. The select tool grabs a handle
+    /// of the selected area, or else selects the element under the cursor
+    /// and starts moving the selection, or else starts a marquee. With
+    /// `shift_key` the clicked element is toggled and the marquee adds to the
+    /// selection.
+    pub fn mouse_down(&mut self, x: i32, y: i32, shift_key: bool) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        if self.tools.get_tool() != Tool::Select {
+            let (x, y) = self.to_plane(x, y);
+            let created = self.tools.press(x, y);
+            self.update_preview();
+            // a shape that does not fit is dropped, like a drag step
+            let _ = self.create(created);
+            return;
+        }
+        let start = self.to_world(x, y);
+        let handle = self
+            .get_selected_area()
+            .and_then(|area| Handle::at(&area.rect, self.get_pixel_size(), start.0, start.1));
+        let ids = match (handle, self.get_selected()) {
+            (Some(Handle::Rotate), Some(id)) => vec![(id, DragAction::Rotate)],
+            (Some(handle), Some(id)) => vec![(id, DragAction::Resize(handle))],
+            _ => {
+                self.hover = self.check_hover();
+                let clicked = self.hover.filter(|&id| Some(id) != self.scene.get_plane());
+                match clicked {
+                    Some(id) if shift_key => {
+                        self.toggle_selected(id);
+                        return;
+                    }
+                    Some(id) => {
+                        if !self.selection.contains(&id) {
+                            self.selection = vec![id];
+                        }
+                        // the clicked element leads, the others follow it
+                        let mut ids = vec![id];
+                        ids.extend(self.get_selection_roots().into_iter().filter(|&o| o != id));
+                        ids.into_iter().map(|id| (id, DragAction::Move)).collect()
+                    }
+                    None => {
+                        if !shift_key {
+                            self.selection.clear();
+                        }
+                        self.marquee = Some((start, start));
+                        return;
+                    }
+                }
+            }
+        };
+        self.drags = ids
+            .into_iter()
+            .filter(|(id, _)| !self.locked.contains(id))
+            .filter_map(|(id, action)| Drag::new(&self.scene, id, action, start))
+            .collect();
+        if !self.drags.is_empty() {
+            self.history.begin_group();
+        }
+    }
+    pub fn mouse_up(&mut self, x: i32, y: i32) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        if !self.drags.is_empty() {
+            self.drags.clear();
+            self.history.end_group();
+        }
+        if let Some((start, _)) = self.marquee.take() {
+            let end = self.to_world(x, y);
+            let marquee = Rectangle::new(
+                start.0.min(end.0),
+                start.1.min(end.1),
+                (end.0 - start.0).abs(),
+                (end.1 - start.1).abs(),
+            );
+            for id in self.scene.annotations_in(&marquee) {
+                let element = self.scene.get(id).unwrap();
+                let is_area = matches!(element.kind, ElementKind::AREA(_));
+                if is_area && !self.selection.contains(&id) {
+                    self.selection.push(id);
+                }
+            }
+            self.update_preview();
+        }
+        let (wx, wy) = self.to_plane(x, y);
+        let created = self.tools.release(wx, wy);
+        self.update_preview();
+        let _ = self.create(created);
+        self.hover = self.check_hover();
+    }
+    /// Closes the polygon being drawn.
+    pub fn double_click(&mut self, x: i32, y: i32) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        self.close_sketch();
+    }
+    fn close_sketch(&mut self) {
+        if let Ok(created) = self.tools.close() {
+            self.update_preview();
+            let _ = self.create(created);
+        }
+    }
+    pub fn get_tool(&self) -> Tool {
+        self.tools.get_tool()
+    }
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tools.set_tool(tool);
+        self.update_preview();
+    }
+    /// Unprojects a point of the canvas and brings it inside the plane, where
+    /// the tools draw.
+    fn to_plane(&self, x: i32, y: i32) -> (f32, f32) {
+        let (x, y) = self.to_world(x, y);
+        match self
+            .scene
+            .get_plane()
+            .and_then(|plane| self.scene.get(plane))
+        {
+            Some(plane) => {
+                let (left, bottom) = plane.rect.down_left_corner;
+                let (width, height) = plane.rect.dimensions;
+                (
+                    x.max(left).min(left + width),
+                    y.max(bottom).min(bottom + height),
+                )
+            }
+            None => (x, y),
+        }
+    }
+    fn update_preview(&mut self) {
+        let marquee = self
+            .marquee
+            .map(|(start, end)| vec![start, (end.0, start.1), end, (start.0, end.1)]);
+        self.preview = marquee
+            .or_else(|| self.tools.get_preview())
+            .map(|outline| meshes::stroke(&outline, true, meshes::Join::Round))
+            .and_then(|mesh| Vao::new(&self.backend, mesh).ok());
+    }
+    /// Adds what a tool drew, with the active label, and selects it. Points
+    /// go to the area under them.
+    fn create(&mut self, created: Option<Created>) -> Result<(), String> {
+        self.history.begin_group();
+        let created = self.create_labeled(created);
+        self.history.end_group();
+        created
+    }
+    fn create_labeled(&mut self, created: Option<Created>) -> Result<(), String> {
+        let id = match created {
+            Some(Created::Area(polygon)) => self.create_area(polygon)?,
+            Some(Created::Point(x, y)) => {
+                let area = self
+                    .scene
+                    .select(x, y)
+                    .and_then(|id| match self.scene.get(id)?.kind {
+                        ElementKind::AREA(_) => Some(id),
+                        ElementKind::POINT(_) => self.scene.get(id)?.parent,
+                        ElementKind::PLANE(_) => None,
+                    })
+                    .ok_or("points are placed inside an area")?;
+                self.create_point(area, x, y, 0.0)?
+            }
+            None => return Ok(()),
+        };
+        if self.active_label.is_some() {
+            self.set_label(id, self.active_label)?;
+        }
+        self.selection = vec![id];
+        Ok(())
+    }
+    /// The area showing handles, when it is the only one selected.
+    fn get_selected_area(&self) -> Option<&Element> {
+        let element = match self.selection[..] {
+            [id] => self.scene.get(id)?,
+            _ => return None,
+        };
+        match element.kind {
+            ElementKind::AREA(_) => Some(element),
+            _ => None,
+        }
     }
-    pub fn get_instance() -> &'static mut Renderer<'a> {
-        // return Renderer::new().unwrap();
-        unsafe {
-            if RENDERER.is_none() {
-                RENDERER = Some(Box::new(Renderer::new().unwrap()))
+    fn drag_to(&mut self, drags: &[Drag], x: f32, y: f32) -> Result<(), String> {
+        let drag = &drags[0];
+        let id = drag.id;
+        let plane = self
+            .scene
+            .get_plane()
+            .and_then(|plane| self.scene.get(plane))
+            .ok_or("the plane is not created")?
+            .rect;
+        let moves = |target: &dyn Fn((f32, f32)) -> (f32, f32)| -> Vec<(ElementId, f32, f32)> {
+            drag.points
+                .iter()
+                .filter_map(|&(point, original)| {
+                    let (x, y) = self.scene.get(point)?.rect.get_center();
+                    let (tx, ty) = target(original);
+                    Some((point, tx - x, ty - y))
+                })
+                .collect()
+        };
+        match (drag.action, &drag.polygon) {
+            (DragAction::Move, _) => {
+                let (mut dx, mut dy) = (x - drag.start.0, y - drag.start.1);
+                // areas slide along the border of the plane, together
+                let (left, bottom) = plane.down_left_corner;
+                for area in drags.iter().filter(|drag| drag.polygon.is_some()) {
+                    let (ox, oy) = area.rect.down_left_corner;
+                    let (w, h) = area.rect.dimensions;
+                    dx = dx.max(left - ox).min(left + plane.dimensions.0 - w - ox);
+                    dy = dy
+                        .max(bottom - oy)
+                        .min(bottom + plane.dimensions.1 - h - oy);
+                }
+                let mut result = Ok(());
+                for drag in drags {
+                    let current = self.scene.get(drag.id).ok_or("dragged element is gone")?;
+                    let (cx, cy) = current.rect.down_left_corner;
+                    let (ox, oy) = drag.rect.down_left_corner;
+                    // a point stuck at the border of its area does not stop
+                    // the others
+                    result = result.and(self.move_element(drag.id, ox + dx - cx, oy + dy - cy));
+                }
+                result
+            }
+            (DragAction::Resize(handle), Some(polygon)) => {
+                let (left, bottom) = plane.down_left_corner;
+                let x = x.max(left).min(left + plane.dimensions.0);
+                let y = y.max(bottom).min(bottom + plane.dimensions.1);
+                let rect = handle.resize(&drag.rect, x, y);
+                let polygon = polygon.map(|x, y| handles::stretch(&drag.rect, &rect, (x, y)));
+                let moves = moves(&|p| handles::stretch(&drag.rect, &rect, p));
+                self.reshape_area(id, polygon, &moves)
+            }
+            (DragAction::Rotate, Some(polygon)) => {
+                let center = drag.rect.get_center();
+                let angle = (y - center.1).atan2(x - center.0)
+                    - (drag.start.1 - center.1).atan2(drag.start.0 - center.0);
+                let polygon = polygon.map(|x, y| handles::rotate(center, angle, (x, y)));
+                let moves = moves(&|p| handles::rotate(center, angle, p));
+                self.reshape_area(id, polygon, &moves)
             }
-            RENDERER.as_deref_mut().unwrap()
+            _ => Ok(()),
         }
     }
-    pub fn get_context_instance(&self) -> &WebGl2RenderingContext {
-        &self.context
+    pub fn key_down(&mut self, key: &str, ctrl_key: bool) {
+        match key {
+            "Escape" => {
+                self.tools.cancel();
+                self.update_preview();
+            }
+            "Enter" => self.close_sketch(),
+            "Delete" | "Backspace" => {
+                self.remove_selection();
+            }
+            "a" | "A" if ctrl_key => {
+                self.select_all();
+                return;
+            }
+            _ => {}
+        }
+        if let Some(class) = self.labels.get_by_hotkey(key).filter(|_| !ctrl_key) {
+            // the hotkey picks the class of the next elements too
+            self.active_label = Some(class.id);
+            let _ = self.set_selection_label(self.active_label);
+            return;
+        }
+        // held keys move the camera on every frame
+        if !ctrl_key {
+            self.input.press(key);
+        }
+    }
+    pub fn key_up(&mut self, key: &str) {
+        self.input.release(key);
+    }
+    /// Lets go of every key, the canvas gets no key up once it lost focus.
+    pub fn release_keys(&mut self) {
+        self.input.clear();
+    }
+    pub fn get_scene(&self) -> &Scene {
+        &self.scene
+    }
+    pub fn get_hover(&self) -> Option<ElementId> {
+        self.hover
+    }
+    /// The last element added to the selection.
+    pub fn get_selected(&self) -> Option<ElementId> {
+        self.selection.last().copied()
+    }
+    pub fn set_selected(&mut self, id: Option<ElementId>) {
+        self.set_selection(&id.into_iter().collect::<Vec<_>>());
+    }
+    pub fn get_selection(&self) -> &[ElementId] {
+        &self.selection
+    }
+    pub fn set_selection(&mut self, ids: &[ElementId]) {
+        self.selection.clear();
+        for &id in ids {
+            if self.scene.contains(id) && !self.selection.contains(&id) {
+                self.selection.push(id);
+            }
+        }
+    }
+    pub fn toggle_selected(&mut self, id: ElementId) {
+        match self.selection.iter().position(|&selected| selected == id) {
+            Some(index) => {
+                self.selection.remove(index);
+            }
+            None => self.set_selection(&[&self.selection[..], &[id]].concat()),
+        }
+    }
+    pub fn is_locked(&self, id: ElementId) -> bool {
+        self.locked.contains(&id)
+    }
+    /// Locked elements can still be selected and edited, but drags and
+    /// group moves leave them in place.
+    pub fn set_locked(&mut self, id: ElementId, locked: bool) -> Result<(), String> {
+        if !self.scene.contains(id) {
+            return Err(format!("no element with id {}", id.0));
+        }
+        if locked {
+            self.locked.insert(id);
+        } else {
+            self.locked.remove(&id);
+        }
+        Ok(())
+    }
+    /// Selects every area of the plane.
+    pub fn select_all(&mut self) {
+        self.selection = self.scene.get_areas().to_vec();
+    }
+    /// Selected elements that are not inside another selected element, the
+    /// ones group operations act on.
+    fn get_selection_roots(&self) -> Vec<ElementId> {
+        self.selection
+            .iter()
+            .copied()
+            .filter(|&id| {
+                let parent = self.scene.get(id).and_then(|element| element.parent);
+                !parent.is_some_and(|parent| self.selection.contains(&parent))
+            })
+            .collect()
+    }
+    pub fn get_dragged(&self) -> Option<ElementId> {
+        self.drags.first().map(|drag| drag.id)
+    }
+    /// Drops the interaction state that refers to elements no longer in the
+    /// scene.
+    fn forget_removed(&mut self) {
+        let scene = &self.scene;
+        let alive = |id: Option<ElementId>| id.filter(|id| scene.contains(*id));
+        self.selection.retain(|id| scene.contains(*id));
+        self.locked.retain(|id| scene.contains(*id));
+        if self.drags.iter().any(|drag| !scene.contains(drag.id)) {
+            self.drags.clear();
+            self.history.end_group();
+        }
+        self.hover = alive(self.hover);
+    }
+    pub fn get_backend(&self) -> &Rc<dyn GraphicsBackend> {
+        &self.backend
     }
     pub fn prepare(&self) {
-        let context = &self.context;
-        context.clear_color(0.0, 0.0, 1.0, 1.0);
-        context.clear(
-            WebGl2RenderingContext::COLOR_BUFFER_BIT | WebGl2RenderingContext::DEPTH_BUFFER_BIT,
-        );
+        let backend = &self.backend;
+        backend.clear_color(0.0, 0.0, 1.0, 1.0);
+        backend.clear(true, true);
+    }
+    /// Records an edit already applied to the scene.
+    fn record(&mut self, edit: Edit) {
+        self.history.push(edit);
+        self.changed = true;
+        self.forget_removed();
+        self.hover = self.check_hover();
     }
-    pub async fn create_area(&mut self, image_name: &str) -> Result<(), JsValue> {
-        if self.plane.is_none() {
-            return Err(JsValue::from("cannot create area if plane is not created"));
+    fn record_insert(&mut self, id: ElementId) {
+        if let Some(snapshot) = Snapshot::take(&self.scene, id) {
+            self.record(Edit::Insert(snapshot));
         }
-        let plane = self.plane.as_ref().unwrap();
-        let texture = Texture::new(image_name).await?;
-        let (width, height) = plane.rect.dimensions;
-        let mesh = meshes::textured_rectangle(texture)?;
-        let vao = Vao::new(mesh).map_err(|s| JsValue::from_str(&s))?;
-
-        let mesh = meshes::line_rectangle(width, height);
-        let frame = Vao::new(mesh).map_err(|s| JsValue::from_str(&s))?;
-
-        let area = Element::area(vao, frame, width, height);
-        if let ElementKind::PLANE(ref plane) = plane.kind {
-            // plane.areas.push();
+    }
+    /// Adds an area outlined by `polygon`, in world coordinates, to the plane.
+    pub fn create_area(&mut self, polygon: Polygon) -> Result<ElementId, String> {
+        let area = Element::area(&self.backend, polygon)?;
+        let id = self.scene.add_area(area)?;
+        self.record_insert(id);
+        Ok(id)
+    }
+    /// Adds a point at `(x, y)`, in world coordinates, to `area`.
+    pub fn create_point(
+        &mut self,
+        area: ElementId,
+        x: f32,
+        y: f32,
+        value: f32,
+    ) -> Result<ElementId, String> {
+        let point = Element::point(&self.backend, x, y, value)?;
+        let id = self.scene.add_point(area, point)?;
+        self.record_insert(id);
+        Ok(id)
+    }
+    pub fn set_point_value(&mut self, id: ElementId, value: f32) -> Result<(), String> {
+        let before = match self.scene.get_mut(id).map(|element| &mut element.kind) {
+            Some(ElementKind::POINT(point)) => std::mem::replace(&mut point.value, value),
+            _ => return Err(format!("element {} is not a point", id.0)),
+        };
+        self.record(Edit::SetValue {
+            id,
+            before,
+            after: value,
+        });
+        Ok(())
+    }
+    pub fn get_fill(&self, id: ElementId) -> Option<[f32; 4]> {
+        self.scene.get(id)?.fill
+    }
+    /// Gives an area its own fill color and opacity, `None` fills it with
+    /// the color of its label.
+    pub fn set_fill(&mut self, id: ElementId, fill: Option<[f32; 4]>) -> Result<(), String> {
+        Self::check_fill(fill)?;
+        let before = match self.scene.get_mut(id) {
+            Some(element) if matches!(element.kind, ElementKind::AREA(_)) => {
+                std::mem::replace(&mut element.fill, fill)
+            }
+            _ => return Err(format!("element {} is not an area", id.0)),
+        };
+        self.record(Edit::SetFill {
+            id,
+            before,
+            after: fill,
+        });
+        Ok(())
+    }
+    fn check_fill(fill: Option<[f32; 4]>) -> Result<(), String> {
+        if fill.is_some_and(|fill| !fill.iter().all(|c| (0.0..=1.0).contains(c))) {
+            return Err("fill components must be between 0 and 1".into());
         }
         Ok(())
     }
-    pub async fn create_plane(&mut self, image_name: &str) -> Result<(), JsValue> {
-        let texture = Texture::new(image_name).await?;
+    pub fn get_overlay_opacity(&self) -> f32 {
+        self.overlay_opacity
+    }
+    pub fn set_overlay_opacity(&mut self, opacity: f32) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err("the overlay opacity must be between 0 and 1".into());
+        }
+        self.overlay_opacity = opacity;
+        Ok(())
+    }
+    /// Moves an area, with its points, or a point. The element must stay
+    /// inside its parent.
+    pub fn move_element(&mut self, id: ElementId, dx: f32, dy: f32) -> Result<(), String> {
+        self.scene.check_move(id, dx, dy)?;
+        self.scene.translate(id, dx, dy);
+        self.record(Edit::Move { id, dx, dy });
+        Ok(())
+    }
+    /// Gives an area a new outline. It must fit in the plane and keep all of
+    /// its points inside.
+    pub fn set_polygon(&mut self, id: ElementId, polygon: Polygon) -> Result<(), String> {
+        self.reshape_area(id, polygon, &[])
+    }
+    /// Gives an area a new outline while moving some of its points by
+    /// `(dx, dy)`. The area must fit in the plane and keep all of its points
+    /// inside.
+    fn reshape_area(
+        &mut self,
+        id: ElementId,
+        polygon: Polygon,
+        moves: &[(ElementId, f32, f32)],
+    ) -> Result<(), String> {
+        let (before, parent) = match self.scene.get(id) {
+            Some(Element {
+                kind: ElementKind::AREA(area),
+                parent: Some(parent),
+                ..
+            }) => (area.polygon.clone(), *parent),
+            _ => return Err(format!("element {} is not an area", id.0)),
+        };
+        let plane = self.scene.get(parent).ok_or("the area has no plane")?;
+        if !plane.rect.contains_rect(&Rectangle::bounding(&polygon)) {
+            return Err(format!("area {} would leave the plane", id.0));
+        }
+        for point in self.scene.get_points(id) {
+            let (x, y) = self.scene.get(*point).unwrap().rect.get_center();
+            let (dx, dy) = moves
+                .iter()
+                .find(|(moved, _, _)| moved == point)
+                .map_or((0.0, 0.0), |&(_, dx, dy)| (dx, dy));
+            if !polygon.contains(x + dx, y + dy) {
+                return Err(format!("point {} would be outside of the area", point.0));
+            }
+        }
+        let edit = Edit::Reshape {
+            id,
+            before,
+            after: polygon,
+        };
+        edit.apply(&mut self.scene, &self.backend)?;
+        self.record(edit);
+        for &(point, dx, dy) in moves {
+            if dx != 0.0 || dy != 0.0 {
+                self.scene.translate(point, dx, dy);
+                self.record(Edit::Move { id: point, dx, dy });
+            }
+        }
+        Ok(())
+    }
+    /// Removes an area or a point, with everything it contains.
+    pub fn remove_element(&mut self, id: ElementId) -> bool {
+        let snapshot = match Snapshot::take(&self.scene, id) {
+            Some(snapshot) => snapshot,
+            None => return false,
+        };
+        self.scene.remove(id);
+        self.record(Edit::Remove(snapshot));
+        true
+    }
+    /// Removes the selected elements as a single undoable step.
+    pub fn remove_selection(&mut self) -> bool {
+        self.history.begin_group();
+        let mut removed = false;
+        for id in self.get_selection_roots() {
+            removed |= self.remove_element(id);
+        }
+        self.history.end_group();
+        removed
+    }
+    /// Moves the selected elements together, as a single undoable step.
+    /// Nothing moves if one of them would leave its parent.
+    pub fn move_selection(&mut self, dx: f32, dy: f32) -> Result<(), String> {
+        let mut roots = self.get_selection_roots();
+        roots.retain(|id| !self.locked.contains(id));
+        for &id in &roots {
+            self.scene.check_move(id, dx, dy)?;
+        }
+        self.history.begin_group();
+        for id in roots {
+            self.scene.translate(id, dx, dy);
+            self.record(Edit::Move { id, dx, dy });
+        }
+        self.history.end_group();
+        Ok(())
+    }
+    /// Gives the same value to every selected point, as a single undoable
+    /// step.
+    pub fn set_selection_value(&mut self, value: f32) -> Result<(), String> {
+        let points: Vec<ElementId> = self
+            .selection
+            .iter()
+            .copied()
+            .filter(|&id| matches!(self.scene.get(id).unwrap().kind, ElementKind::POINT(_)))
+            .collect();
+        if points.is_empty() {
+            return Err("no point is selected".into());
+        }
+        self.history.begin_group();
+        for id in points {
+            self.set_point_value(id, value)?;
+        }
+        self.history.end_group();
+        Ok(())
+    }
+    pub fn get_labels(&self) -> &[LabelClass] {
+        self.labels.get_classes()
+    }
+    /// Adds a label class or replaces the one with the same id. The new
+    /// attribute schema must accept the attributes of the elements of the
+    /// class.
+    pub fn define_label(&mut self, class: LabelClass) -> Result<(), String> {
+        for id in self.scene.annotations() {
+            let element = self.scene.get(id).unwrap();
+            if element.label == Some(class.id) {
+                for (name, value) in &element.attributes {
+                    attributes::check(&class.attributes, name, value)
+                        .map_err(|e| format!("element {}: {}", id.0, e))?;
+                }
+            }
+        }
+        self.labels.define(class)?;
+        self.changed = true;
+        Ok(())
+    }
+    /// Removes a class no element is labeled with.
+    pub fn remove_label(&mut self, id: u32) -> Result<(), String> {
+        let used = self
+            .scene
+            .annotations()
+            .into_iter()
+            .filter(|&element| self.scene.get(element).unwrap().label == Some(id))
+            .count();
+        if used > 0 {
+            return Err(format!("label {} is used by {} elements", id, used));
+        }
+        if !self.labels.remove(id) {
+            return Err(format!("no label with id {}", id));
+        }
+        if self.active_label == Some(id) {
+            self.active_label = None;
+        }
+        self.changed = true;
+        Ok(())
+    }
+    pub fn get_active_label(&self) -> Option<u32> {
+        self.active_label
+    }
+    pub fn set_active_label(&mut self, label: Option<u32>) -> Result<(), String> {
+        self.check_label(label)?;
+        self.active_label = label;
+        Ok(())
+    }
+    fn check_label(&self, label: Option<u32>) -> Result<(), String> {
+        Self::check_label_in(&self.labels, label)
+    }
+    fn check_label_in(labels: &Labels, label: Option<u32>) -> Result<(), String> {
+        match label {
+            Some(id) if labels.get(id).is_none() => Err(format!("no label with id {}", id)),
+            _ => Ok(()),
+        }
+    }
+    /// Checks the label and that the class of `labels` defines the
+    /// attributes.
+    fn check_attributes_in(
+        labels: &Labels,
+        label: Option<u32>,
+        values: &Attributes,
+    ) -> Result<(), String> {
+        Self::check_label_in(labels, label)?;
+        let specs = label
+            .and_then(|label| labels.get(label))
+            .map_or(&[][..], |class| &class.attributes);
+        for (name, value) in values {
+            attributes::check(specs, name, value)?;
+        }
+        Ok(())
+    }
+    pub fn get_label(&self, id: ElementId) -> Option<u32> {
+        self.scene.get(id)?.label
+    }
+    /// Assigns a class to an area or a point, `None` clears it. Attributes
+    /// the new class does not define are dropped in the same undoable step.
+    pub fn set_label(&mut self, id: ElementId, label: Option<u32>) -> Result<(), String> {
+        self.check_label(label)?;
+        let element = match self.scene.get_mut(id) {
+            Some(element) if element.parent.is_some() => element,
+            _ => return Err(format!("element {} is not an area or a point", id.0)),
+        };
+        let before = std::mem::replace(&mut element.label, label);
+        let labels = &self.labels;
+        let specs = label
+            .and_then(|label| labels.get(label))
+            .map_or(&[][..], |class| &class.attributes);
+        let kept = attributes::retain_valid(specs, &element.attributes);
+        let dropped: Vec<_> = element
+            .attributes
+            .iter()
+            .filter(|(name, _)| !kept.contains_key(*name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        element.attributes = kept;
+        self.history.begin_group();
+        self.record(Edit::SetLabel {
+            id,
+            before,
+            after: label,
+        });
+        for (name, value) in dropped {
+            self.record(Edit::SetAttribute {
+                id,
+                name,
+                before: Some(value),
+                after: None,
+            });
+        }
+        self.history.end_group();
+        Ok(())
+    }
+    /// Assigns a class to every selected element, as a single undoable step.
+    pub fn set_selection_label(&mut self, label: Option<u32>) -> Result<(), String> {
+        self.check_label(label)?;
+        if self.selection.is_empty() {
+            return Err("nothing is selected".into());
+        }
+        self.history.begin_group();
+        for id in self.selection.clone() {
+            // the plane has no label
+            let _ = self.set_label(id, label);
+        }
+        self.history.end_group();
+        Ok(())
+    }
+    pub fn get_attributes(&self, id: ElementId) -> Option<&Attributes> {
+        Some(&self.scene.get(id)?.attributes)
+    }
+    /// Sets an attribute of a labeled element, `None` removes it. The value
+    /// must match the schema of the label class.
+    pub fn set_attribute(
+        &mut self,
+        id: ElementId,
+        name: &str,
+        value: Option<AttributeValue>,
+    ) -> Result<(), String> {
+        let element = self
+            .scene
+            .get_mut(id)
+            .ok_or_else(|| format!("no element with id {}", id.0))?;
+        if let Some(value) = &value {
+            let labels = &self.labels;
+            let class = element
+                .label
+                .and_then(|label| labels.get(label))
+                .ok_or_else(|| format!("element {} has no label", id.0))?;
+            attributes::check(&class.attributes, name, value)?;
+        }
+        let before = match &value {
+            Some(value) => element.attributes.insert(name.into(), value.clone()),
+            None => element.attributes.remove(name),
+        };
+        if before == value {
+            return Ok(());
+        }
+        self.record(Edit::SetAttribute {
+            id,
+            name: name.into(),
+            before,
+            after: value,
+        });
+        Ok(())
+    }
+    /// Edits made until `end_edit_group` are undone in one step.
+    pub fn begin_edit_group(&mut self) {
+        self.history.begin_group();
+    }
+    pub fn end_edit_group(&mut self) {
+        self.history.end_group();
+    }
+    pub fn undo(&mut self) -> Result<bool, String> {
+        let undone = self.history.undo(&mut self.scene, &self.backend)?;
+        if undone {
+            self.record_history_move();
+        }
+        Ok(undone)
+    }
+    pub fn redo(&mut self) -> Result<bool, String> {
+        let redone = self.history.redo(&mut self.scene, &self.backend)?;
+        if redone {
+            self.record_history_move();
+        }
+        Ok(redone)
+    }
+    fn record_history_move(&mut self) {
+        self.changed = true;
+        self.forget_removed();
+        self.hover = self.check_hover();
+    }
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+    /// `listener` is called after the element tree changes, see
+    /// `notify_change`.
+    pub fn set_change_listener(&mut self, listener: Rc<dyn Fn()>) {
+        self.change_listener = Some(listener);
+    }
+    /// Calls the change listener if the element tree changed since the last
+    /// call. The renderer is not borrowed while the listener runs, so it can
+    /// query the renderer again.
+    pub fn notify_change(renderer: &Rc<RefCell<Renderer>>) {
+        let listener = {
+            let mut renderer = renderer.borrow_mut();
+            let changed = std::mem::replace(&mut renderer.changed, false);
+            renderer.change_listener.clone().filter(|_| changed)
+        };
+        if let Some(listener) = listener {
+            listener();
+        }
+    }
+    /// Replaces the areas of the plane, as a single undoable step. Nothing
+    /// changes if one of them does not fit in the plane or has a point
+    /// outside of it.
+    pub fn set_areas(&mut self, areas: &[AreaData]) -> Result<(), String> {
+        let plane = self
+            .scene
+            .get_plane()
+            .and_then(|id| self.scene.get(id))
+            .ok_or("cannot create area if plane is not created")?;
+        let elements = self.build_areas(&self.labels, &plane.rect, areas)?;
+        self.replace_areas(elements)
+    }
+    /// Elements of the areas and their points, checked against the classes
+    /// of `labels` and the bounds of the plane.
+    fn build_areas(
+        &self,
+        labels: &Labels,
+        bounds: &Rectangle,
+        areas: &[AreaData],
+    ) -> Result<Vec<(Element, Vec<Element>)>, String> {
+        let mut elements = Vec::with_capacity(areas.len());
+        for (i, area) in areas.iter().enumerate() {
+            Self::check_attributes_in(labels, area.label, &area.attributes)?;
+            Self::check_fill(area.fill)?;
+            let mut element = Element::area(&self.backend, area.polygon.clone())?;
+            element.label = area.label;
+            element.attributes = area.attributes.clone();
+            element.fill = area.fill;
+            if !bounds.contains_rect(&element.rect) {
+                return Err(format!("area {} is outside of the plane", i));
+            }
+            let mut points = Vec::with_capacity(area.points.len());
+            for point in &area.points {
+                if !element.contains(point.x, point.y) {
+                    return Err(format!("a point of area {} is outside of it", i));
+                }
+                Self::check_attributes_in(labels, point.label, &point.attributes)?;
+                let mut element = Element::point(&self.backend, point.x, point.y, point.value)?;
+                element.label = point.label;
+                element.attributes = point.attributes.clone();
+                points.push(element);
+            }
+            elements.push((element, points));
+        }
+        Ok(elements)
+    }
+    fn replace_areas(&mut self, elements: Vec<(Element, Vec<Element>)>) -> Result<(), String> {
+        self.history.begin_group();
+        for id in self.scene.get_areas().to_vec() {
+            self.remove_element(id);
+        }
+        for (element, points) in elements {
+            let area = self.scene.add_area(element)?;
+            for point in points {
+                self.scene.add_point(area, point)?;
+            }
+            self.record_insert(area);
+        }
+        self.history.end_group();
+        self.changed = true;
+        Ok(())
+    }
+    pub fn export_coco(&self) -> Result<String, String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let areas = formats::collect_areas(&self.scene);
+        let coco = coco::export(&image, &areas, &self.labels);
+        serde_json::to_string(&coco).map_err(|e| e.to_string())
+    }
+    /// Replaces the areas of the plane with the ones annotated in a COCO file.
+    pub fn import_coco(&mut self, json: &str) -> Result<(), String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let coco: coco::Coco = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let areas = coco::import(&coco, &image, &self.labels)?;
+        self.set_areas(&areas)
+    }
+    pub fn export_voc(&self) -> Result<String, String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let areas = formats::collect_areas(&self.scene);
+        voc::to_string(&voc::export(&image, &areas, &self.labels))
+    }
+    /// Replaces the areas of the plane with the boxes of a Pascal VOC file.
+    pub fn import_voc(&mut self, xml: &str) -> Result<(), String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let areas = voc::import(&voc::from_str(xml)?, &image, &self.labels)?;
+        self.set_areas(&areas)
+    }
+    pub fn export_yolo(&self) -> Result<String, String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let areas = formats::collect_areas(&self.scene);
+        Ok(yolo::export(&image, &areas, &self.labels))
+    }
+    /// Replaces the areas of the plane with the boxes of a YOLO text file.
+    pub fn import_yolo(&mut self, text: &str) -> Result<(), String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let areas = yolo::import(text, &image, &self.labels)?;
+        self.set_areas(&areas)
+    }
+    pub fn save_project(&self) -> Result<String, String> {
+        let image = ImageFrame::of_plane(&self.scene)?;
+        let (width, height) = image.get_dimensions();
+        let image = ProjectImage {
+            file_name: image.file_name,
+            width,
+            height,
+        };
+        let areas = formats::collect_areas(&self.scene);
+        let labels = self.labels.get_classes();
+        project::to_string(&Project::new(image, &self.camera, labels, &areas))
+    }
+    /// Replaces the plane, its areas, the labels and the camera with the ones
+    /// of a saved project. `texture` is the image the project refers to.
+    /// Everything is checked before the session is replaced, a bad project
+    /// leaves it as it was.
+    pub fn load_project(&mut self, project: &Project, texture: Texture) -> Result<(), String> {
+        let areas = project.get_areas(texture.width, texture.height)?;
+        let mut camera = project.camera.to_camera()?;
+        let (min, max) = self.zoom_limits;
+        camera.set_zoom(camera.get_zoom().clamp(min, max));
+        let mut labels = Labels::default();
+        labels.set_classes(&project.labels)?;
+        let plane = self.build_plane(texture)?;
+        let elements = self.build_areas(&labels, &plane.rect, &areas)?;
+        self.set_plane(plane);
+        self.labels = labels;
+        self.active_label = None;
+        self.replace_areas(elements)?;
+        self.history.clear();
+        self.camera = camera;
+        self.hover = self.check_hover();
+        Ok(())
+    }
+    pub fn create_plane_from_texture(&mut self, texture: Texture) -> Result<(), String> {
+        let plane = self.build_plane(texture)?;
+        self.set_plane(plane);
+        Ok(())
+    }
+    fn build_plane(&self, texture: Texture) -> Result<Element, String> {
         let width = texture.width;
         let height = texture.height;
-        let mesh = meshes::textured_rectangle(texture)?;
-        let vao = Vao::new(mesh).map_err(|s| JsValue::from_str(&s))?;
+        let image_name = texture.get_name().to_string();
+        let mesh = meshes::textured_rectangle(texture).map_err(|_| "cannot create the mesh")?;
+        let vao = Vao::new(&self.backend, mesh)?;
 
-        let mesh = meshes::line_rectangle(width, height);
-        let frame = Vao::new(mesh).map_err(|s| JsValue::from_str(&s))?;
+        let mesh = meshes::stroke_rectangle(width, height);
+        let frame = Vao::new(&self.backend, mesh)?;
 
-        let plane = Element::plane(vao, frame, width, height);
-        self.plane = Some(plane);
-        Ok(())
+        let mut plane = Element::plane(vao, frame, width, height);
+        if let ElementKind::PLANE(ref mut plane) = plane.kind {
+            plane.image_name = image_name;
+        }
+        Ok(plane)
+    }
+    fn set_plane(&mut self, plane: Element) {
+        self.scene.set_plane(plane);
+        self.history.clear();
+        self.changed = true;
+        self.forget_removed();
+        self.hover = self.check_hover();
     }
     pub fn draw(&self) {
         let program = self.get_std_program();
         program.use_program();
-        let mat = Mat4::identity();
-        // let mat = mat.rotate(&vec4::Vec4([0.0, 0.0, rotation, 1.0]));
-        // let mat = mat.scale(&vec4::Vec4([0.5, 0.5, 0.5, 1.0]));
-        program.load_tranformation_matrix(mat.data());
         program.load_view_matrix(self.camera.get_view_matrix().data());
         program.load_projection_matrix(self.get_projection_matrix().data());
-        if self.plane.is_none() {
-            return;
+        let plane_id = match self.scene.get_plane() {
+            Some(plane_id) => plane_id,
+            None => return,
+        };
+
+        let plane = self.scene.get(plane_id).unwrap();
+        if let (Some(vao), false) = (&plane.vao, self.get_dragged() == Some(plane_id)) {
+            program.load_tranformation_matrix(plane.get_transformation_matrix().data());
+            vao.draw();
         }
 
-        let plane = self.plane.as_ref().unwrap();
-        if self.dragged.is_none() || !std::ptr::eq(plane, self.dragged.unwrap()) {
-            plane.vao.draw();
+        // frames off the canvas are culled
+        let visible = match self.get_view() {
+            Some(view) => self.scene.annotations_in(&view),
+            None => self.scene.annotations(),
+        };
+        self.draw_fills(&visible);
+        let mut frames = visible.clone();
+        if let Some(hover) = self.hover.filter(|hover| !frames.contains(hover)) {
+            frames.push(hover);
+        }
+        // the selection is drawn last, over the other frames
+        frames.retain(|id| !self.selection.contains(id));
+        frames.extend(self.selection.iter().copied());
+        self.draw_frames(frames);
+        self.draw_texts(&visible);
+    }
+    /// Translucent fills of the visible areas over the plane, in the order of
+    /// the scene so the topmost area is drawn last.
+    fn draw_fills(&self, visible: &[ElementId]) {
+        if self.overlay_opacity == 0.0 {
+            return;
+        }
+        let visible: BTreeSet<ElementId> = visible.iter().copied().collect();
+        let fills: Vec<&Element> = self
+            .scene
+            .get_areas()
+            .iter()
+            .filter(|id| visible.contains(id))
+            .filter_map(|&id| self.scene.get(id))
+            .collect();
+        if fills.is_empty() {
+            return;
         }
 
-        if self.hover.is_none() {
+        let view = self.camera.get_view_matrix();
+        let program = self.get_line_program();
+        program.use_program();
+        program.load_view_matrix(view.data());
+        program.load_projection_matrix(self.get_projection_matrix().data());
+        program.load_dash(StrokeStyle::Solid.get_dash(self.line_width, self.time), 1.0);
+        // the fills lie on the plane and let it show through
+        self.backend.disable(Capability::DepthTest);
+        self.backend.enable(Capability::Blend);
+        for element in fills {
+            let vao = match &element.vao {
+                Some(vao) => vao,
+                None => continue,
+            };
+            let mut color = element.fill.unwrap_or_else(|| {
+                let [r, g, b, _] = self.get_label_color(element);
+                [r, g, b, FILL_OPACITY]
+            });
+            color[3] *= self.overlay_opacity;
+            program.load_color(color);
+            program.load_tranformation_matrix(element.get_transformation_matrix().data());
+            vao.draw();
+        }
+        self.backend.disable(Capability::Blend);
+        self.backend.enable(Capability::DepthTest);
+    }
+    fn draw_frames(&self, frames: Vec<ElementId>) {
+        if frames.is_empty() && self.preview.is_none() {
             return;
         }
 
         let program = self.get_line_program();
         program.use_program();
-        let mat = Mat4::identity();
-        // let mat = mat.rotate(&vec4::Vec4([0.0, 0.0, rotation, 1.0]));
-        // let mat = mat.scale(&vec4::Vec4([0.5, 0.5, 0.5, 1.0]));
-        program.load_tranformation_matrix(mat.data());
         program.load_view_matrix(self.camera.get_view_matrix().data());
         program.load_projection_matrix(self.get_projection_matrix().data());
-        self.hover.unwrap().frame.draw_line_loop();
+        program.load_stroke(self.backend.drawing_buffer_size(), self.line_width);
+        // frames lie on the plane, they would lose the depth test against it
+        self.backend.disable(Capability::DepthTest);
+        let pixel = self.get_pixel_size();
+        for id in frames {
+            let element = match self.scene.get(id) {
+                Some(element) => element,
+                None => continue,
+            };
+            let style = self.styles.get(self.get_frame_state(id));
+            // dashes are measured on the canvas, the frame mesh may be scaled
+            let (scale_x, scale_y) = element.get_scale();
+            let scale = (scale_x * scale_y).sqrt() / pixel;
+            program.load_dash(style.get_dash(self.line_width, self.time), scale);
+            program.load_color(self.get_frame_color(id, element));
+            program.load_tranformation_matrix(element.get_transformation_matrix().data());
+            element.frame.draw();
+        }
+        program.load_dash(StrokeStyle::Solid.get_dash(self.line_width, self.time), 1.0);
+        program.load_color(SELECTED_COLOR);
+        if let (Some(handle), Some(area)) = (&self.handle, self.get_selected_area()) {
+            // handles keep the same size on screen whatever the zoom
+            let pixel = self.get_pixel_size();
+            let size = Handle::SIZE * pixel;
+            for grip in Handle::ALL.iter() {
+                let (x, y) = grip.position(&area.rect, pixel);
+                let transformation = Mat4::identity()
+                    .translate(&Vec4([x, y, 0.0, 1.0]))
+                    .scale(&Vec4([size, size, 1.0, 1.0]));
+                program.load_tranformation_matrix(transformation.data());
+                handle.draw();
+            }
+        }
+        if let Some(preview) = &self.preview {
+            program.load_tranformation_matrix(Mat4::identity().data());
+            preview.draw();
+        }
+        self.backend.enable(Capability::DepthTest);
+    }
+    fn get_frame_state(&self, id: ElementId) -> FrameState {
+        if self.selection.contains(&id) {
+            FrameState::Selected
+        } else if self.hover == Some(id) {
+            FrameState::Hovered
+        } else if self.locked.contains(&id) {
+            FrameState::Locked
+        } else {
+            FrameState::Normal
+        }
+    }
+    fn get_label_color(&self, element: &Element) -> [f32; 4] {
+        let label = element.label.and_then(|label| self.labels.get(label));
+        label.map_or(FRAME_COLOR, LabelClass::get_color)
+    }
+    fn get_frame_color(&self, id: ElementId, element: &Element) -> [f32; 4] {
+        match self.get_frame_state(id) {
+            FrameState::Selected => SELECTED_COLOR,
+            FrameState::Hovered => HOVER_COLOR,
+            FrameState::Normal | FrameState::Locked => self.get_label_color(element),
+        }
+    }
+    /// Text labels, then the captions of the visible annotations: the class
+    /// of an area over its top left corner and the value of a point at its
+    /// right.
+    fn draw_texts(&self, visible: &[ElementId]) {
+        let (program, font) = match (&self.text_program, &self.font) {
+            (Some(program), Some(font)) => (program, font),
+            _ => return,
+        };
+        program.use_program();
+        self.backend.disable(Capability::DepthTest);
+        self.backend.enable(Capability::Blend);
+        let (width, height) = (self.get_width() as f32, self.get_height() as f32);
+        for (label, vao) in self.texts.values() {
+            let transformation = match label.space {
+                TextSpace::Screen => {
+                    // straight to clip space, y goes down on the canvas
+                    program.load_view_matrix(Mat4::identity().data());
+                    program.load_projection_matrix(Mat4::identity().data());
+                    let (x, y) = (label.x * 2.0 / width - 1.0, 1.0 - label.y * 2.0 / height);
+                    let (sx, sy) = (label.size * 2.0 / width, label.size * 2.0 / height);
+                    Mat4::identity()
+                        .translate(&Vec4([x, y, 0.0, 1.0]))
+                        .scale(&Vec4([sx, sy, 1.0, 1.0]))
+                }
+                TextSpace::World => {
+                    program.load_view_matrix(self.camera.get_view_matrix().data());
+                    program.load_projection_matrix(self.get_projection_matrix().data());
+                    Mat4::identity()
+                        .translate(&Vec4([label.x, label.y, 0.0, 1.0]))
+                        .scale(&Vec4([label.size, label.size, 1.0, 1.0]))
+                }
+            };
+            program.load_color(label.color);
+            program.load_tranformation_matrix(transformation.data());
+            vao.draw();
+        }
+        if self.captions {
+            program.load_view_matrix(self.camera.get_view_matrix().data());
+            program.load_projection_matrix(self.get_projection_matrix().data());
+            // captions keep the same size on screen whatever the zoom
+            let pixel = self.get_pixel_size();
+            let size = CAPTION_SIZE * pixel;
+            let mut cache = self.caption_vaos.borrow_mut();
+            let mut drawn = BTreeSet::new();
+            for &id in visible {
+                let element = self.scene.get(id).unwrap();
+                let (left, bottom) = element.rect.down_left_corner;
+                let (w, h) = element.rect.dimensions;
+                let (text, x, y) = match element.kind {
+                    ElementKind::AREA(_) => match element.label.and_then(|l| self.labels.get(l)) {
+                        Some(class) => (class.name.clone(), left, bottom + h + size / 4.0),
+                        None => continue,
+                    },
+                    ElementKind::POINT(ref point) => (
+                        format!("{}", point.value),
+                        left + w + size / 4.0,
+                        bottom + h / 2.0 - size / 3.0,
+                    ),
+                    ElementKind::PLANE(_) => continue,
+                };
+                if !cache.contains_key(&text) {
+                    match Vao::new(&self.backend, meshes::text(font, &text)) {
+                        Ok(vao) => cache.insert(text.clone(), vao),
+                        Err(_) => continue,
+                    };
+                }
+                let transformation = Mat4::identity()
+                    .translate(&Vec4([x, y, 0.0, 1.0]))
+                    .scale(&Vec4([size, size, 1.0, 1.0]));
+                program.load_color(self.get_frame_color(id, element));
+                program.load_tranformation_matrix(transformation.data());
+                cache[&text].draw();
+                drawn.insert(text);
+            }
+            // the texts change with the values and the classes, old ones
+            // would pile up
+            cache.retain(|text, _| drawn.contains(text));
+        } else {
+            self.caption_vaos.borrow_mut().clear();
+        }
+        self.backend.disable(Capability::Blend);
+        self.backend.enable(Capability::DepthTest);
     }
 }
+
+#[cfg(test)]
+pub fn test_renderer(backend: Rc<dyn GraphicsBackend>) -> Renderer {
+    let mut renderer = Renderer::with_backend(backend);
+    let std_program = ShaderProgram::from_source(
+        renderer.get_backend(),
+        include_str!("../../shader.vert"),
+        include_str!("../../shader.frag"),
+    )
+    .unwrap();
+    renderer.set_std_program(Shader::new(std_program));
+    let line_program = ShaderProgram::from_source(
+        renderer.get_backend(),
+        include_str!("../../line.vert"),
+        include_str!("../../line.frag"),
+    )
+    .unwrap();
+    renderer.set_line_program(Shader::new(line_program));
+    let text_program = ShaderProgram::from_source(
+        renderer.get_backend(),
+        include_str!("../../text.vert"),
+        include_str!("../../text.frag"),
+    )
+    .unwrap();
+    renderer.set_text_program(Shader::new(text_program));
+    renderer
+}
+
+/// Indices in the round joined frame of a four sided area.
+#[cfg(test)]
+const AREA_FRAME: i32 = 4 * 6 + 4 * 8 * 3;
+
+#[cfg(test)]
+fn headless_renderer() -> (Rc<backend::HeadlessBackend>, Renderer) {
+    headless_renderer_with_image(2, 2)
+}
+
+#[cfg(test)]
+fn headless_renderer_with_image(
+    width: u32,
+    height: u32,
+) -> (Rc<backend::HeadlessBackend>, Renderer) {
+    let headless = Rc::new(backend::HeadlessBackend::new(800, 600));
+    let mut renderer = test_renderer(headless.clone());
+    let pixels = vec![255; (width * height * 4) as usize];
+    let texture = Texture::from_rgba(renderer.get_backend(), width, height, &pixels).unwrap();
+    renderer.create_plane_from_texture(texture).unwrap();
+    headless.clear_commands();
+    (headless, renderer)
+}
+
+#[test]
+fn draw_plane_issues_one_triangles_call() {
+    use backend::Primitive;
+    let (headless, mut renderer) = headless_renderer();
+    renderer.mouse_move(-1, -1, 0.0, 0.0, false);
+    renderer.draw();
+    assert_eq!(headless.draw_calls(), vec![(Primitive::Triangles, 6)]);
+
+    headless.clear_commands();
+    renderer.mouse_move(400, 300, 0.0, 0.0, false);
+    renderer.draw();
+    assert_eq!(
+        headless.draw_calls(),
+        vec![(Primitive::Triangles, 6), (Primitive::Triangles, 24)]
+    );
+}
+
+#[test]
+fn renderers_are_independent() {
+    let (first_backend, mut first) = headless_renderer();
+    let (second_backend, second) = headless_renderer();
+    first.key_down("d", false);
+    first.update(0.5);
+    assert!(first.camera.get_position()[0] > second.camera.get_position()[0]);
+
+    second.draw();
+    assert!(first_backend.draw_calls().is_empty());
+    assert_eq!(second_backend.draw_calls().len(), 1);
+}
+
+#[test]
+fn replacing_the_plane_clears_stale_interaction_state() {
+    let (_headless, mut renderer) = headless_renderer();
+    renderer.mouse_move(400, 300, 0.0, 0.0, false);
+    let plane = renderer.get_scene().get_plane();
+    assert!(plane.is_some());
+    assert_eq!(renderer.get_hover(), plane);
+    renderer.set_selected(plane);
+    assert_eq!(renderer.get_selected(), plane);
+
+    let texture = Texture::from_rgba(renderer.get_backend(), 1, 1, &[255; 4]).unwrap();
+    renderer.create_plane_from_texture(texture).unwrap();
+    assert_ne!(renderer.get_scene().get_plane(), plane);
+    assert_eq!(renderer.get_selected(), None);
+    assert_eq!(renderer.get_hover(), renderer.get_scene().get_plane());
+
+    renderer.set_selected(plane);
+    assert_eq!(renderer.get_selected(), None);
+}
+
+#[test]
+fn areas_and_points_are_drawn_as_frames() {
+    use backend::Primitive;
+    let (headless, mut renderer) = headless_renderer();
+    let area = renderer
+        .create_area(Polygon::rectangle(-0.5, -0.5, 1.0, 1.0))
+        .unwrap();
+    let point = renderer.create_point(area, 0.25, 0.25, 2.0).unwrap();
+    assert!(renderer.create_point(area, 0.9, 0.9, 2.0).is_err());
+    renderer.set_point_value(point, 3.0).unwrap();
+    assert!(renderer.set_point_value(area, 3.0).is_err());
+
+    renderer.mouse_move(-1, -1, 0.0, 0.0, false);
+    renderer.draw();
+    assert_eq!(
+        headless.draw_calls(),
+        vec![
+            (Primitive::Triangles, 6),
+            (Primitive::Triangles, 6),
+            (Primitive::Triangles, AREA_FRAME),
+            (Primitive::Triangles, 24)
+        ]
+    );
+
+    renderer.set_selected(Some(point));
+    assert!(!renderer.remove_element(renderer.get_scene().get_plane().unwrap()));
+    assert!(renderer.remove_element(area));
+    assert!(!renderer.remove_element(point));
+    assert_eq!(renderer.get_selected(), None);
+    assert!(renderer.get_scene().get_areas().is_empty());
+}
+
+/// Unprojecting the mouse is not exact, world positions are compared with
+/// some tolerance.
+#[cfg(test)]
+fn assert_near(actual: &[f32], expected: &[f32]) {
+    let near = actual
+        .iter()
+        .zip(expected)
+        .all(|(a, b)| (a - b).abs() < 1e-3);
+    assert!(near, "{:?} is not {:?}", actual, expected);
+}
+
+#[cfg(test)]
+fn area_bounds(renderer: &Renderer, id: ElementId) -> Vec<f32> {
+    match &renderer.scene.get(id).unwrap().kind {
+        ElementKind::AREA(area) => {
+            let ((left, bottom), (right, top)) = area.polygon.bounding_box();
+            vec![left, bottom, right, top]
+        }
+        _ => panic!("element {} is not an area", id.0),
+    }
+}
+
+#[cfg(test)]
+fn point_center(renderer: &Renderer, id: ElementId) -> Vec<f32> {
+    let (x, y) = renderer.scene.get(id).unwrap().rect.get_center();
+    vec![x, y]
+}
+
+#[test]
+fn dragging_moves_in_world_units_inside_the_plane() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    // a canvas pixel is 0.75 world units, the canvas center is the origin
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let point = renderer.create_point(area, 0.0, 0.0, 1.0).unwrap();
+    renderer.mouse_down(405, 300, false);
+    assert_eq!(renderer.get_selected(), Some(area));
+    assert_eq!(renderer.get_dragged(), Some(area));
+
+    renderer.mouse_move(425, 300, 20.0, 0.0, false);
+    assert_near(&area_bounds(&renderer, area), &[5.0, -10.0, 25.0, 10.0]);
+    assert_near(&point_center(&renderer, point), &[15.0, 0.0]);
+    // the area stops at the border of the plane
+    renderer.mouse_move(700, 0, 0.0, 0.0, false);
+    assert_near(&area_bounds(&renderer, area), &[30.0, 5.0, 50.0, 25.0]);
+    renderer.mouse_up(700, 0);
+    assert_eq!(renderer.get_dragged(), None);
+
+    // the whole drag is undone at once
+    renderer.undo().unwrap();
+    assert_near(&area_bounds(&renderer, area), &[-10.0, -10.0, 10.0, 10.0]);
+    assert_near(&point_center(&renderer, point), &[0.0, 0.0]);
+}
+
+#[test]
+fn handles_resize_and_rotate_the_selected_area() {
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-15.0, -9.0, 30.0, 18.0))
+        .unwrap();
+    let point = renderer.create_point(area, 6.0, 0.0, 1.0).unwrap();
+    renderer.mouse_down(400, 300, false);
+    renderer.mouse_up(400, 300);
+    assert_eq!(renderer.get_selected(), Some(area));
+
+    // the top right handle, dragged past the top of the plane
+    renderer.mouse_down(420, 288, false);
+    assert_eq!(renderer.get_dragged(), Some(area));
+    renderer.mouse_move(440, 200, 0.0, 0.0, false);
+    renderer.mouse_up(440, 200);
+    assert_near(&area_bounds(&renderer, area), &[-15.0, -9.0, 30.0, 25.0]);
+    assert_near(&point_center(&renderer, point), &[16.5, 8.0]);
+    renderer.undo().unwrap();
+
+    // the rotation handle sits 24 pixels above the top edge
+    renderer.mouse_down(400, 264, false);
+    renderer.mouse_move(364, 300, 0.0, 0.0, false);
+    renderer.mouse_up(364, 300);
+    assert_near(&area_bounds(&renderer, area), &[-9.0, -15.0, 9.0, 15.0]);
+    assert_near(&point_center(&renderer, point), &[0.0, 6.0]);
+
+    // eight resize handles and the rotation handle around the frame
+    use backend::Primitive;
+    headless.clear_commands();
+    renderer.draw();
+    let handles = headless
+        .draw_calls()
+        .into_iter()
+        .filter(|call| *call == (Primitive::Triangles, 24))
+        .count();
+    // the frames of its point and the hovered plane come first
+    assert_eq!(handles, 2 + Handle::ALL.len());
+}
+
+#[test]
+fn tools_draw_areas_and_points_with_the_mouse() {
+    use backend::Primitive;
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    renderer.set_tool(Tool::Rectangle);
+    renderer.mouse_down(380, 280, false);
+    renderer.mouse_move(420, 310, 40.0, 30.0, false);
+    // the rubber band is drawn over the plane
+    renderer.draw();
+    assert!(headless
+        .draw_calls()
+        .contains(&(Primitive::Triangles, AREA_FRAME)));
+    renderer.mouse_up(420, 310);
+    let areas = renderer.get_scene().get_areas().to_vec();
+    assert_eq!(areas.len(), 1);
+    assert_eq!(renderer.get_selected(), Some(areas[0]));
+    assert_near(
+        &area_bounds(&renderer, areas[0]),
+        &[-15.0, -7.5, 15.0, 15.0],
+    );
+
+    // clicks outside of the plane are brought back to its border
+    renderer.set_tool(Tool::Polygon);
+    renderer.mouse_down(0, 0, false);
+    renderer.mouse_down(360, 0, false);
+    renderer.mouse_move(0, 200, 0.0, 0.0, false);
+    renderer.key_down("Escape", false);
+    renderer.key_down("Enter", false);
+    assert_eq!(renderer.get_scene().get_areas().len(), 1);
+    renderer.mouse_down(0, 0, false);
+    renderer.mouse_down(360, 0, false);
+    renderer.mouse_down(0, 300, false);
+    renderer.double_click(0, 300);
+    let areas = renderer.get_scene().get_areas().to_vec();
+    assert_eq!(areas.len(), 2);
+    assert_near(
+        &area_bounds(&renderer, areas[1]),
+        &[-50.0, 0.0, -30.0, 25.0],
+    );
+
+    renderer.set_tool(Tool::Point);
+    renderer.mouse_down(400, 300, false);
+    let points = renderer.get_scene().get_points(areas[0]).to_vec();
+    assert_eq!(points.len(), 1);
+    assert_near(&point_center(&renderer, points[0]), &[0.0, 0.0]);
+    // no area under the cursor
+    renderer.mouse_down(700, 300, false);
+    assert_eq!(renderer.get_scene().get_areas().len(), 2);
+    assert_eq!(renderer.get_scene().get_points(areas[0]).len(), 1);
+}
+
+#[test]
+fn selections_grow_with_shift_marquee_and_select_all() {
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let left = renderer
+        .create_area(Polygon::rectangle(-40.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let right = renderer
+        .create_area(Polygon::rectangle(20.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let point = renderer.create_point(right, 30.0, 0.0, 1.0).unwrap();
+
+    // canvas x = 400 + world x / 0.75
+    renderer.mouse_down(360, 300, false);
+    renderer.mouse_up(360, 300);
+    renderer.mouse_down(440, 290, true);
+    renderer.mouse_up(440, 290);
+    assert_eq!(renderer.get_selection(), &[left, right]);
+    renderer.mouse_down(360, 300, true);
+    assert_eq!(renderer.get_selection(), &[right]);
+
+    // a marquee over empty space starts a new selection
+    renderer.mouse_down(400, 290, false);
+    assert!(renderer.get_selection().is_empty());
+    renderer.mouse_move(370, 310, 0.0, 0.0, false);
+    renderer.mouse_up(370, 310);
+    assert_eq!(renderer.get_selection(), &[left]);
+    renderer.mouse_down(400, 290, true);
+    renderer.mouse_up(440, 310);
+    assert_eq!(renderer.get_selection(), &[left, right]);
+
+    renderer.set_selected(None);
+    renderer.key_down("a", true);
+    assert_eq!(renderer.get_selection(), &[left, right]);
+
+    // selected frames are drawn last, with their own color
+    renderer.set_overlay_opacity(0.0).unwrap();
+    headless.clear_commands();
+    renderer.draw();
+    let colors = headless.uniform_values("color");
+    assert_eq!(
+        colors[..3],
+        [
+            FRAME_COLOR.to_vec(),
+            SELECTED_COLOR.to_vec(),
+            SELECTED_COLOR.to_vec()
+        ]
+    );
+    assert!(!renderer.get_selection().contains(&point));
+}
+
+#[test]
+fn group_operations_act_on_the_whole_selection() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let left = renderer
+        .create_area(Polygon::rectangle(-40.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let right = renderer
+        .create_area(Polygon::rectangle(20.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let point = renderer.create_point(right, 30.0, 0.0, 1.0).unwrap();
+    let other = renderer.create_point(left, -30.0, 0.0, 1.0).unwrap();
+    renderer.set_selection(&[left, right, point]);
+
+    // dragging one of them moves all, stopping at the border of the plane
+    renderer.mouse_down(440, 300, false);
+    renderer.mouse_move(480, 300, 40.0, 0.0, false);
+    renderer.mouse_up(480, 300);
+    assert_near(&area_bounds(&renderer, left), &[-30.0, -10.0, -10.0, 10.0]);
+    assert_near(&area_bounds(&renderer, right), &[30.0, -10.0, 50.0, 10.0]);
+    assert_near(&point_center(&renderer, point), &[40.0, 0.0]);
+
+    assert!(renderer.move_selection(0.0, 20.0).is_err());
+    renderer.move_selection(0.0, -5.0).unwrap();
+    assert_near(&area_bounds(&renderer, left), &[-30.0, -15.0, -10.0, 5.0]);
+
+    renderer.set_selection(&[point, other]);
+    renderer.set_selection_value(7.0).unwrap();
+    for id in [point, other] {
+        match renderer.get_scene().get(id).unwrap().kind {
+            ElementKind::POINT(ref point) => assert_eq!(point.value, 7.0),
+            _ => unreachable!(),
+        }
+    }
+
+    renderer.set_selection(&[left, right]);
+    renderer.key_down("Delete", false);
+    assert!(renderer.get_scene().get_areas().is_empty());
+    assert!(renderer.get_selection().is_empty());
+    // every group operation is one step
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_scene().get_areas(), &[left, right]);
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_scene().get_points(left).len(), 1);
+    match renderer.get_scene().get(other).unwrap().kind {
+        ElementKind::POINT(ref point) => assert_eq!(point.value, 1.0),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+fn label_class(id: u32, name: &str, color: &str, hotkey: Option<&str>) -> LabelClass {
+    LabelClass {
+        id,
+        name: name.into(),
+        color: color.into(),
+        hotkey: hotkey.map(String::from),
+        attributes: Vec::new(),
+    }
+}
+
+#[test]
+fn labels_color_frames_and_follow_the_annotations() {
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    renderer
+        .define_label(label_class(3, "car", "#00ff00", Some("1")))
+        .unwrap();
+    renderer
+        .define_label(label_class(5, "person", "#0000ff", Some("2")))
+        .unwrap();
+    let area = renderer
+        .create_area(Polygon::rectangle(-40.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let point = renderer.create_point(area, -30.0, 0.0, 1.0).unwrap();
+    assert!(renderer.set_label(area, Some(9)).is_err());
+
+    // the hotkey labels the selection and the next drawn elements
+    renderer.set_selection(&[area, point]);
+    renderer.key_down("1", false);
+    assert_eq!(renderer.get_label(area), Some(3));
+    assert_eq!(renderer.get_label(point), Some(3));
+    renderer.set_tool(Tool::Rectangle);
+    renderer.mouse_down(440, 290, false);
+    renderer.mouse_up(460, 310);
+    let drawn = renderer.get_selected().unwrap();
+    assert_eq!(renderer.get_label(drawn), Some(3));
+    renderer.undo().unwrap();
+    assert!(!renderer.get_scene().contains(drawn));
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_label(area), None);
+    renderer.redo().unwrap();
+    renderer.set_label(point, Some(5)).unwrap();
+
+    renderer.set_selection(&[]);
+    renderer.mouse_move(0, 0, 0.0, 0.0, false);
+    headless.clear_commands();
+    renderer.draw();
+    let colors = headless.uniform_values("color");
+    // the area is filled with its label color too
+    assert_eq!(colors[0], vec![0.0, 1.0, 0.0, FILL_OPACITY]);
+    assert_eq!(colors[1], vec![0.0, 1.0, 0.0, 1.0]);
+    assert_eq!(colors[2], vec![0.0, 0.0, 1.0, 1.0]);
+
+    assert!(renderer.remove_label(3).is_err());
+    renderer.set_label(area, None).unwrap();
+    renderer.remove_label(3).unwrap();
+
+    // classes travel with the project and name the exported categories
+    let json = renderer.save_project().unwrap();
+    let coco: coco::Coco = serde_json::from_str(&renderer.export_coco().unwrap()).unwrap();
+    let names: Vec<&str> = coco.categories.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["person", "area"]);
+    assert_eq!(coco.annotations[0].category_id, 6);
+
+    let (_headless, mut other) = headless_renderer();
+    let texture = Texture::from_rgba(other.get_backend(), 100, 50, &[255; 100 * 50 * 4]).unwrap();
+    other
+        .load_project(&project::from_str(&json).unwrap(), texture)
+        .unwrap();
+    assert_eq!(other.get_labels(), renderer.get_labels());
+    let areas = formats::collect_areas(other.get_scene());
+    assert_eq!(areas[0].points[0].label, Some(5));
+}
+
+#[test]
+fn attributes_follow_the_label_schema() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let mut car = label_class(1, "car", "#00ff00", None);
+    car.attributes = serde_json::from_str(
+        r#"[
+            { "name": "occluded", "type": "bool" },
+            { "name": "pose", "type": "enum", "options": ["front", "side"] }
+        ]"#,
+    )
+    .unwrap();
+    renderer.define_label(car.clone()).unwrap();
+    renderer
+        .define_label(label_class(2, "person", "#0000ff", None))
+        .unwrap();
+    let area = renderer
+        .create_area(Polygon::rectangle(-40.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let side = AttributeValue::String("side".into());
+    assert!(renderer
+        .set_attribute(area, "pose", Some(side.clone()))
+        .is_err());
+
+    renderer.set_label(area, Some(1)).unwrap();
+    renderer
+        .set_attribute(area, "pose", Some(side.clone()))
+        .unwrap();
+    renderer
+        .set_attribute(area, "occluded", Some(AttributeValue::Bool(true)))
+        .unwrap();
+    let back = AttributeValue::String("back".into());
+    assert!(renderer.set_attribute(area, "pose", Some(back)).is_err());
+    assert!(renderer
+        .set_attribute(area, "pose", Some(AttributeValue::Bool(true)))
+        .is_err());
+    assert_eq!(renderer.get_attributes(area).unwrap().len(), 2);
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_attributes(area).unwrap().len(), 1);
+    renderer.redo().unwrap();
+
+    // the schema cannot drop attributes in use
+    let mut narrowed = car.clone();
+    narrowed.attributes.truncate(1);
+    assert!(renderer.define_label(narrowed).is_err());
+
+    // relabeling drops the attributes in one undoable step
+    renderer.set_label(area, Some(2)).unwrap();
+    assert!(renderer.get_attributes(area).unwrap().is_empty());
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_label(area), Some(1));
+    assert_eq!(renderer.get_attributes(area).unwrap()["pose"], side);
+
+    let json = renderer.save_project().unwrap();
+    let (_headless, mut other) = headless_renderer();
+    let texture = Texture::from_rgba(other.get_backend(), 100, 50, &[255; 100 * 50 * 4]).unwrap();
+    other
+        .load_project(&project::from_str(&json).unwrap(), texture)
+        .unwrap();
+    let loaded = other.get_scene().get_areas()[0];
+    assert_eq!(other.get_attributes(loaded), renderer.get_attributes(area));
+    assert_eq!(other.get_labels()[0].attributes, car.attributes);
+}
+
+#[test]
+fn frames_off_the_canvas_are_culled() {
+    use backend::Primitive;
+    let (headless, mut renderer) = headless_renderer_with_image(1000, 600);
+    let near = renderer
+        .create_area(Polygon::rectangle(-5.0, -5.0, 10.0, 10.0))
+        .unwrap();
+    let far = renderer
+        .create_area(Polygon::rectangle(360.0, -5.0, 10.0, 10.0))
+        .unwrap();
+    let frames = |renderer: &Renderer| {
+        headless.clear_commands();
+        renderer.draw();
+        headless
+            .draw_calls()
+            .into_iter()
+            .filter(|call| call.0 == Primitive::Triangles && call.1 != 6)
+            .count()
+    };
+    renderer.mouse_move(0, 0, 0.0, 0.0, false);
+    assert_eq!(renderer.get_hover(), renderer.get_scene().get_plane());
+    // the plane and the fills are quads; the hovered plane is framed, the far
+    // area is off the canvas
+    assert_eq!(frames(&renderer), 2);
+
+    renderer.camera.set_position(Vec4([365.0, 0.0, 0.0, 1.0]));
+    renderer.mouse_move(400, 300, 0.0, 0.0, false);
+    assert_eq!(renderer.get_hover(), Some(far));
+    assert_eq!(frames(&renderer), 1);
+
+    // a selected element is drawn wherever it is
+    renderer.set_selection(&[near]);
+    assert_eq!(frames(&renderer), 2 + Handle::ALL.len());
+}
+
+#[test]
+fn text_labels_and_captions_are_drawn_over_the_frames() {
+    use backend::{Command, Primitive};
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let label = |text: &str, space| TextLabel {
+        text: text.into(),
+        x: 10.0,
+        y: 20.0,
+        size: 16.0,
+        space,
+        color: [1.0; 4],
+    };
+    assert!(renderer.add_text(label("AV", TextSpace::World)).is_err());
+    let atlas = Texture::from_rgba(renderer.get_backend(), 40, 20, &[0; 40 * 20 * 4]).unwrap();
+    renderer
+        .set_font(Font::new(text::test_metrics(), atlas).unwrap())
+        .unwrap();
+    renderer.add_text(label("AV", TextSpace::World)).unwrap();
+    let screen = renderer.add_text(label("A", TextSpace::Screen)).unwrap();
+
+    renderer
+        .define_label(label_class(1, "A A", "#00ff00", None))
+        .unwrap();
+    let area = renderer
+        .create_area(Polygon::rectangle(-40.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    renderer.set_label(area, Some(1)).unwrap();
+    let point = renderer.create_point(area, -30.0, 0.0, 1.0).unwrap();
+    renderer.mouse_move(0, 0, 0.0, 0.0, false);
+    // texts are drawn after the frames, in the last blended pass
+    let glyphs = |commands: &[Command]| -> Vec<i32> {
+        let blend = commands
+            .iter()
+            .rposition(|command| *command == Command::Enable(Capability::Blend))
+            .unwrap();
+        commands[blend..]
+            .iter()
+            .filter_map(|command| match command {
+                Command::DrawElements {
+                    mode: Primitive::Triangles,
+                    count,
+                } => Some(*count),
+                _ => None,
+            })
+            .collect()
+    };
+    headless.clear_commands();
+    renderer.draw();
+    let commands = headless.commands();
+    let blend = commands
+        .iter()
+        .rposition(|command| *command == Command::Enable(Capability::Blend))
+        .unwrap();
+    // labels, then the class of the area and the value of its point
+    assert_eq!(glyphs(&commands), [12, 6, 12, 6]);
+    // the class name takes the color of the frame
+    assert!(commands[blend..].iter().any(|command| matches!(
+        command,
+        Command::Uniform4fv(_, color) if *color == [0.0, 1.0, 0.0, 1.0]
+    )));
+    assert_eq!(
+        commands.last(),
+        Some(&Command::Enable(Capability::DepthTest))
+    );
+
+    // caption meshes are built once
+    headless.clear_commands();
+    renderer.draw();
+    let created = headless
+        .commands()
+        .into_iter()
+        .filter(|command| matches!(command, Command::CreateVertexArray(_)))
+        .count();
+    assert_eq!(created, 0);
+    // and dropped once their text is not drawn anymore
+    renderer.set_point_value(point, 2.5).unwrap();
+    headless.clear_commands();
+    renderer.draw();
+    let deleted = headless
+        .commands()
+        .into_iter()
+        .filter(|command| matches!(command, Command::DeleteVertexArray(_)))
+        .count();
+    assert_eq!(deleted, 1);
+    assert_eq!(renderer.caption_vaos.borrow().len(), 2);
+
+    assert!(renderer.remove_text(screen));
+    assert!(!renderer.remove_text(screen));
+    renderer.set_captions(false);
+    headless.clear_commands();
+    renderer.draw();
+    // the world label only
+    assert_eq!(glyphs(&headless.commands()), [12]);
+    assert!(renderer.caption_vaos.borrow().is_empty());
+}
+
+#[test]
+fn frames_take_the_stroke_style_of_their_state() {
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    let other = renderer
+        .create_area(Polygon::rectangle(20.0, -10.0, 10.0, 10.0))
+        .unwrap();
+    let dashes = |renderer: &Renderer| {
+        headless.clear_commands();
+        renderer.draw();
+        headless.uniform_values("dash")
+    };
+    renderer.set_overlay_opacity(0.0).unwrap();
+    renderer.set_selection(&[area]);
+    // the other area, then the selection and its solid handles; dashes are
+    // measured in pixels of 0.75 world units
+    let drawn = dashes(&renderer);
+    assert_near(&drawn[0], &[1.0, 0.0, 0.0, 1.0 / 0.75]);
+    assert_near(&drawn[1], &[8.0, 6.0, 0.0, 1.0 / 0.75]);
+    assert_eq!(dashes(&renderer)[2], [1.0, 0.0, 0.0, 1.0]);
+    renderer.update(0.5);
+    assert_near(&dashes(&renderer)[1], &[8.0, 6.0, 10.0, 1.0 / 0.75]);
+
+    renderer.set_locked(other, true).unwrap();
+    assert!(renderer.is_locked(other));
+    renderer
+        .set_stroke_style(FrameState::Locked, StrokeStyle::Dotted { gap: 3.0 })
+        .unwrap();
+    assert!(renderer
+        .set_stroke_style(FrameState::Locked, StrokeStyle::Dotted { gap: -1.0 })
+        .is_err());
+    assert_near(&dashes(&renderer)[0], &[LINE_WIDTH, 3.0, 0.0, 1.0 / 0.75]);
+
+    // locked elements stay in place, the rest of the selection moves
+    renderer.set_selection(&[area, other]);
+    renderer.move_selection(1.0, 0.0).unwrap();
+    assert_near(&area_bounds(&renderer, area), &[-9.0, -10.0, 11.0, 10.0]);
+    assert_near(&area_bounds(&renderer, other), &[20.0, -10.0, 30.0, 0.0]);
+    renderer.set_selection(&[]);
+    renderer.mouse_down(433, 307, false);
+    renderer.mouse_move(453, 307, 20.0, 0.0, false);
+    renderer.mouse_up(453, 307);
+    assert_eq!(renderer.get_selected(), Some(other));
+    assert_near(&area_bounds(&renderer, other), &[20.0, -10.0, 30.0, 0.0]);
+
+    renderer.remove_element(other);
+    assert!(!renderer.is_locked(other));
+    assert!(renderer.set_locked(other, true).is_err());
+}
+
+#[test]
+fn areas_are_filled_in_the_scene_order_over_the_plane() {
+    use backend::Command;
+    let (headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let under = renderer
+        .create_area(Polygon::rectangle(-20.0, -20.0, 30.0, 30.0))
+        .unwrap();
+    let over = renderer
+        .create_area(Polygon::rectangle(-10.0, -10.0, 30.0, 30.0))
+        .unwrap();
+    let point = renderer.create_point(over, 0.0, 0.0, 1.0).unwrap();
+    let blue = [0.0, 0.0, 1.0, 0.5];
+    renderer.set_fill(over, Some(blue)).unwrap();
+    assert!(renderer.set_fill(over, Some([0.0, 0.0, 2.0, 1.0])).is_err());
+    assert!(renderer.set_fill(point, None).is_err());
+    assert_eq!(renderer.get_fill(under), None);
+
+    let fills = |renderer: &Renderer| {
+        headless.clear_commands();
+        renderer.draw();
+        let commands = headless.commands();
+        let blend = commands
+            .iter()
+            .position(|command| *command == Command::Enable(Capability::Blend));
+        if let Some(blend) = blend {
+            assert_eq!(commands[blend - 1], Command::Disable(Capability::DepthTest));
+        }
+        blend.map(|_| headless.uniform_values("color")[..2].to_vec())
+    };
+    // the topmost area is blended last
+    let under_color = vec![1.0, 0.0, 0.0, FILL_OPACITY];
+    assert_eq!(
+        fills(&renderer),
+        Some(vec![under_color.clone(), blue.to_vec()])
+    );
+    // whatever side the camera looks from
+    renderer.set_render_mode(RenderMode::M3D);
+    renderer.mouse_move(400, 300, 300.0, -100.0, true);
+    assert_eq!(fills(&renderer), Some(vec![under_color, blue.to_vec()]));
+    renderer.set_render_mode(RenderMode::M2D);
+    renderer.set_overlay_opacity(0.5).unwrap();
+    assert_eq!(
+        fills(&renderer),
+        Some(vec![
+            vec![1.0, 0.0, 0.0, FILL_OPACITY / 2.0],
+            vec![0.0, 0.0, 1.0, 0.25]
+        ])
+    );
+    assert!(renderer.set_overlay_opacity(1.5).is_err());
+    renderer.set_overlay_opacity(0.0).unwrap();
+    assert_eq!(fills(&renderer), None);
+
+    // the fill comes back with the area
+    renderer.remove_element(over);
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_fill(over), Some(blue));
+
+    // and fill changes are undone like any other edit
+    renderer.set_fill(over, None).unwrap();
+    renderer.set_fill(over, Some([0.0, 1.0, 0.0, 1.0])).unwrap();
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_fill(over), None);
+    renderer.undo().unwrap();
+    assert_eq!(renderer.get_fill(over), Some(blue));
+    renderer.redo().unwrap();
+    renderer.redo().unwrap();
+    assert_eq!(renderer.get_fill(over), Some([0.0, 1.0, 0.0, 1.0]));
+}
+
+#[test]
+fn switching_the_render_mode_keeps_the_framing() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(20.0, -10.0, 20.0, 20.0))
+        .unwrap();
+    renderer.camera.set_position(Vec4([10.0, 5.0, 1.0, 1.0]));
+    let framing = |renderer: &Renderer| {
+        let (x0, y0) = renderer.to_world(400, 300);
+        let (x1, y1) = renderer.to_world(440, 260);
+        vec![x0, y0, x1, y1]
+    };
+    let flat = framing(&renderer);
+    assert_near(&flat, &[10.0, 5.0, 40.0, 35.0]);
+
+    renderer.set_render_mode(RenderMode::M3D);
+    assert_eq!(renderer.get_render_mode(), RenderMode::M3D);
+    assert_near(&framing(&renderer), &flat);
+    // the perspective camera picks through the plane
+    renderer.mouse_move(427, 300, 0.0, 0.0, false);
+    assert_eq!(renderer.get_hover(), Some(area));
+
+    // orbiting tilts the plane around the center of the canvas
+    renderer.mouse_move(427, 300, 0.0, 100.0, true);
+    assert!(renderer.camera.get_pitch() != 0.0);
+    let tilted = framing(&renderer);
+    assert_near(&tilted[..2], &[10.0, 5.0]);
+    assert!(tilted[3] - 5.0 > 30.0);
+
+    renderer.set_render_mode(RenderMode::M2D);
+    assert_eq!(renderer.camera.get_pitch(), 0.0);
+    assert_near(&framing(&renderer)[..2], &[10.0, 5.0]);
+    renderer.set_render_mode(RenderMode::M3D);
+    renderer.set_render_mode(RenderMode::M2D);
+    assert_near(&framing(&renderer)[..2], &[10.0, 5.0]);
+    assert!(RenderMode::from_name("4d").is_err());
+    assert_eq!(RenderMode::from_name("3d"), Ok(RenderMode::M3D));
+}
+
+#[test]
+fn the_3d_camera_orbits_pans_and_dollies_around_the_plane() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    renderer.camera.set_position(Vec4([10.0, 5.0, 1.0, 1.0]));
+    renderer.set_render_mode(RenderMode::M3D);
+    let distance = renderer.get_orbit().get_distance();
+    assert_near(&[distance], &[225.0]);
+
+    // right-drags move the plane with the cursor
+    renderer.pan(40.0, 0.0);
+    assert_near(&[renderer.to_world(440, 300).0], &[10.0]);
+    renderer.mouse_move(400, 300, 200.0, 0.0, true);
+    assert_near(&[renderer.get_orbit().get_azimuth()], &[310.0]);
+    let (x, y) = renderer.to_world(400, 300);
+    assert_near(&[x, y], &[-20.0, 5.0]);
+
+    // the wheel moves towards the target, within the limits
+    renderer.wheel(400, 300, -100.0, false);
+    assert!(renderer.get_orbit().get_distance() < distance);
+    assert_near(&[renderer.to_world(400, 300).0], &[-20.0]);
+    renderer.set_orbit_distance_limits(50.0, 100.0).unwrap();
+    renderer.wheel(400, 300, 1000.0, false);
+    assert_near(&[renderer.get_orbit().get_distance()], &[100.0]);
+    assert!(renderer.set_orbit_polar_limits(90.0, 200.0).is_err());
+
+    // and pinches too
+    renderer.wheel(400, 300, -1000.0, true);
+    assert_near(&[renderer.get_orbit().get_distance()], &[50.0]);
+}
+
+#[test]
+fn the_wheel_zooms_towards_the_cursor_within_the_limits() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let under = renderer.to_world(600, 150);
+    renderer.wheel(600, 150, -200.0, false);
+    assert!(renderer.camera.get_zoom() < 300.0);
+    assert_near(
+        &[renderer.to_world(600, 150).0, renderer.to_world(600, 150).1],
+        &[under.0, under.1],
+    );
+    // pinching zooms faster for the same scroll
+    let zoom = renderer.camera.get_zoom();
+    renderer.wheel(200, 500, 20.0, true);
+    assert_near(&[renderer.camera.get_zoom()], &[zoom * 0.2_f32.exp()]);
+
+    renderer.set_zoom_limits(50.0, 400.0).unwrap();
+    renderer.wheel(0, 0, 10_000.0, false);
+    assert_eq!(renderer.camera.get_zoom(), 400.0);
+    let under = renderer.to_world(100, 100);
+    renderer.wheel(100, 100, -10_000.0, false);
+    assert_eq!(renderer.camera.get_zoom(), 50.0);
+    assert_near(
+        &[renderer.to_world(100, 100).0, renderer.to_world(100, 100).1],
+        &[under.0, under.1],
+    );
+    // the limits hold for ctrl + drag too
+    renderer.mouse_move(100, 100, 0.0, 100.0, true);
+    assert_eq!(renderer.camera.get_zoom(), 50.0);
+    assert!(renderer.set_zoom_limits(10.0, 5.0).is_err());
+    assert_eq!(renderer.get_zoom_limits(), (50.0, 400.0));
+}
+
+#[test]
+fn held_keys_and_released_pans_move_the_camera_every_frame() {
+    let (_headless, mut renderer) = headless_renderer();
+    let x = |renderer: &Renderer| renderer.camera.get_position()[0];
+    renderer.key_down("d", false);
+    renderer.update(0.1);
+    // 20 pixels in, speeding up
+    assert_near(&[x(&renderer)], &[15.0]);
+    renderer.update(0.1);
+    assert!((x(&renderer) - 60.0).abs() < 0.01);
+    renderer.key_up("d");
+    renderer.update(0.1);
+    let glided = x(&renderer);
+    assert!(glided > 60.0);
+    renderer.update(10.0);
+    renderer.update(0.1);
+    let stopped = x(&renderer);
+    renderer.update(0.1);
+    assert_eq!(x(&renderer), stopped);
+    renderer.key_down("d", false);
+    renderer.release_keys();
+    renderer.update(0.1);
+    assert_eq!(x(&renderer), stopped);
+
+    // a flicked pan keeps going, until the next pan
+    renderer.pan(-10.0, 0.0);
+    renderer.update(0.02);
+    renderer.pan(-10.0, 0.0);
+    renderer.pan_end();
+    let released = x(&renderer);
+    assert_near(&[released], &[stopped + 15.0]);
+    renderer.update(0.02);
+    assert!(x(&renderer) > released + 5.0);
+    renderer.pan(0.0, 0.0);
+    let caught = x(&renderer);
+    renderer.update(0.02);
+    assert_eq!(x(&renderer), caught);
+
+    renderer.set_inertia(false);
+    renderer.pan(-10.0, 0.0);
+    renderer.update(0.02);
+    renderer.pan_end();
+    renderer.update(0.02);
+    assert_near(&[x(&renderer)], &[caught + 7.5]);
+    let params = MotionParams {
+        damping: 0.0,
+        ..MotionParams::default()
+    };
+    assert!(renderer.set_motion_params(params).is_err());
+}
+
+#[test]
+fn the_camera_flies_to_fit_elements_until_the_user_takes_over() {
+    let (_headless, mut renderer) = headless_renderer_with_image(100, 50);
+    let area = renderer
+        .create_area(Polygon::rectangle(0.0, -10.0, 40.0, 20.0))
+        .unwrap();
+    renderer.fit_to_element(area, Easing::EaseInOut).unwrap();
+    assert!(renderer.is_flying());
+    // the camera has not moved yet, halfway it is on its way
+    assert_eq!(renderer.camera.get_zoom(), 300.0);
+    renderer.update(0.3);
+    let zoom = renderer.camera.get_zoom();
+    assert!(zoom < 300.0 && zoom > 24.0);
+    renderer.update(0.3);
+    assert!(!renderer.is_flying());
+    // the area and a tenth of it on each side fill the width of the canvas
+    assert_near(&[renderer.camera.get_zoom()], &[24.0]);
+    assert_near(&[renderer.to_world(0, 300).0], &[-4.0]);
+    assert_near(&[renderer.to_world(800, 300).0], &[44.0]);
+
+    // a wheel scroll takes the camera back
+    renderer
+        .fit_to_element(renderer.get_scene().get_plane().unwrap(), Easing::Linear)
+        .unwrap();
+    renderer.update(0.1);
+    renderer.wheel(400, 300, 10.0, false);
+    assert!(!renderer.is_flying());
+    let camera = renderer.camera.clone();
+    renderer.update(0.1);
+    assert_eq!(renderer.camera.get_zoom(), camera.get_zoom());
+    // so do the held keys
+    renderer.fly_to(0.0, 0.0, 100.0, Easing::EaseOut).unwrap();
+    renderer.key_down("w", false);
+    renderer.update(0.1);
+    assert!(!renderer.is_flying());
+    renderer.key_up("w");
+
+    // in 3D the camera faces the element
+    renderer.set_render_mode(RenderMode::M3D);
+    renderer.fit_to_element(area, Easing::EaseInOut).unwrap();
+    renderer.update(1.0);
+    assert_near(&[renderer.camera.get_pitch()], &[0.0]);
+    assert_near(&[renderer.to_world(0, 300).0], &[-4.0]);
+    assert_near(&[renderer.to_world(800, 300).0], &[44.0]);
+    // and orbits around it
+    renderer.mouse_move(400, 300, 120.0, -80.0, true);
+    assert!(renderer.camera.get_pitch() != 0.0);
+    let (x, y) = renderer.to_world(400, 300);
+    assert_near(&[x, y], &[20.0, 0.0]);
+    renderer.wheel(400, 300, 200.0, false);
+    let (x, y) = renderer.to_world(400, 300);
+    assert_near(&[x, y], &[20.0, 0.0]);
+    assert!(renderer
+        .fit_to_element(ElementId(99), Easing::Linear)
+        .is_err());
+    assert!(renderer.fly_to(0.0, 0.0, -1.0, Easing::Linear).is_err());
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeSet;
+
+/// Keys held down, the camera polls it every frame instead of moving on the
+/// repeated key events.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    held: BTreeSet<String>,
+}
+
+impl InputState {
+    pub fn press(&mut self, key: &str) {
+        self.held.insert(key.to_lowercase());
+    }
+    pub fn release(&mut self, key: &str) {
+        self.held.remove(&key.to_lowercase());
+    }
+    /// Forgets every key, their release is lost once the canvas loses focus.
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+    pub fn is_held(&self, key: &str) -> bool {
+        self.held.contains(&key.to_lowercase())
+    }
+    /// Direction the held keys push the view in, like a drag: wasd or the
+    /// arrows move the camera, so the plane goes the other way.
+    pub fn get_thrust(&self) -> (f32, f32) {
+        let axis = |negative: [&str; 2], positive: [&str; 2]| {
+            let held = |keys: [&str; 2]| keys.iter().any(|key| self.is_held(key));
+            held(positive) as i32 as f32 - held(negative) as i32 as f32
+        };
+        (
+            axis(["d", "arrowright"], ["a", "arrowleft"]),
+            axis(["s", "arrowdown"], ["w", "arrowup"]),
+        )
+    }
+}
+
+/// How the view speeds up and slows down, in canvas pixels and seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionParams {
+    pub acceleration: f32,
+    pub max_speed: f32,
+    /// Fraction of the speed lost per second is `1 - e^-damping`.
+    pub damping: f32,
+}
+
+impl Default for MotionParams {
+    fn default() -> Self {
+        MotionParams {
+            acceleration: 4000.0,
+            max_speed: 800.0,
+            damping: 8.0,
+        }
+    }
+}
+
+impl MotionParams {
+    pub fn check(&self) -> Result<(), String> {
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        if !positive(self.acceleration) || !positive(self.max_speed) || !positive(self.damping) {
+            return Err(
+                "the camera motion needs a positive acceleration, speed and damping".into(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Velocity of the view in pixels per second, along a drag.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Motion {
+    pub velocity: (f32, f32),
+}
+
+impl Motion {
+    /// Below it the view stops.
+    const MIN_SPEED: f32 = 1.0;
+
+    pub fn is_still(&self) -> bool {
+        self.velocity == (0.0, 0.0)
+    }
+}
+
+/// Advances `motion` by `dt` seconds under `thrust`, between -1 and 1 on
+/// each axis. Axes without thrust are damped, so a flick keeps gliding for a
+/// while. Gives the new motion and the pixels the view moved.
+pub fn step(
+    motion: Motion,
+    thrust: (f32, f32),
+    dt: f32,
+    params: &MotionParams,
+) -> (Motion, (f32, f32)) {
+    if dt <= 0.0 {
+        return (motion, (0.0, 0.0));
+    }
+    let decay = (-params.damping * dt).exp();
+    let axis = |velocity: f32, thrust: f32| {
+        if thrust != 0.0 {
+            velocity + thrust * params.acceleration * dt
+        } else {
+            velocity * decay
+        }
+    };
+    let (vx, vy) = motion.velocity;
+    let mut velocity = (axis(vx, thrust.0), axis(vy, thrust.1));
+    let speed = velocity.0.hypot(velocity.1);
+    if speed > params.max_speed {
+        let scale = params.max_speed / speed;
+        velocity = (velocity.0 * scale, velocity.1 * scale);
+    } else if speed < Motion::MIN_SPEED && thrust == (0.0, 0.0) {
+        velocity = (0.0, 0.0);
+    }
+    // the mean velocity over the step keeps the distance independent of dt
+    let moved = ((vx + velocity.0) * 0.5 * dt, (vy + velocity.1) * 0.5 * dt);
+    (Motion { velocity }, moved)
+}
+
+#[cfg(test)]
+fn travel(thrust: (f32, f32), seconds: f32, fps: f32, params: &MotionParams) -> (Motion, f32) {
+    let (mut motion, mut distance) = (Motion::default(), 0.0);
+    for frame in 0..(seconds * fps) as usize {
+        // the keys are let go halfway through
+        let thrust = if (frame as f32) < seconds * fps * 0.5 {
+            thrust
+        } else {
+            (0.0, 0.0)
+        };
+        let (next, moved) = step(motion, thrust, 1.0 / fps, params);
+        motion = next;
+        distance += moved.0;
+    }
+    (motion, distance)
+}
+
+#[test]
+fn held_keys_push_the_view_until_they_are_released() {
+    let mut input = InputState::default();
+    input.press("d");
+    input.press("ArrowUp");
+    assert_eq!(input.get_thrust(), (-1.0, 1.0));
+    input.press("A");
+    input.release("arrowup");
+    assert_eq!(input.get_thrust(), (0.0, 0.0));
+    input.clear();
+    assert!(!input.is_held("d"));
+
+    let params = MotionParams::default();
+    // speeding up, capped at the top speed
+    let (motion, moved) = step(Motion::default(), (1.0, 0.0), 0.1, &params);
+    assert_eq!(motion.velocity, (400.0, 0.0));
+    assert_eq!(moved, (20.0, 0.0));
+    let (motion, _) = step(motion, (1.0, -1.0), 1.0, &params);
+    assert!((motion.velocity.0.hypot(motion.velocity.1) - 800.0).abs() < 1e-3);
+    // then gliding to a stop
+    let (glide, _) = step(motion, (0.0, 0.0), 0.5, &params);
+    assert!(glide.velocity.0 < motion.velocity.0 * 0.02 && glide.velocity.0 > 0.0);
+    let (glide, _) = step(glide, (0.0, 0.0), 5.0, &params);
+    assert!(glide.is_still());
+    assert_eq!(step(motion, (0.0, 0.0), 0.0, &params), (motion, (0.0, 0.0)));
+}
+
+#[test]
+fn motion_does_not_depend_on_the_frame_rate() {
+    let params = MotionParams::default();
+    let (slow, slow_distance) = travel((1.0, 0.0), 2.0, 20.0, &params);
+    let (fast, fast_distance) = travel((1.0, 0.0), 2.0, 144.0, &params);
+    assert!(slow.is_still() && fast.is_still());
+    assert!(fast_distance > 500.0);
+    assert!((slow_distance - fast_distance).abs() < fast_distance * 0.05);
+}
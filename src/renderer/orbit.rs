@@ -0,0 +1,246 @@
+use super::super::alg::{utils, Vec4};
+use super::camera::Camera;
+
+/// Turns drags into a camera circling a target point. The camera stays
+/// `distance` away from the target, `azimuth` turns it around the y axis and
+/// `polar` is its angle from the y axis, both in degrees: at 0 and 90 it
+/// looks down -z at the target, like the 2D view.
+#[derive(Clone, Debug)]
+pub struct OrbitController {
+    target: Vec4,
+    distance: f32,
+    azimuth: f32,
+    polar: f32,
+    min_distance: f32,
+    max_distance: f32,
+    min_polar: f32,
+    max_polar: f32,
+}
+
+impl OrbitController {
+    /// Degrees turned by a pixel of drag.
+    const ROTATE_SPEED: f32 = 0.25;
+
+    pub fn new(target: Vec4, distance: f32) -> Self {
+        let mut orbit = OrbitController {
+            target,
+            distance,
+            azimuth: 0.0,
+            polar: 90.0,
+            min_distance: 1.0,
+            max_distance: 900.0,
+            // the poles would flip the camera
+            min_polar: 5.0,
+            max_polar: 175.0,
+        };
+        orbit.clamp();
+        orbit
+    }
+    #[allow(dead_code)]
+    pub fn get_target(&self) -> Vec4 {
+        self.target
+    }
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
+    #[allow(dead_code)]
+    pub fn get_azimuth(&self) -> f32 {
+        self.azimuth
+    }
+    #[allow(dead_code)]
+    pub fn get_polar(&self) -> f32 {
+        self.polar
+    }
+    pub fn set_distance_limits(&mut self, min: f32, max: f32) -> Result<(), String> {
+        if min.is_nan() || min <= 0.0 || max.is_nan() || max < min {
+            return Err(format!("invalid distance limits {} and {}", min, max));
+        }
+        self.min_distance = min;
+        self.max_distance = max;
+        self.clamp();
+        Ok(())
+    }
+    pub fn set_polar_limits(&mut self, min: f32, max: f32) -> Result<(), String> {
+        if !(0.0..=180.0).contains(&min) || !(min..=180.0).contains(&max) {
+            return Err(format!("invalid polar limits {} and {}", min, max));
+        }
+        self.min_polar = min;
+        self.max_polar = max;
+        self.clamp();
+        Ok(())
+    }
+    fn clamp(&mut self) {
+        self.distance = self.distance.clamp(self.min_distance, self.max_distance);
+        self.polar = self.polar.clamp(self.min_polar, self.max_polar);
+    }
+    /// Takes over a camera moved by other means, the new target is straight
+    /// ahead of it at `distance`.
+    pub fn follow(&mut self, camera: &Camera, distance: f32) {
+        self.distance = distance.clamp(self.min_distance, self.max_distance);
+        let (pitch, yaw) = (camera.get_pitch(), camera.get_yaw());
+        let back = Self::direction(pitch, yaw);
+        self.target = camera.get_position() - back.mul(self.distance);
+        self.azimuth = yaw;
+        self.polar = 90.0 - pitch;
+    }
+    /// From the target to a camera with this pitch and yaw.
+    fn direction(pitch: f32, yaw: f32) -> Vec4 {
+        let (pitch, yaw) = (utils::to_radians(pitch), utils::to_radians(yaw));
+        Vec4([
+            yaw.sin() * pitch.cos(),
+            pitch.sin(),
+            yaw.cos() * pitch.cos(),
+            0.0,
+        ])
+    }
+    /// Circles the target by a drag of `(dx, dy)` pixels.
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        self.azimuth = (self.azimuth - dx * Self::ROTATE_SPEED).rem_euclid(360.0);
+        self.polar -= dy * Self::ROTATE_SPEED;
+        self.clamp();
+    }
+    /// Multiplies the distance to the target.
+    pub fn dolly(&mut self, factor: f32) {
+        if factor > 0.0 {
+            self.distance *= factor;
+            self.clamp();
+        }
+    }
+    /// Slides the target across the view so it follows a drag of `(dx, dy)`
+    /// pixels, `pixel_size` is a pixel in world units at the target.
+    pub fn pan(&mut self, dx: f32, dy: f32, pixel_size: f32) {
+        let pitch = utils::to_radians(90.0 - self.polar);
+        let yaw = utils::to_radians(self.azimuth);
+        let right = Vec4([yaw.cos(), 0.0, -yaw.sin(), 0.0]);
+        let up = Vec4([
+            -yaw.sin() * pitch.sin(),
+            pitch.cos(),
+            -yaw.cos() * pitch.sin(),
+            0.0,
+        ]);
+        self.target = self.target - right.mul(dx * pixel_size) + up.mul(dy * pixel_size);
+    }
+    /// Camera looking at the target, `zoom` is kept for the 2D view.
+    pub fn get_camera(&self, zoom: f32) -> Camera {
+        let pitch = 90.0 - self.polar;
+        let back = Self::direction(pitch, self.azimuth);
+        let position = self.target + back.mul(self.distance);
+        Camera::new(position, pitch, self.azimuth, 0.0, zoom)
+    }
+}
+
+/// View matrix of a camera at `eye` looking at `target` with y up.
+#[cfg(test)]
+fn look_at(eye: [f32; 3], target: [f32; 3]) -> super::super::alg::Mat4 {
+    let sub = |a: [f32; 3], b: [f32; 3]| [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    let cross = |a: [f32; 3], b: [f32; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let normalize = |a: [f32; 3]| {
+        let length = dot(a, a).sqrt();
+        [a[0] / length, a[1] / length, a[2] / length]
+    };
+    let back = normalize(sub(eye, target));
+    let right = normalize(cross([0.0, 1.0, 0.0], back));
+    let up = cross(back, right);
+    super::super::alg::Mat4([
+        right[0],
+        right[1],
+        right[2],
+        -dot(right, eye),
+        up[0],
+        up[1],
+        up[2],
+        -dot(up, eye),
+        back[0],
+        back[1],
+        back[2],
+        -dot(back, eye),
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    ])
+}
+
+#[cfg(test)]
+fn assert_view(camera: &Camera, expected: super::super::alg::Mat4) {
+    let view = camera.get_view_matrix();
+    let near = view
+        .data()
+        .iter()
+        .zip(expected.data())
+        .all(|(a, b)| (a - b).abs() < 1e-4);
+    assert!(near, "{}\nis not\n{}", view, expected);
+}
+
+#[test]
+fn orbits_give_the_view_matrices_of_a_camera_circling_the_target() {
+    let mut orbit = OrbitController::new(Vec4([1.0, 2.0, 0.0, 1.0]), 10.0);
+    // straight in front of the target, like the 2D view
+    assert_view(
+        &orbit.get_camera(1.0),
+        look_at([1.0, 2.0, 10.0], [1.0, 2.0, 0.0]),
+    );
+
+    // a quarter turn to the right of the target
+    orbit.rotate(-360.0, 0.0);
+    assert_eq!(orbit.get_azimuth(), 90.0);
+    assert_view(
+        &orbit.get_camera(1.0),
+        look_at([11.0, 2.0, 0.0], [1.0, 2.0, 0.0]),
+    );
+
+    // then 45 degrees above it, twice as far
+    orbit.rotate(0.0, 180.0);
+    orbit.dolly(2.0);
+    let side = 20.0 * std::f32::consts::FRAC_1_SQRT_2;
+    assert_view(
+        &orbit.get_camera(1.0),
+        look_at([1.0 + side, 2.0 + side, 0.0], [1.0, 2.0, 0.0]),
+    );
+
+    // the limits hold
+    orbit.rotate(0.0, 1000.0);
+    assert_eq!(orbit.get_polar(), 5.0);
+    orbit.dolly(1000.0);
+    assert_eq!(orbit.get_distance(), 900.0);
+    orbit.set_distance_limits(2.0, 50.0).unwrap();
+    assert_eq!(orbit.get_distance(), 50.0);
+    orbit.set_polar_limits(60.0, 120.0).unwrap();
+    assert_eq!(orbit.get_polar(), 60.0);
+    assert!(orbit.set_distance_limits(0.0, 50.0).is_err());
+    assert!(orbit.set_polar_limits(120.0, 60.0).is_err());
+}
+
+#[test]
+fn pans_keep_the_target_under_the_cursor() {
+    let mut orbit = OrbitController::new(Vec4([0.0, 0.0, 0.0, 1.0]), 10.0);
+    orbit.rotate(-120.0, 60.0);
+    let camera = orbit.get_camera(1.0);
+    let view = camera.get_view_matrix();
+    let before = view.mul(&orbit.get_target());
+    // dragging the plane right and down takes the target left and up
+    orbit.pan(4.0, 2.0, 0.5);
+    let after = view.mul(&orbit.get_target());
+    let moved = [
+        after[0] - before[0],
+        after[1] - before[1],
+        after[2] - before[2],
+    ];
+    assert!((moved[0] + 2.0).abs() < 1e-4 && (moved[1] - 1.0).abs() < 1e-4);
+    assert!(moved[2].abs() < 1e-4);
+
+    // following a camera gives it back
+    let mut other = OrbitController::new(Vec4([5.0, 5.0, 5.0, 1.0]), 10.0);
+    other.follow(&orbit.get_camera(1.0), 10.0);
+    assert_view(
+        &other.get_camera(1.0),
+        orbit.get_camera(1.0).get_view_matrix(),
+    );
+}
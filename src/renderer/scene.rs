@@ -0,0 +1,481 @@
+use super::backend::GraphicsBackend;
+use super::element::{Element, ElementKind, Rectangle};
+use super::spatial::SpatialIndex;
+use crate::alg::Polygon;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+/// Stable handle to an element of a `Scene`. Ids are never reused, so a
+/// handle to a removed element simply stops resolving.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ElementId(pub u32);
+
+/// Arena owning every element of the annotation tree. Elements refer to
+/// their children by `ElementId`, kept in id order so newer elements lie
+/// over older ones. Areas and points are indexed by their bounds, their
+/// rectangles must only change through the scene.
+pub struct Scene {
+    elements: BTreeMap<ElementId, Element>,
+    next_id: u32,
+    plane: Option<ElementId>,
+    index: SpatialIndex,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene {
+            elements: BTreeMap::new(),
+            next_id: 0,
+            plane: None,
+            index: SpatialIndex::new(Rectangle::new(0.0, 0.0, 0.0, 0.0)),
+        }
+    }
+    pub fn insert(&mut self, element: Element) -> ElementId {
+        let id = ElementId(self.next_id);
+        self.next_id += 1;
+        self.elements.insert(id, element);
+        id
+    }
+    /// Removes the element together with all of its descendants.
+    pub fn remove(&mut self, id: ElementId) -> Option<Element> {
+        let element = self.elements.remove(&id)?;
+        self.index.remove(id);
+        for child in element.children() {
+            self.remove(*child);
+        }
+        let siblings = element
+            .parent
+            .and_then(|parent| self.elements.get_mut(&parent))
+            .and_then(|parent| parent.children_mut());
+        if let Some(siblings) = siblings {
+            siblings.retain(|sibling| *sibling != id);
+        }
+        if self.plane == Some(id) {
+            self.plane = None;
+        }
+        Some(element)
+    }
+    pub fn get(&self, id: ElementId) -> Option<&Element> {
+        self.elements.get(&id)
+    }
+    pub fn get_mut(&mut self, id: ElementId) -> Option<&mut Element> {
+        self.elements.get_mut(&id)
+    }
+    pub fn contains(&self, id: ElementId) -> bool {
+        self.elements.contains_key(&id)
+    }
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+    pub fn get_plane(&self) -> Option<ElementId> {
+        self.plane
+    }
+    /// Replaces the current plane, dropping every element that belonged to it.
+    pub fn set_plane(&mut self, plane: Element) -> ElementId {
+        debug_assert!(matches!(plane.kind, ElementKind::PLANE(_)));
+        if let Some(old) = self.plane {
+            self.remove(old);
+        }
+        self.index = SpatialIndex::new(plane.rect);
+        let id = self.insert(plane);
+        self.plane = Some(id);
+        id
+    }
+    /// Checks that `child` can be attached under `parent`. Areas go on the
+    /// plane, points go in an area, and a child must lie inside the bounds of
+    /// its parent.
+    pub fn check_child(&self, parent: ElementId, child: &Element) -> Result<(), String> {
+        let parent_element = self
+            .get(parent)
+            .ok_or_else(|| format!("no element with id {}", parent.0))?;
+        match (&parent_element.kind, &child.kind) {
+            (ElementKind::PLANE(_), ElementKind::AREA(_)) => {}
+            (ElementKind::AREA(_), ElementKind::POINT(_)) => {}
+            _ => {
+                return Err(format!(
+                    "element {} cannot hold this kind of element",
+                    parent.0
+                ))
+            }
+        }
+        let inside = match child.kind {
+            ElementKind::POINT(_) => {
+                let (x, y) = child.rect.get_center();
+                parent_element.contains(x, y)
+            }
+            _ => parent_element.rect.contains_rect(&child.rect),
+        };
+        if !inside {
+            return Err(format!("element is outside of element {}", parent.0));
+        }
+        Ok(())
+    }
+    fn attach(&mut self, id: ElementId, parent: ElementId, index: usize, mut child: Element) {
+        child.parent = Some(parent);
+        self.index.insert(id, child.rect);
+        self.elements.insert(id, child);
+        if let Some(children) = self
+            .get_mut(parent)
+            .and_then(|parent| parent.children_mut())
+        {
+            children.insert(index.min(children.len()), id);
+            debug_assert!(children.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+    fn add_child(&mut self, parent: ElementId, child: Element) -> Result<ElementId, String> {
+        self.check_child(parent, &child)?;
+        let id = ElementId(self.next_id);
+        self.next_id += 1;
+        self.attach(id, parent, usize::MAX, child);
+        Ok(id)
+    }
+    /// Puts back a removed element under its old id, at position `index`
+    /// among the children of `parent`.
+    pub fn restore(
+        &mut self,
+        id: ElementId,
+        parent: ElementId,
+        index: usize,
+        child: Element,
+    ) -> Result<(), String> {
+        if self.contains(id) || id.0 >= self.next_id {
+            return Err(format!("element {} cannot be restored", id.0));
+        }
+        if !self.contains(parent) {
+            return Err(format!("no element with id {}", parent.0));
+        }
+        self.attach(id, parent, index, child);
+        Ok(())
+    }
+    /// Position of the element among the children of its parent.
+    pub fn index_of(&self, id: ElementId) -> Option<usize> {
+        let parent = self.get(self.get(id)?.parent?)?;
+        parent.children().iter().position(|child| *child == id)
+    }
+    /// Checks that moving the element keeps it inside its parent.
+    pub fn check_move(&self, id: ElementId, dx: f32, dy: f32) -> Result<(), String> {
+        let element = self
+            .get(id)
+            .ok_or_else(|| format!("no element with id {}", id.0))?;
+        let parent = element
+            .parent
+            .and_then(|parent| self.get(parent))
+            .ok_or_else(|| format!("element {} cannot be moved", id.0))?;
+        let mut rect = element.rect;
+        rect.down_left_corner.0 += dx;
+        rect.down_left_corner.1 += dy;
+        let inside = match element.kind {
+            ElementKind::POINT(_) => {
+                let (x, y) = rect.get_center();
+                parent.contains(x, y)
+            }
+            _ => parent.rect.contains_rect(&rect),
+        };
+        if !inside {
+            return Err(format!("element {} would leave its parent", id.0));
+        }
+        Ok(())
+    }
+    /// Moves the element together with all of its descendants.
+    pub fn translate(&mut self, id: ElementId, dx: f32, dy: f32) {
+        let children = match self.elements.get_mut(&id) {
+            Some(element) => {
+                element.translate(dx, dy);
+                if element.parent.is_some() {
+                    self.index.insert(id, element.rect);
+                }
+                element.children().to_vec()
+            }
+            None => return,
+        };
+        for child in children {
+            self.translate(child, dx, dy);
+        }
+    }
+    /// Gives an area a new outline, see `Element::set_polygon`.
+    pub fn set_polygon(
+        &mut self,
+        id: ElementId,
+        backend: &Rc<dyn GraphicsBackend>,
+        polygon: Polygon,
+    ) -> Result<(), String> {
+        let element = self
+            .elements
+            .get_mut(&id)
+            .ok_or_else(|| format!("no element with id {}", id.0))?;
+        element.set_polygon(backend, polygon)?;
+        self.index.insert(id, element.rect);
+        Ok(())
+    }
+    pub fn add_area(&mut self, area: Element) -> Result<ElementId, String> {
+        let plane = self
+            .plane
+            .ok_or("cannot create area if plane is not created")?;
+        self.add_child(plane, area)
+    }
+    pub fn add_point(&mut self, area: ElementId, point: Element) -> Result<ElementId, String> {
+        self.add_child(area, point)
+    }
+    pub fn get_areas(&self) -> &[ElementId] {
+        match self.plane.and_then(|plane| self.get(plane)) {
+            Some(plane) => plane.children(),
+            None => &[],
+        }
+    }
+    /// Points of `area`, empty if `area` is not an area of this scene.
+    pub fn get_points(&self, area: ElementId) -> &[ElementId] {
+        match self.get(area) {
+            Some(element) if matches!(element.kind, ElementKind::AREA(_)) => element.children(),
+            _ => &[],
+        }
+    }
+    /// Every element but the plane, parents before their children.
+    pub fn annotations(&self) -> Vec<ElementId> {
+        let mut ids = Vec::new();
+        for area in self.get_areas() {
+            ids.push(*area);
+            ids.extend_from_slice(self.get_points(*area));
+        }
+        ids
+    }
+    /// Areas and points whose bounds overlap `rect`, parents before their
+    /// children as in `annotations`.
+    pub fn annotations_in(&self, rect: &Rectangle) -> Vec<ElementId> {
+        let mut ids = self.index.query(rect);
+        ids.sort_by_key(|id| {
+            let element = self.get(*id).unwrap();
+            match element.kind {
+                ElementKind::POINT(_) => (element.parent, true, *id),
+                _ => (Some(*id), false, *id),
+            }
+        });
+        ids
+    }
+    /// Returns the deepest element under `(x, y)`: the topmost point of the
+    /// topmost area there, else that area, else the plane.
+    pub fn select(&self, x: f32, y: f32) -> Option<ElementId> {
+        let plane = self.plane?;
+        if !self.get(plane)?.contains(x, y) {
+            return None;
+        }
+        let hits: Vec<(ElementId, &Element)> = self
+            .index
+            .query(&Rectangle::new(x, y, 0.0, 0.0))
+            .into_iter()
+            .filter_map(|id| Some((id, self.get(id)?)))
+            .filter(|(_, element)| element.contains(x, y))
+            .collect();
+        let topmost = |parent: ElementId| {
+            hits.iter()
+                .filter(|(_, element)| element.parent == Some(parent))
+                .map(|(id, _)| *id)
+                .max()
+        };
+        let area = match topmost(plane) {
+            Some(area) => area,
+            None => return Some(plane),
+        };
+        topmost(area).or(Some(area))
+    }
+    /// `select` without the index, walking the whole tree.
+    #[cfg(test)]
+    pub fn select_linear(&self, x: f32, y: f32) -> Option<ElementId> {
+        self.select_from(self.plane?, x, y)
+    }
+    #[cfg(test)]
+    fn select_from(&self, id: ElementId, x: f32, y: f32) -> Option<ElementId> {
+        let element = self.get(id)?;
+        if !element.contains(x, y) {
+            return None;
+        }
+        let inside = element
+            .children()
+            .iter()
+            .rev()
+            .find_map(|child| self.select_from(*child, x, y));
+        inside.or(Some(id))
+    }
+}
+
+#[test]
+fn removed_ids_stop_resolving_and_are_not_reused() {
+    use super::backend::{GraphicsBackend, HeadlessBackend};
+    use super::{meshes, Vao};
+    use std::rc::Rc;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    let plane = |width: f32, height: f32| {
+        let vao = Vao::new(&backend, meshes::rectangle(width, height)).unwrap();
+        let frame = Vao::new(&backend, meshes::line_rectangle(width, height)).unwrap();
+        Element::plane(vao, frame, width, height)
+    };
+    let mut scene = Scene::new();
+    let first = scene.set_plane(plane(10.0, 10.0));
+    assert_eq!(scene.select(0.0, 0.0), Some(first));
+    assert_eq!(scene.select(20.0, 0.0), None);
+
+    let second = scene.set_plane(plane(40.0, 40.0));
+    assert_ne!(first, second);
+    assert!(scene.get(first).is_none());
+    assert_eq!(scene.len(), 1);
+    assert_eq!(scene.select(15.0, 0.0), Some(second));
+}
+
+#[test]
+fn areas_and_points_nest_under_the_plane() {
+    use super::backend::{GraphicsBackend, HeadlessBackend};
+    use super::{meshes, Vao};
+    use crate::alg::Polygon;
+    use std::rc::Rc;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    let mut scene = Scene::new();
+    let area = |x, y, w, h| Element::area(&backend, Polygon::rectangle(x, y, w, h)).unwrap();
+    assert!(scene.add_area(area(0.0, 0.0, 10.0, 10.0)).is_err());
+
+    let vao = Vao::new(&backend, meshes::rectangle(100.0, 100.0)).unwrap();
+    let frame = Vao::new(&backend, meshes::line_rectangle(100.0, 100.0)).unwrap();
+    let plane = scene.set_plane(Element::plane(vao, frame, 100.0, 100.0));
+
+    let first = scene.add_area(area(-40.0, -40.0, 30.0, 30.0)).unwrap();
+    let second = scene.add_area(area(0.0, 0.0, 40.0, 40.0)).unwrap();
+    assert!(scene.add_area(area(30.0, 30.0, 40.0, 40.0)).is_err());
+    assert_eq!(scene.get_areas(), &[first, second]);
+    assert_eq!(scene.get(first).unwrap().parent, Some(plane));
+
+    let point = Element::point(&backend, 20.0, 20.0, 1.5).unwrap();
+    let point = scene.add_point(second, point).unwrap();
+    let outside = Element::point(&backend, -20.0, 20.0, 0.0).unwrap();
+    assert!(scene.add_point(second, outside).is_err());
+    let nested = Element::point(&backend, -30.0, -30.0, 0.0).unwrap();
+    assert!(scene.add_point(point, nested).is_err());
+    assert_eq!(scene.get_points(second), &[point]);
+    assert_eq!(scene.annotations(), vec![first, second, point]);
+
+    assert_eq!(scene.select(20.0, 20.0), Some(point));
+    assert_eq!(scene.select(10.0, 30.0), Some(second));
+    assert_eq!(scene.select(-30.0, -30.0), Some(first));
+    assert_eq!(scene.select(-45.0, 45.0), Some(plane));
+
+    scene.remove(second);
+    assert_eq!(scene.get_areas(), &[first]);
+    assert!(!scene.contains(point));
+    assert_eq!(scene.select(20.0, 20.0), Some(plane));
+}
+
+#[test]
+fn points_in_the_notch_of_a_polygon_hit_the_plane() {
+    use super::backend::{GraphicsBackend, HeadlessBackend};
+    use super::{meshes, Vao};
+    use crate::alg::Polygon;
+    use std::rc::Rc;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    let mut scene = Scene::new();
+    let vao = Vao::new(&backend, meshes::rectangle(100.0, 100.0)).unwrap();
+    let frame = Vao::new(&backend, meshes::line_rectangle(100.0, 100.0)).unwrap();
+    let plane = scene.set_plane(Element::plane(vao, frame, 100.0, 100.0));
+    let triangle = Polygon::new(vec![(0.0, 0.0), (40.0, 0.0), (0.0, 40.0)]).unwrap();
+    let area = scene
+        .add_area(Element::area(&backend, triangle).unwrap())
+        .unwrap();
+
+    assert_eq!(scene.select(5.0, 5.0), Some(area));
+    assert_eq!(scene.select(35.0, 35.0), Some(plane));
+    let point = Element::point(&backend, 35.0, 35.0, 0.0).unwrap();
+    assert!(scene.add_point(area, point).is_err());
+}
+
+#[cfg(test)]
+fn grid_scene(backend: &Rc<dyn GraphicsBackend>, side: usize) -> Scene {
+    use super::{meshes, Vao};
+
+    let size = side as f32 * 20.0;
+    let mut scene = Scene::new();
+    let vao = Vao::new(backend, meshes::rectangle(size, size)).unwrap();
+    let frame = Vao::new(backend, meshes::line_rectangle(size, size)).unwrap();
+    scene.set_plane(Element::plane(vao, frame, size, size));
+    for i in 0..side * side {
+        let x = (i % side) as f32 * 20.0 - size / 2.0;
+        let y = (i / side) as f32 * 20.0 - size / 2.0;
+        // every other box overlaps its right neighbour
+        let overlaps = i % 2 == 0 && i % side != side - 1;
+        let width = if overlaps { 30.0 } else { 15.0 };
+        let polygon = Polygon::rectangle(x + 2.0, y + 2.0, width, 15.0);
+        let area = scene
+            .add_area(Element::area(backend, polygon).unwrap())
+            .unwrap();
+        if i % 3 == 0 {
+            let point = Element::point(backend, x + 9.0, y + 9.0, 0.0).unwrap();
+            scene.add_point(area, point).unwrap();
+        }
+    }
+    scene
+}
+
+#[test]
+fn the_index_selects_like_the_tree_walk() {
+    use super::backend::HeadlessBackend;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    let mut scene = grid_scene(&backend, 10);
+    let area = scene.get_areas()[44];
+    scene.translate(area, 3.0, 4.0);
+    scene
+        .set_polygon(area, &backend, Polygon::rectangle(0.0, 0.0, 35.0, 35.0))
+        .unwrap();
+    scene.remove(scene.get_areas()[12]);
+    for x in (-110..110).step_by(3) {
+        for y in (-110..110).step_by(3) {
+            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+            assert_eq!(scene.select(x, y), scene.select_linear(x, y), "{} {}", x, y);
+        }
+    }
+    let view = Rectangle::new(-100.0, -100.0, 30.0, 30.0);
+    let visible = scene.annotations_in(&view);
+    let expected: Vec<ElementId> = scene
+        .annotations()
+        .into_iter()
+        .filter(|id| scene.get(*id).unwrap().rect.intersects(&view))
+        .collect();
+    assert_eq!(visible, expected);
+}
+
+/// Run with `cargo test --release -- --ignored --nocapture` to compare the
+/// index with the tree walk.
+#[test]
+#[ignore]
+fn benchmark_select() {
+    use super::backend::HeadlessBackend;
+    use std::time::Instant;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    let scene = grid_scene(&backend, 100);
+    let positions: Vec<(f32, f32)> = (0..10_000)
+        .map(|i| {
+            (
+                (i * 37 % 2000) as f32 - 999.5,
+                (i * 91 % 2000) as f32 - 999.5,
+            )
+        })
+        .collect();
+    let time = |select: &dyn Fn(f32, f32) -> Option<ElementId>| {
+        let start = Instant::now();
+        let hits = positions
+            .iter()
+            .filter(|&&(x, y)| select(x, y).is_some())
+            .count();
+        (start.elapsed(), hits)
+    };
+    let (linear, linear_hits) = time(&|x, y| scene.select_linear(x, y));
+    let (indexed, indexed_hits) = time(&|x, y| scene.select(x, y));
+    assert_eq!(linear_hits, indexed_hits);
+    println!(
+        "{} selects among {} elements: linear {:?}, indexed {:?}",
+        positions.len(),
+        scene.len() - 1,
+        linear,
+        indexed
+    );
+}
@@ -1,27 +1,30 @@
+use super::backend::{GraphicsBackend, ProgramId, ShaderId, ShaderType, UniformLocation};
 use super::loader;
-use crate::renderer::Renderer;
-use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader, WebGlUniformLocation};
+use std::rc::Rc;
 
 pub trait UniformLocations {
-    fn init_locations(&mut self, program: &WebGlProgram) -> Result<(), String>;
-    fn new() -> Self;
+    fn init_locations(&mut self, program: ProgramId) -> Result<(), String>;
+    fn new(backend: &Rc<dyn GraphicsBackend>) -> Self;
 }
 
 pub struct ShaderProgram<T: UniformLocations> {
-    shaders: Vec<WebGlShader>,
-    program: Option<WebGlProgram>,
+    backend: Rc<dyn GraphicsBackend>,
+    shaders: Vec<ShaderId>,
+    program: Option<ProgramId>,
     pub uniforms: T,
 }
 
 impl<T: UniformLocations> ShaderProgram<T> {
-    pub fn new() -> Self {
+    pub fn new(backend: &Rc<dyn GraphicsBackend>) -> Self {
         ShaderProgram {
+            backend: backend.clone(),
             shaders: Vec::new(),
             program: None,
-            uniforms: UniformLocations::new(),
+            uniforms: UniformLocations::new(backend),
         }
     }
     pub async fn from_file_name(
+        backend: &Rc<dyn GraphicsBackend>,
         vertex_shader_file: &str,
         fragment_shader_file: &str,
     ) -> Result<Self, JsValue> {
@@ -35,14 +38,14 @@ impl<T: UniformLocations> ShaderProgram<T> {
         let vert_shader_source = to_utf_str(&vert_shader_source[..])?;
         let fragment_shader_source = to_utf_str(&fragment_shader_source[..])?;
 
-        let mut program = Self::new();
+        let mut program = Self::new(backend);
         program.add_shader(
-            WebGl2RenderingContext::VERTEX_SHADER,
+            ShaderType::Vertex,
             &vert_shader_source,
             Some(vertex_shader_file),
         )?;
         program.add_shader(
-            WebGl2RenderingContext::FRAGMENT_SHADER,
+            ShaderType::Fragment,
             &fragment_shader_source,
             Some(fragment_shader_file),
         )?;
@@ -50,102 +53,87 @@ impl<T: UniformLocations> ShaderProgram<T> {
         Ok(program)
     }
     #[allow(dead_code)]
-    pub fn get_program(&self) -> &WebGlProgram {
-        self.program.as_ref().unwrap()
+    pub fn from_source(
+        backend: &Rc<dyn GraphicsBackend>,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Result<Self, String> {
+        let mut program = Self::new(backend);
+        program.add_shader(ShaderType::Vertex, vertex_shader_source, None)?;
+        program.add_shader(ShaderType::Fragment, fragment_shader_source, None)?;
+        program.link_program()?;
+        Ok(program)
+    }
+    #[allow(dead_code)]
+    pub fn get_program(&self) -> ProgramId {
+        self.program.unwrap()
     }
     pub fn add_shader(
         &mut self,
-        shader_type: u32,
+        shader_type: ShaderType,
         source: &str,
         file_name: Option<&str>,
     ) -> Result<(), String> {
-        let context = &Renderer::get_instance().get_context_instance();
-        let shader = context
-            .create_shader(shader_type)
-            .ok_or_else(|| "Unable to create a shader object".to_string())?;
-
-        context.shader_source(&shader, source);
-        context.compile_shader(&shader);
-
-        if context
-            .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
-            .as_bool()
-            .unwrap_or(false)
-        {
-            self.shaders.push(shader);
-            Ok(())
-        } else {
-            let compile_error = context
-                .get_shader_info_log(&shader)
-                .unwrap_or_else(|| "Unknown error creating shader".to_string());
-            Err(format!(
-                "Compile error: {}\n source {}",
-                compile_error,
-                file_name.unwrap_or("")
-            ))
-        }
+        let shader = self
+            .backend
+            .create_shader(shader_type, source)
+            .map_err(|compile_error| {
+                format!(
+                    "Compile error: {}\n source {}",
+                    compile_error,
+                    file_name.unwrap_or("")
+                )
+            })?;
+        self.shaders.push(shader);
+        Ok(())
     }
 
     pub fn use_program(&self) {
-        let context = Renderer::get_instance().get_context_instance();
-        context.use_program(self.program.as_ref());
+        self.backend.use_program(self.program);
     }
 
     pub fn link_program(&mut self) -> Result<(), String> {
-        let context = Renderer::get_instance().get_context_instance();
-        let program = context
-            .create_program()
-            .ok_or_else(|| "Unable to create the program".to_string())?;
-        for shader in &self.shaders {
-            context.attach_shader(&program, &shader)
-        }
-        context.link_program(&program);
-
-        if context
-            .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
-            .as_bool()
-            .unwrap_or(false)
-        {
-            self.program = Some(program);
-            self.uniforms.init_locations(self.program.as_ref().unwrap())
-        } else {
-            Err(context
-                .get_program_info_log(&program)
-                .unwrap_or_else(|| "Unknown error creating the program".to_string()))
-        }
+        let program = self.backend.create_program(&self.shaders)?;
+        self.program = Some(program);
+        self.uniforms.init_locations(program)
     }
 }
 
 pub struct StdUniforms {
-    transformation_matrix_location: Option<WebGlUniformLocation>,
-    view_matrix_location: Option<WebGlUniformLocation>,
-    projection_matrix_location: Option<WebGlUniformLocation>,
+    backend: Rc<dyn GraphicsBackend>,
+    transformation_matrix_location: Option<UniformLocation>,
+    view_matrix_location: Option<UniformLocation>,
+    projection_matrix_location: Option<UniformLocation>,
+    color_location: Option<UniformLocation>,
+    stroke_location: Option<UniformLocation>,
+    dash_location: Option<UniformLocation>,
 }
 impl UniformLocations for StdUniforms {
-    fn new() -> Self {
+    fn new(backend: &Rc<dyn GraphicsBackend>) -> Self {
         StdUniforms {
+            backend: backend.clone(),
             transformation_matrix_location: None,
             view_matrix_location: None,
             projection_matrix_location: None,
+            color_location: None,
+            stroke_location: None,
+            dash_location: None,
         }
     }
-    fn init_locations(&mut self, program: &WebGlProgram) -> Result<(), String> {
-        let context = Renderer::get_instance().get_context_instance();
-        self.transformation_matrix_location = Some(
-            context
-                .get_uniform_location(program, "transformationMatrix")
-                .expect(&format!("Unknown {}", "transformationMatrix")),
-        );
-        self.view_matrix_location = Some(
-            context
-                .get_uniform_location(program, "viewMatrix")
-                .expect(&format!("Unknown {}", "viewMatrix")),
-        );
-        self.projection_matrix_location = Some(
-            context
-                .get_uniform_location(program, "projectionMatrix")
-                .expect(&format!("Unknown {}", "projectionMatrix")),
-        );
+    fn init_locations(&mut self, program: ProgramId) -> Result<(), String> {
+        let backend = self.backend.clone();
+        let location = |name: &str| {
+            backend
+                .get_uniform_location(program, name)
+                .ok_or_else(|| format!("Unknown {}", name))
+        };
+        self.transformation_matrix_location = Some(location("transformationMatrix")?);
+        self.view_matrix_location = Some(location("viewMatrix")?);
+        self.projection_matrix_location = Some(location("projectionMatrix")?);
+        // only the line program is colored
+        self.color_location = backend.get_uniform_location(program, "color");
+        self.stroke_location = backend.get_uniform_location(program, "stroke");
+        self.dash_location = backend.get_uniform_location(program, "dash");
         // texture 0
         // context.use_program(Some(program));
         // let sampler_location = Some(
@@ -159,24 +147,30 @@ impl UniformLocations for StdUniforms {
 }
 impl StdUniforms {
     pub fn load_tranformation_matrix(&self, data: &[f32]) {
-        let context = Renderer::get_instance().get_context_instance();
-        context.uniform_matrix4fv_with_f32_array(
-            self.transformation_matrix_location.as_ref(),
-            true,
-            data,
-        );
+        self.backend
+            .uniform_matrix4fv(self.transformation_matrix_location, true, data);
     }
     pub fn load_view_matrix(&self, data: &[f32]) {
-        let context = Renderer::get_instance().get_context_instance();
-        context.uniform_matrix4fv_with_f32_array(self.view_matrix_location.as_ref(), true, data);
+        self.backend
+            .uniform_matrix4fv(self.view_matrix_location, true, data);
     }
     pub fn load_projection_matrix(&self, data: &[f32]) {
-        let context = Renderer::get_instance().get_context_instance();
-        context.uniform_matrix4fv_with_f32_array(
-            self.projection_matrix_location.as_ref(),
-            true,
-            data,
-        );
+        self.backend
+            .uniform_matrix4fv(self.projection_matrix_location, true, data);
+    }
+    pub fn load_color(&self, color: [f32; 4]) {
+        self.backend.uniform4fv(self.color_location, &color);
+    }
+    /// Width, in pixels, of the strokes drawn on a canvas of the given size.
+    pub fn load_stroke(&self, canvas: (u32, u32), width: f32) {
+        let stroke = [canvas.0 as f32, canvas.1 as f32, width, 0.0];
+        self.backend.uniform4fv(self.stroke_location, &stroke);
+    }
+    /// Dash, gap and offset of the strokes in pixels, see
+    /// `StrokeStyle::get_dash`, and the pixels in a unit of their mesh.
+    pub fn load_dash(&self, dash: [f32; 3], scale: f32) {
+        let dash = [dash[0], dash[1], dash[2], scale];
+        self.backend.uniform4fv(self.dash_location, &dash);
     }
 }
 
@@ -204,6 +198,6 @@ pub struct Shader(ShaderProgram<StdUniforms>);
 
 impl Shader {
     pub fn new(program: ShaderProgram<StdUniforms>) -> Self {
-        Self { 0: program }
+        Self(program)
     }
 }
@@ -0,0 +1,163 @@
+use super::element::Rectangle;
+use super::scene::ElementId;
+use std::collections::BTreeMap;
+
+/// Quadtree of element bounds. An element sits in the deepest node whose
+/// quadrant holds its whole rectangle, so a query only visits the nodes it
+/// overlaps. Elements outside of the root stay in the root.
+pub struct SpatialIndex {
+    root: Node,
+    bounds: BTreeMap<ElementId, Rectangle>,
+}
+
+struct Node {
+    rect: Rectangle,
+    depth: u32,
+    items: Vec<(ElementId, Rectangle)>,
+    children: Option<Box<[Node; 4]>>,
+}
+
+impl Node {
+    /// A leaf is split once it holds more elements than this.
+    const MAX_ITEMS: usize = 8;
+    const MAX_DEPTH: u32 = 8;
+
+    fn new(rect: Rectangle, depth: u32) -> Self {
+        Node {
+            rect,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+    fn quadrant(&mut self, rect: &Rectangle) -> Option<&mut Node> {
+        self.children
+            .as_mut()?
+            .iter_mut()
+            .find(|child| child.rect.contains_rect(rect))
+    }
+    fn insert(&mut self, id: ElementId, rect: Rectangle) {
+        if let Some(child) = self.quadrant(&rect) {
+            return child.insert(id, rect);
+        }
+        self.items.push((id, rect));
+        if self.children.is_none()
+            && self.items.len() > Self::MAX_ITEMS
+            && self.depth < Self::MAX_DEPTH
+        {
+            self.split();
+        }
+    }
+    fn split(&mut self) {
+        let (x, y) = self.rect.down_left_corner;
+        let (w, h) = (self.rect.dimensions.0 / 2.0, self.rect.dimensions.1 / 2.0);
+        let quadrant = |x, y| Node::new(Rectangle::new(x, y, w, h), self.depth + 1);
+        self.children = Some(Box::new([
+            quadrant(x, y),
+            quadrant(x + w, y),
+            quadrant(x, y + h),
+            quadrant(x + w, y + h),
+        ]));
+        for (id, rect) in std::mem::take(&mut self.items) {
+            self.insert(id, rect);
+        }
+    }
+    /// Follows the path `insert` took for `rect`.
+    fn remove(&mut self, id: ElementId, rect: &Rectangle) {
+        match self.quadrant(rect) {
+            Some(child) => child.remove(id, rect),
+            None => self.items.retain(|(item, _)| *item != id),
+        }
+    }
+    fn query(&self, rect: &Rectangle, found: &mut Vec<ElementId>) {
+        found.extend(
+            self.items
+                .iter()
+                .filter(|(_, bounds)| bounds.intersects(rect))
+                .map(|(id, _)| *id),
+        );
+        for child in self.children.iter().flat_map(|children| children.iter()) {
+            if child.rect.intersects(rect) {
+                child.query(rect, found);
+            }
+        }
+    }
+}
+
+impl SpatialIndex {
+    /// Index covering `rect`, usually the plane.
+    pub fn new(rect: Rectangle) -> Self {
+        SpatialIndex {
+            root: Node::new(rect, 0),
+            bounds: BTreeMap::new(),
+        }
+    }
+    /// Adds an element, or moves it if it is already indexed.
+    pub fn insert(&mut self, id: ElementId, rect: Rectangle) {
+        self.remove(id);
+        self.bounds.insert(id, rect);
+        self.root.insert(id, rect);
+    }
+    pub fn remove(&mut self, id: ElementId) {
+        if let Some(rect) = self.bounds.remove(&id) {
+            self.root.remove(id, &rect);
+        }
+    }
+    /// Elements whose bounds overlap `rect`, touching borders included, in
+    /// no particular order.
+    pub fn query(&self, rect: &Rectangle) -> Vec<ElementId> {
+        let mut found = Vec::new();
+        self.root.query(rect, &mut found);
+        found
+    }
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+}
+
+#[test]
+fn queries_find_the_overlapping_bounds() {
+    let mut index = SpatialIndex::new(Rectangle::new(-50.0, -50.0, 100.0, 100.0));
+    for i in 0..100 {
+        let (x, y) = ((i % 10) as f32 * 10.0 - 50.0, (i / 10) as f32 * 10.0 - 50.0);
+        index.insert(ElementId(i), Rectangle::new(x + 1.0, y + 1.0, 8.0, 8.0));
+    }
+    // the whole plane, an element outside of it stays findable
+    index.insert(ElementId(100), Rectangle::new(-50.0, -50.0, 100.0, 100.0));
+    index.insert(ElementId(101), Rectangle::new(60.0, 60.0, 5.0, 5.0));
+    assert!(index.root.children.is_some());
+
+    let mut found = index.query(&Rectangle::new(-45.0, -45.0, 0.0, 0.0));
+    found.sort();
+    assert_eq!(found, [ElementId(0), ElementId(100)]);
+    let mut found = index.query(&Rectangle::new(-35.0, -45.0, 10.0, 10.0));
+    found.sort();
+    assert_eq!(
+        found,
+        [
+            ElementId(1),
+            ElementId(2),
+            ElementId(11),
+            ElementId(12),
+            ElementId(100)
+        ]
+    );
+    assert_eq!(
+        index.query(&Rectangle::new(61.0, 61.0, 1.0, 1.0)),
+        [ElementId(101)]
+    );
+
+    // moving and removing update the tree
+    index.insert(ElementId(0), Rectangle::new(31.0, 31.0, 8.0, 8.0));
+    assert_eq!(
+        index.query(&Rectangle::new(-45.0, -45.0, 0.0, 0.0)),
+        [ElementId(100)]
+    );
+    index.remove(ElementId(100));
+    index.remove(ElementId(100));
+    let mut found = index.query(&Rectangle::new(35.0, 35.0, 0.0, 0.0));
+    found.sort();
+    assert_eq!(found, [ElementId(0), ElementId(88)]);
+    assert_eq!(index.len(), 101);
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+/// How an outline is drawn, lengths are in canvas pixels.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StrokeStyle {
+    Solid,
+    /// Dashes `gap` apart. A `speed`, in pixels per second, makes them march
+    /// along the outline.
+    Dashed {
+        dash: f32,
+        gap: f32,
+        #[serde(default)]
+        speed: f32,
+    },
+    /// Square dots as long as the line is wide.
+    Dotted {
+        gap: f32,
+    },
+}
+
+impl StrokeStyle {
+    pub fn check(&self) -> Result<(), String> {
+        let positive = |length: f32| length.is_finite() && length > 0.0;
+        match *self {
+            StrokeStyle::Solid => Ok(()),
+            StrokeStyle::Dashed { dash, gap, speed } => {
+                if !positive(dash) || !positive(gap) {
+                    return Err("dashes and gaps must be longer than zero".into());
+                }
+                if !speed.is_finite() {
+                    return Err("invalid dash speed".into());
+                }
+                Ok(())
+            }
+            StrokeStyle::Dotted { gap } if positive(gap) => Ok(()),
+            StrokeStyle::Dotted { .. } => Err("dots must have a gap".into()),
+        }
+    }
+    /// Dash, gap and offset of the line shader at `time` seconds, no gap
+    /// draws a solid line.
+    pub fn get_dash(&self, line_width: f32, time: f32) -> [f32; 3] {
+        match *self {
+            StrokeStyle::Solid => [1.0, 0.0, 0.0],
+            StrokeStyle::Dashed { dash, gap, speed } => {
+                [dash, gap, (time * speed).rem_euclid(dash + gap)]
+            }
+            StrokeStyle::Dotted { gap } => [line_width, gap, 0.0],
+        }
+    }
+}
+
+/// State of an element that picks the style of its outline, the first that
+/// applies of selected, hovered and locked.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameState {
+    Normal,
+    Hovered,
+    Selected,
+    Locked,
+}
+
+impl FrameState {
+    pub fn from_name(name: &str) -> Result<FrameState, String> {
+        match name {
+            "normal" => Ok(FrameState::Normal),
+            "hovered" => Ok(FrameState::Hovered),
+            "selected" => Ok(FrameState::Selected),
+            "locked" => Ok(FrameState::Locked),
+            _ => Err(format!("unknown frame state {}", name)),
+        }
+    }
+}
+
+/// Outline style of every frame state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyles {
+    normal: StrokeStyle,
+    hovered: StrokeStyle,
+    selected: StrokeStyle,
+    locked: StrokeStyle,
+}
+
+impl Default for StrokeStyles {
+    fn default() -> Self {
+        StrokeStyles {
+            normal: StrokeStyle::Solid,
+            hovered: StrokeStyle::Solid,
+            // marching ants
+            selected: StrokeStyle::Dashed {
+                dash: 8.0,
+                gap: 6.0,
+                speed: 20.0,
+            },
+            locked: StrokeStyle::Dotted { gap: 4.0 },
+        }
+    }
+}
+
+impl StrokeStyles {
+    pub fn get(&self, state: FrameState) -> StrokeStyle {
+        match state {
+            FrameState::Normal => self.normal,
+            FrameState::Hovered => self.hovered,
+            FrameState::Selected => self.selected,
+            FrameState::Locked => self.locked,
+        }
+    }
+    pub fn set(&mut self, state: FrameState, style: StrokeStyle) -> Result<(), String> {
+        style.check()?;
+        let slot = match state {
+            FrameState::Normal => &mut self.normal,
+            FrameState::Hovered => &mut self.hovered,
+            FrameState::Selected => &mut self.selected,
+            FrameState::Locked => &mut self.locked,
+        };
+        *slot = style;
+        Ok(())
+    }
+}
+
+#[test]
+fn stroke_styles_give_the_dashes_of_the_line_shader() {
+    let style: StrokeStyle =
+        serde_json::from_str(r#"{ "type": "dashed", "dash": 6, "gap": 4, "speed": 5 }"#).unwrap();
+    assert_eq!(style.get_dash(2.0, 0.0), [6.0, 4.0, 0.0]);
+    // the offset wraps around the pattern
+    assert_eq!(style.get_dash(2.0, 3.0), [6.0, 4.0, 5.0]);
+    let dotted: StrokeStyle = serde_json::from_str(r#"{ "type": "dotted", "gap": 3 }"#).unwrap();
+    assert_eq!(dotted.get_dash(2.0, 1.0), [2.0, 3.0, 0.0]);
+    assert_eq!(StrokeStyle::Solid.get_dash(2.0, 1.0)[1], 0.0);
+
+    let mut styles = StrokeStyles::default();
+    assert!(styles
+        .set(FrameState::Hovered, StrokeStyle::Dotted { gap: 0.0 })
+        .is_err());
+    assert_eq!(styles.get(FrameState::Hovered), StrokeStyle::Solid);
+    styles.set(FrameState::Hovered, dotted).unwrap();
+    assert_eq!(styles.get(FrameState::Hovered), dotted);
+    assert!(FrameState::from_name("dragged").is_err());
+}
@@ -0,0 +1,211 @@
+use super::texture::Texture;
+use serde::Deserialize;
+
+/// Metrics of a signed distance field font, in the JSON flavour of the
+/// BMFont format written by tools like `msdf-bmfont-xml`. Distances are
+/// stored in the red channel of the atlas, 0.5 lies on the outline.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FontMetrics {
+    pub info: FontInfo,
+    pub common: FontCommon,
+    pub chars: Vec<Glyph>,
+    #[serde(default)]
+    pub kernings: Vec<Kerning>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FontInfo {
+    /// Size the glyphs were rendered at, in atlas pixels.
+    pub size: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FontCommon {
+    pub line_height: f32,
+    /// Distance from the top of a line to the baseline.
+    pub base: f32,
+    pub scale_w: f32,
+    pub scale_h: f32,
+}
+
+/// Place of a character in the atlas, in atlas pixels with y down.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Glyph {
+    pub id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub xoffset: f32,
+    pub yoffset: f32,
+    pub xadvance: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Kerning {
+    pub first: u32,
+    pub second: u32,
+    pub amount: f32,
+}
+
+/// Quad of a laid out glyph. Positions are in ems, the first baseline starts
+/// at the origin and y goes up. Texture coordinates go from the top left
+/// corner of the glyph to the bottom right one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphQuad {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub texture_coords: [f32; 4],
+}
+
+pub struct Font {
+    pub metrics: FontMetrics,
+    pub atlas: Texture,
+}
+
+impl Font {
+    pub fn new(metrics: FontMetrics, atlas: Texture) -> Result<Font, String> {
+        if metrics.info.size <= 0.0 {
+            return Err("the font has no size".into());
+        }
+        if metrics.common.scale_w <= 0.0 || metrics.common.scale_h <= 0.0 {
+            return Err("the font atlas has no size".into());
+        }
+        Ok(Font { metrics, atlas })
+    }
+    pub fn from_json(json: &str, atlas: Texture) -> Result<Font, String> {
+        let metrics = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Font::new(metrics, atlas)
+    }
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        let find = |c: char| self.metrics.chars.iter().find(|glyph| glyph.id == c as u32);
+        // characters missing from the atlas show as a question mark
+        find(c).or_else(|| find('?'))
+    }
+    fn kerning(&self, first: char, second: char) -> f32 {
+        self.metrics
+            .kernings
+            .iter()
+            .find(|kerning| kerning.first == first as u32 && kerning.second == second as u32)
+            .map_or(0.0, |kerning| kerning.amount)
+    }
+    /// Height of a line, in ems.
+    pub fn get_line_height(&self) -> f32 {
+        self.metrics.common.line_height / self.metrics.info.size
+    }
+    /// Lays `text` out in quads, `\n` starts a new line.
+    pub fn layout(&self, text: &str) -> Vec<GlyphQuad> {
+        let size = self.metrics.info.size;
+        let common = &self.metrics.common;
+        let mut quads = Vec::new();
+        for (line, text) in text.split('\n').enumerate() {
+            let baseline = -(line as f32) * self.get_line_height();
+            let mut pen = 0.0;
+            let mut previous = None;
+            for c in text.chars() {
+                let glyph = match self.glyph(c) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+                if let Some(previous) = previous {
+                    pen += self.kerning(previous, c) / size;
+                }
+                previous = Some(c);
+                if glyph.width > 0.0 && glyph.height > 0.0 {
+                    let left = pen + glyph.xoffset / size;
+                    let top = baseline + (common.base - glyph.yoffset) / size;
+                    quads.push(GlyphQuad {
+                        left,
+                        top,
+                        right: left + glyph.width / size,
+                        bottom: baseline + (common.base - glyph.yoffset - glyph.height) / size,
+                        texture_coords: [
+                            glyph.x / common.scale_w,
+                            glyph.y / common.scale_h,
+                            (glyph.x + glyph.width) / common.scale_w,
+                            (glyph.y + glyph.height) / common.scale_h,
+                        ],
+                    });
+                }
+                pen += glyph.xadvance / size;
+            }
+        }
+        quads
+    }
+}
+
+/// Where a text label is anchored and in which units it is sized.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextSpace {
+    /// Canvas pixels, the label keeps its place and size on screen.
+    Screen,
+    /// World units, the label follows the plane under the camera.
+    World,
+}
+
+/// Text placed on the canvas. `(x, y)` is the start of the first baseline
+/// and `size` the height of an em.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLabel {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    pub space: TextSpace,
+    pub color: [f32; 4],
+}
+
+#[cfg(test)]
+pub fn test_metrics() -> FontMetrics {
+    serde_json::from_str(
+        r#"{
+            "info": { "size": 10 },
+            "common": { "lineHeight": 12, "base": 8, "scaleW": 40, "scaleH": 20 },
+            "chars": [
+                { "id": 65, "x": 0, "y": 0, "width": 8, "height": 10,
+                  "xoffset": 0, "yoffset": 0, "xadvance": 7 },
+                { "id": 86, "x": 10, "y": 0, "width": 8, "height": 10,
+                  "xoffset": 1, "yoffset": 0, "xadvance": 7 },
+                { "id": 32, "x": 0, "y": 0, "width": 0, "height": 0,
+                  "xoffset": 0, "yoffset": 0, "xadvance": 5 },
+                { "id": 63, "x": 20, "y": 10, "width": 6, "height": 8,
+                  "xoffset": 0, "yoffset": 2, "xadvance": 6 }
+            ],
+            "kernings": [{ "first": 65, "second": 86, "amount": -2 }]
+        }"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn text_is_laid_out_in_ems_from_the_baseline() {
+    use super::backend::{GraphicsBackend, HeadlessBackend};
+    use std::rc::Rc;
+
+    let backend: Rc<dyn GraphicsBackend> = Rc::new(HeadlessBackend::new(800, 600));
+    let atlas = Texture::from_rgba(&backend, 40, 20, &[0; 40 * 20 * 4]).unwrap();
+    let font = Font::new(test_metrics(), atlas).unwrap();
+    let quads = font.layout("AV A\né");
+    assert_eq!(quads.len(), 4);
+    assert_eq!(
+        quads[0],
+        GlyphQuad {
+            left: 0.0,
+            top: 0.8,
+            right: 0.8,
+            bottom: -0.2,
+            texture_coords: [0.0, 0.0, 0.2, 0.5],
+        }
+    );
+    // kerning pulls the V closer, the space only advances
+    assert!((quads[1].left - 0.6).abs() < 1e-6);
+    assert!((quads[2].left - 1.7).abs() < 1e-6);
+    // the next line starts under the first one, unknown characters are
+    // replaced
+    assert!((quads[3].top - (-1.2 + 0.6)).abs() < 1e-6);
+    assert_eq!(quads[3].texture_coords, [0.5, 0.5, 0.65, 0.9]);
+}
@@ -1,8 +1,9 @@
-use crate::Renderer;
+use super::backend::{GraphicsBackend, TextureId, TextureWrap};
+use std::rc::Rc;
 
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{HtmlImageElement, WebGl2RenderingContext, WebGlTexture};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
 
 #[wasm_bindgen]
 extern "C" {
@@ -11,52 +12,121 @@ extern "C" {
     // fn log(value: &JsValue);
 }
 
+/// Texture on the GPU, deleted with the last `Texture` sharing it.
+struct Handle {
+    backend: Rc<dyn GraphicsBackend>,
+    texture: TextureId,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.backend.delete_texture(self.texture);
+    }
+}
+
+/// RGBA pixels of a loaded image, drawn on a 2D canvas to read them back so
+/// that every backend can upload them.
+fn image_pixels(image: &HtmlImageElement) -> Result<Vec<u8>, JsValue> {
+    let (width, height) = (image.width(), image.height());
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or("no document to read the image")?;
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = canvas
+        .get_context("2d")?
+        .ok_or("no 2d context to read the image")?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    context.draw_image_with_html_image_element(image, 0.0, 0.0)?;
+    let data = context.get_image_data(0.0, 0.0, width as f64, height as f64)?;
+    Ok(data.data().0)
+}
+
+/// Handle to an image on the GPU, clones share it.
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct Texture {
-    texture: Option<WebGlTexture>,
+    backend: Rc<dyn GraphicsBackend>,
+    texture: Rc<Handle>,
+    name: String,
     pub width: f32,
     pub height: f32,
 }
 
 impl Texture {
+    /// Name of the image the texture was loaded from, empty for textures
+    /// built from raw pixels.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
     pub fn aspect_ratio(&self) -> f32 {
         self.width / self.height
     }
 
-    pub async fn new(image_name: &str) -> Result<Texture, JsValue> {
-        let context = Renderer::get_instance().get_context_instance();
-        let texture = context.create_texture().expect("cannot create a texture");
+    pub async fn new(
+        backend: &Rc<dyn GraphicsBackend>,
+        image_name: &str,
+    ) -> Result<Texture, JsValue> {
+        let texture = backend
+            .create_texture()
+            .map_err(|s| JsValue::from_str(&s))?;
+        // freed even when the image does not load
+        let texture = Rc::new(Handle {
+            backend: backend.clone(),
+            texture,
+        });
 
         let image = parseImage(String::from(image_name)).await;
         let image = image.dyn_into::<HtmlImageElement>()?;
 
         let texture = Texture {
-            texture: Some(texture),
+            backend: backend.clone(),
+            texture,
+            name: String::from(image_name),
             width: image.width() as f32,
             height: image.height() as f32,
         };
 
+        let pixels = image_pixels(&image)?;
         texture.bind();
 
-        context.tex_image_2d_with_u32_and_u32_and_html_image_element(
-            WebGl2RenderingContext::TEXTURE_2D,
-            0,
-            WebGl2RenderingContext::RGBA as i32,
-            WebGl2RenderingContext::RGBA,
-            WebGl2RenderingContext::UNSIGNED_BYTE,
-            &image,
-        )?;
-        context.tex_parameteri(
-            WebGl2RenderingContext::TEXTURE_2D,
-            WebGl2RenderingContext::TEXTURE_WRAP_S,
-            WebGl2RenderingContext::CLAMP_TO_EDGE as i32,
-        );
-
-        context.generate_mipmap(WebGl2RenderingContext::TEXTURE_2D);
+        backend
+            .tex_image_rgba(image.width(), image.height(), &pixels)
+            .map_err(|s| JsValue::from_str(&s))?;
+        texture.set_parameters();
         Ok(texture)
     }
+    pub fn from_rgba(
+        backend: &Rc<dyn GraphicsBackend>,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+    ) -> Result<Texture, String> {
+        let texture = Texture {
+            backend: backend.clone(),
+            texture: Rc::new(Handle {
+                backend: backend.clone(),
+                texture: backend.create_texture()?,
+            }),
+            name: String::new(),
+            width: width as f32,
+            height: height as f32,
+        };
+
+        texture.bind();
+
+        backend.tex_image_rgba(width, height, pixels)?;
+        texture.set_parameters();
+        Ok(texture)
+    }
+    fn set_parameters(&self) {
+        self.backend.texture_wrap_s(TextureWrap::ClampToEdge);
+        self.backend.generate_mipmap();
+    }
     pub fn bind(&self) {
-        let context = Renderer::get_instance().get_context_instance();
-        context.bind_texture(WebGl2RenderingContext::TEXTURE_2D, self.texture.as_ref());
+        self.backend.bind_texture(Some(self.texture.texture));
     }
 }
@@ -0,0 +1,202 @@
+use crate::alg::Polygon;
+
+/// What the left mouse button does on the canvas.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tool {
+    Select,
+    Rectangle,
+    Polygon,
+    Point,
+}
+
+impl Tool {
+    pub fn from_name(name: &str) -> Result<Tool, String> {
+        match name {
+            "select" => Ok(Tool::Select),
+            "rectangle" => Ok(Tool::Rectangle),
+            "polygon" => Ok(Tool::Polygon),
+            "point" => Ok(Tool::Point),
+            _ => Err(format!("unknown tool {}", name)),
+        }
+    }
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Tool::Select => "select",
+            Tool::Rectangle => "rectangle",
+            Tool::Polygon => "polygon",
+            Tool::Point => "point",
+        }
+    }
+}
+
+/// Shape being drawn, in world coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sketch {
+    Rectangle {
+        start: (f32, f32),
+        end: (f32, f32),
+    },
+    Polygon {
+        vertices: Vec<(f32, f32)>,
+        cursor: (f32, f32),
+    },
+}
+
+/// Element a tool asks the renderer to create.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Created {
+    Area(Polygon),
+    Point(f32, f32),
+}
+
+/// The active tool and what it is drawing. It only turns pointer events into
+/// shapes, the renderer decides where they go.
+pub struct Tools {
+    tool: Tool,
+    sketch: Option<Sketch>,
+}
+
+impl Tools {
+    pub fn new() -> Self {
+        Tools {
+            tool: Tool::Select,
+            sketch: None,
+        }
+    }
+    pub fn get_tool(&self) -> Tool {
+        self.tool
+    }
+    /// Switching tool drops the shape being drawn.
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.sketch = None;
+    }
+    pub fn get_sketch(&self) -> Option<&Sketch> {
+        self.sketch.as_ref()
+    }
+    pub fn press(&mut self, x: f32, y: f32) -> Option<Created> {
+        match (self.tool, &mut self.sketch) {
+            (Tool::Point, _) => return Some(Created::Point(x, y)),
+            (Tool::Rectangle, _) => {
+                self.sketch = Some(Sketch::Rectangle {
+                    start: (x, y),
+                    end: (x, y),
+                })
+            }
+            (Tool::Polygon, Some(Sketch::Polygon { vertices, cursor })) => {
+                // the clicks of a double click land on the same spot
+                if vertices.last() != Some(&(x, y)) {
+                    vertices.push((x, y));
+                }
+                *cursor = (x, y);
+            }
+            (Tool::Polygon, _) => {
+                self.sketch = Some(Sketch::Polygon {
+                    vertices: vec![(x, y)],
+                    cursor: (x, y),
+                })
+            }
+            (Tool::Select, _) => {}
+        }
+        None
+    }
+    pub fn motion(&mut self, x: f32, y: f32) {
+        match &mut self.sketch {
+            Some(Sketch::Rectangle { end, .. }) => *end = (x, y),
+            Some(Sketch::Polygon { cursor, .. }) => *cursor = (x, y),
+            None => {}
+        }
+    }
+    /// Ends a rectangle. A click without dragging draws nothing.
+    pub fn release(&mut self, x: f32, y: f32) -> Option<Created> {
+        self.motion(x, y);
+        let (start, end) = match self.sketch {
+            Some(Sketch::Rectangle { start, end }) => (start, end),
+            _ => return None,
+        };
+        self.sketch = None;
+        let (width, height) = ((end.0 - start.0).abs(), (end.1 - start.1).abs());
+        if width == 0.0 || height == 0.0 {
+            return None;
+        }
+        let (left, bottom) = (start.0.min(end.0), start.1.min(end.1));
+        Some(Created::Area(Polygon::rectangle(
+            left, bottom, width, height,
+        )))
+    }
+    /// Ends a polygon. An invalid outline is kept, so it can be fixed by
+    /// adding more vertices.
+    pub fn close(&mut self) -> Result<Option<Created>, String> {
+        let vertices = match &self.sketch {
+            Some(Sketch::Polygon { vertices, .. }) => vertices.clone(),
+            _ => return Ok(None),
+        };
+        let polygon = Polygon::new(vertices)?;
+        self.sketch = None;
+        Ok(Some(Created::Area(polygon)))
+    }
+    pub fn cancel(&mut self) -> bool {
+        self.sketch.take().is_some()
+    }
+    /// Outline of the rubber band, the polygon is closed through the cursor.
+    pub fn get_preview(&self) -> Option<Vec<(f32, f32)>> {
+        match self.sketch.as_ref()? {
+            Sketch::Rectangle { start, end } => {
+                Some(vec![*start, (end.0, start.1), *end, (start.0, end.1)])
+            }
+            Sketch::Polygon { vertices, cursor } => {
+                let mut outline = vertices.clone();
+                if outline.last() != Some(cursor) {
+                    outline.push(*cursor);
+                }
+                Some(outline)
+            }
+        }
+    }
+}
+
+#[test]
+fn rectangles_are_dragged_in_any_direction() {
+    let mut tools = Tools::new();
+    tools.set_tool(Tool::Rectangle);
+    assert_eq!(tools.press(10.0, 10.0), None);
+    tools.motion(0.0, 5.0);
+    assert_eq!(
+        tools.get_preview(),
+        Some(vec![(10.0, 10.0), (0.0, 10.0), (0.0, 5.0), (10.0, 5.0)])
+    );
+    assert_eq!(
+        tools.release(-10.0, 0.0),
+        Some(Created::Area(Polygon::rectangle(-10.0, 0.0, 20.0, 10.0)))
+    );
+    assert_eq!(tools.get_sketch(), None);
+
+    tools.press(1.0, 1.0);
+    assert_eq!(tools.release(1.0, 1.0), None);
+}
+
+#[test]
+fn polygons_close_once_valid_and_cancel() {
+    let mut tools = Tools::new();
+    tools.set_tool(Tool::Polygon);
+    tools.press(0.0, 0.0);
+    tools.press(10.0, 0.0);
+    tools.motion(10.0, 10.0);
+    assert_eq!(
+        tools.get_preview(),
+        Some(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)])
+    );
+    assert!(tools.close().is_err());
+    assert!(tools.get_sketch().is_some());
+
+    // a double click adds the last vertex once
+    tools.press(10.0, 10.0);
+    tools.press(10.0, 10.0);
+    let triangle = Polygon::new(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]).unwrap();
+    assert_eq!(tools.close(), Ok(Some(Created::Area(triangle))));
+
+    tools.press(0.0, 0.0);
+    assert!(tools.cancel());
+    assert_eq!(tools.get_preview(), None);
+    assert_eq!(tools.close(), Ok(None));
+}
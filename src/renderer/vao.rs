@@ -1,12 +1,13 @@
+use super::backend::{BufferId, BufferTarget, GraphicsBackend, Primitive, VertexArrayId};
 use super::mesh::Mesh;
-use crate::renderer::Renderer;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
-use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlVertexArrayObject};
 
 #[wasm_bindgen]
 pub struct Vao {
-    _buffers: Vec<WebGlBuffer>,
-    vao: Option<WebGlVertexArrayObject>,
+    backend: Rc<dyn GraphicsBackend>,
+    buffers: Vec<BufferId>,
+    vao: VertexArrayId,
     size: i32,
     mesh: Mesh,
 }
@@ -15,123 +16,134 @@ impl Vao {
     pub fn get_mesh(&self) -> &Mesh {
         &self.mesh
     }
-    pub fn new(mesh: Mesh) -> Result<Vao, String> {
-        let context = Renderer::get_instance().get_context_instance();
-        let mut _buffers: Vec<WebGlBuffer> = Vec::new();
-        let vao = Some(
-            context
-                .create_vertex_array()
-                .ok_or("Could not create vertex array object")?,
-        );
-        context.bind_vertex_array(vao.as_ref());
+    pub fn new(backend: &Rc<dyn GraphicsBackend>, mesh: Mesh) -> Result<Vao, String> {
+        let mut buffers: Vec<BufferId> = Vec::new();
+        let vao = backend.create_vertex_array()?;
+        backend.bind_vertex_array(Some(vao));
 
-        Self::bind_indices(&mut _buffers, mesh.indices.as_slice())?;
-        Self::store_in_attribute_list(&mut _buffers, 0, mesh.vertices.as_slice(), 3)?;
+        Self::bind_indices(backend.as_ref(), &mut buffers, mesh.indices.as_slice())?;
+        Self::store_in_attribute_list(
+            backend.as_ref(),
+            &mut buffers,
+            0,
+            mesh.vertices.as_slice(),
+            3,
+        )?;
         if let Some(ref texture_coords) = mesh.texture_coords {
-            Self::store_in_attribute_list(&mut _buffers, 1, texture_coords.coords.as_slice(), 2)?;
+            Self::store_in_attribute_list(
+                backend.as_ref(),
+                &mut buffers,
+                1,
+                texture_coords.coords.as_slice(),
+                2,
+            )?;
         }
         if let Some(ref normals) = mesh.normals {
-            Self::store_in_attribute_list(&mut _buffers, 2, normals.as_slice(), 3)?;
+            Self::store_in_attribute_list(
+                backend.as_ref(),
+                &mut buffers,
+                2,
+                normals.as_slice(),
+                3,
+            )?;
+        }
+        if let Some(ref neighbours) = mesh.neighbours {
+            Self::store_in_attribute_list(
+                backend.as_ref(),
+                &mut buffers,
+                3,
+                neighbours.as_slice(),
+                4,
+            )?;
+        }
+        if let Some(ref distances) = mesh.distances {
+            Self::store_in_attribute_list(
+                backend.as_ref(),
+                &mut buffers,
+                4,
+                distances.as_slice(),
+                1,
+            )?;
         }
 
         let size = mesh.indices.len() as i32;
 
         Ok(Vao {
-            _buffers,
+            backend: backend.clone(),
+            buffers,
             vao,
             size,
             mesh,
         })
     }
-    pub fn get_vao(&self) -> &WebGlVertexArrayObject {
-        self.vao.as_ref().unwrap()
+    pub fn get_vao(&self) -> VertexArrayId {
+        self.vao
     }
     pub fn get_size(&self) -> i32 {
         self.size
     }
     fn store_in_attribute_list(
-        _buffers: &mut Vec<WebGlBuffer>,
+        backend: &dyn GraphicsBackend,
+        buffers: &mut Vec<BufferId>,
         attribute_number: u32,
         data: &[f32],
         size: i32,
     ) -> Result<(), String> {
-        let context = Renderer::get_instance().get_context_instance();
-        let buffer = context.create_buffer().ok_or("Failed to create buffer")?;
-        context.bind_buffer(WebGl2RenderingContext::ARRAY_BUFFER, Some(&buffer));
-        _buffers.push(buffer);
-
-        unsafe {
-            let positions_array_buf_view = js_sys::Float32Array::view(data);
-
-            context.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ARRAY_BUFFER,
-                &positions_array_buf_view,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
-        };
+        let buffer = backend.create_buffer()?;
+        backend.bind_buffer(BufferTarget::Array, Some(buffer));
+        buffers.push(buffer);
 
-        context.vertex_attrib_pointer_with_i32(
-            attribute_number,
-            size,
-            WebGl2RenderingContext::FLOAT,
-            false,
-            0,
-            0,
-        );
+        backend.buffer_data_f32(BufferTarget::Array, data);
+        backend.vertex_attrib_pointer(attribute_number, size);
         Ok(())
     }
-    fn bind_indices(_buffers: &mut Vec<WebGlBuffer>, data: &[u32]) -> Result<(), String> {
-        let context = Renderer::get_instance().get_context_instance();
-        let index_buffer = context.create_buffer().ok_or("failed to create a buffer")?;
-        context.bind_buffer(
-            WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
-            Some(&index_buffer),
-        );
-        _buffers.push(index_buffer);
-
-        unsafe {
-            let positions_array_buf_view = js_sys::Uint32Array::view(data);
+    fn bind_indices(
+        backend: &dyn GraphicsBackend,
+        buffers: &mut Vec<BufferId>,
+        data: &[u32],
+    ) -> Result<(), String> {
+        let index_buffer = backend.create_buffer()?;
+        backend.bind_buffer(BufferTarget::ElementArray, Some(index_buffer));
+        buffers.push(index_buffer);
 
-            context.buffer_data_with_array_buffer_view(
-                WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER,
-                &positions_array_buf_view,
-                WebGl2RenderingContext::STATIC_DRAW,
-            );
-        };
+        backend.buffer_data_u32(BufferTarget::ElementArray, data);
         Ok(())
     }
     fn bind(&self) {
-        let context = Renderer::get_instance().get_context_instance();
-        context.bind_vertex_array(self.vao.as_ref());
+        let backend = &self.backend;
+        backend.bind_vertex_array(Some(self.vao));
         // position
-        context.enable_vertex_attrib_array(0);
+        backend.enable_vertex_attrib_array(0);
         if let Some(ref texture_coords) = self.mesh.texture_coords {
-            context.enable_vertex_attrib_array(1);
-            context.active_texture(WebGl2RenderingContext::TEXTURE0);
+            backend.enable_vertex_attrib_array(1);
+            backend.active_texture(0);
             texture_coords.texture.bind();
         }
         if self.mesh.normals.is_some() {
-            context.enable_vertex_attrib_array(2);
+            backend.enable_vertex_attrib_array(2);
+        }
+        if self.mesh.neighbours.is_some() {
+            backend.enable_vertex_attrib_array(3);
+        }
+        if self.mesh.distances.is_some() {
+            backend.enable_vertex_attrib_array(4);
         }
     }
     pub fn draw(&self) {
-        let context = Renderer::get_instance().get_context_instance();
         self.bind();
-        context.draw_elements_with_i32(
-            WebGl2RenderingContext::TRIANGLES,
-            self.size,
-            WebGl2RenderingContext::UNSIGNED_INT,
-            0,
-        );
+        self.backend.draw_elements(Primitive::Triangles, self.size);
     }
     pub fn draw_line_loop(&self) {
-        let context = Renderer::get_instance().get_context_instance();
         self.bind();
-        context.draw_elements_with_i32(
-            WebGl2RenderingContext::LINE_LOOP,
-            self.size,
-            WebGl2RenderingContext::UNSIGNED_INT,
-            0,
-        );
+        self.backend.draw_elements(Primitive::LineLoop, self.size);
+    }
+}
+
+impl Drop for Vao {
+    fn drop(&mut self) {
+        self.backend.delete_vertex_array(self.vao);
+        for &buffer in &self.buffers {
+            self.backend.delete_buffer(buffer);
+        }
     }
 }
@@ -0,0 +1,19 @@
+# version 300 es
+
+precision highp float;
+
+in vec2 passTextureCoords;
+
+uniform sampler2D atlas;
+uniform vec4 color;
+
+out vec4 outColor;
+
+void main() {
+    // signed distance in the red channel, 0.5 on the outline
+    float distance = texture(atlas, passTextureCoords).r;
+    // one screen pixel of antialiasing whatever the zoom
+    float smoothing = fwidth(distance);
+    float alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    outColor = vec4(color.rgb, color.a * alpha);
+}